@@ -0,0 +1,86 @@
+//! Record and compare against a baseline of known findings
+//!
+//! To adopt citati on a large, existing bibliography, the current findings can be recorded to a baseline file using `--write-baseline`.  Subsequent runs with `--baseline` then only report findings that are not in the baseline, so that no new problems are introduced while old ones are fixed over time.
+//!
+//! The baseline is a JSON file recording the check, key, and [`fingerprint`](`Finding::fingerprint`) of each finding.  As fingerprints do not depend on location, findings remain in the baseline when unrelated edits move them around the file, but a finding whose offending value changes (e.g., to a different malformatted page range) is considered new.
+//!
+//! ```json
+//! {
+//!   "version": 1,
+//!   "findings": [
+//!     { "check": "pages", "key": "smith2020", "fingerprint": "5e989411487e9af5" }
+//!   ]
+//! }
+//! ```
+
+use crate::{
+    diagnostics::Finding,
+    error::{Error, Result},
+};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashSet, fs};
+
+/// Version of the baseline file format
+const BASELINE_VERSION: u32 = 1;
+
+/// Identifying information about a finding recorded in the baseline
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+struct BaselineEntry {
+    check: String,
+    key: String,
+    fingerprint: String,
+}
+
+impl From<&Finding> for BaselineEntry {
+    fn from(finding: &Finding) -> Self {
+        Self {
+            check: finding.check.name().to_owned(),
+            key: finding.key.clone(),
+            fingerprint: finding.fingerprint(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Baseline {
+    version: u32,
+    findings: Vec<BaselineEntry>,
+}
+
+/// Write the given findings to a baseline file
+///
+/// Entries are sorted so that the file changes as little as possible between runs
+pub fn write_baseline(path: &str, findings: &[Finding]) -> Result<()> {
+    let mut entries: Vec<BaselineEntry> = findings.iter().map(BaselineEntry::from).collect();
+    entries.sort();
+    entries.dedup();
+
+    let baseline = Baseline {
+        version: BASELINE_VERSION,
+        findings: entries,
+    };
+    let json = serde_json::to_string_pretty(&baseline).unwrap();
+    fs::write(path, json + "\n")
+        .map_err(Error::io(format!("Could not write baseline file {path:?}")))
+}
+
+/// Remove any findings that are recorded in the baseline file
+pub fn filter_baseline(path: &str, findings: Vec<Finding>) -> Result<Vec<Finding>> {
+    let src = fs::read_to_string(path)
+        .map_err(Error::io(format!("Could not read baseline file {path:?}")))?;
+    let baseline: Baseline = serde_json::from_str(&src)
+        .map_err(|e| Error::Parse(format!("Could not parse baseline file {path:?}: {e}")))?;
+
+    if baseline.version != BASELINE_VERSION {
+        return Err(Error::Parse(format!(
+            "Unsupported baseline file version {} in {path:?} (expected {BASELINE_VERSION})",
+            baseline.version
+        )));
+    }
+
+    let known: HashSet<BaselineEntry> = baseline.findings.into_iter().collect();
+    Ok(findings
+        .into_iter()
+        .filter(|finding| !known.contains(&BaselineEntry::from(finding)))
+        .collect())
+}
@@ -3,7 +3,10 @@
 //! Gather citations with bib entry and LaTeX citation information (where applicable).  _c.f._ [`hollow`](`super::hollow`).
 
 use super::GatherCitations;
-use crate::source::{bib, latex, Bib, BibCitation, CitationSource, LaTeX, LaTeXCitation, Source};
+use crate::{
+    source::{bib, latex, Bib, CitationSource, DocumentSource, LaTeXCitation, Source},
+    Result,
+};
 use std::collections::HashMap;
 
 // NOTE: the following can be uncommented if/when needed
@@ -36,6 +39,22 @@ use std::collections::HashMap;
 /// Struct containing a collection of citations
 ///
 /// The type of citations in the collection depends on the source of the citations
+///
+/// ```
+/// use citati::{
+///     citations::{gather_citations, Citations},
+///     source::{Bib, CitationSource},
+/// };
+/// # let bib_file = std::env::temp_dir().join("citati-doc-citations.bib");
+/// # std::fs::write(&bib_file, "@book{smith2020, title = {A title}}\n").unwrap();
+/// # let bib_file = bib_file.to_str().unwrap();
+///
+/// let src = CitationSource::from_bib(bib_file);
+/// let bib = gather_citations::<Citations<Bib>>(&src)?;
+/// let citation = bib.get("smith2020").unwrap();
+/// assert_eq!(citation.get("title").as_deref(), Some("A title"));
+/// # Ok::<(), citati::Error>(())
+/// ```
 pub struct Citations<S: Source> {
     data: HashMap<String, S::CitationType>,
 }
@@ -44,18 +63,17 @@ pub struct Citations<S: Source> {
 ///
 /// These implementations typically access the underlying data but provide a useful/convenient API for the `Citations` struct
 impl<S: Source> Citations<S> {
-    // NOTE: the following can be uncommented if/when needed
-    // pub fn iter(&self) -> impl Iterator<Item = &S::CitationType> {
-    //     self.data.values()
-    // }
-    //
-    // pub fn keys(&self) -> impl Iterator<Item = &String> {
-    //     self.data.keys()
-    // }
-    //
-    // pub fn get(&self, key: &String) -> Option<&S::CitationType> {
-    //     self.data.get(key)
-    // }
+    pub fn iter(&self) -> impl Iterator<Item = &S::CitationType> {
+        self.data.values()
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &String> {
+        self.data.keys()
+    }
+
+    pub fn get(&self, key: &str) -> Option<&S::CitationType> {
+        self.data.get(key)
+    }
 
     pub fn list_sorted(&self) -> Vec<&S::CitationType> {
         let mut citations: Vec<(&String, &S::CitationType)> = self.data.iter().collect();
@@ -82,55 +100,61 @@ impl<S: Source> Citations<S> {
     }
 }
 
-/// Implement the `gather` function for `Citations` for LaTeX source code
-impl GatherCitations for Citations<LaTeX> {
-    fn gather(src: &CitationSource) -> Self {
-        // TODO: instead of unwrapping source, write impl on CitationSource for validating both fields
-        let mut lexer = latex::Lexer::from_str(src.latex_file.unwrap());
-        let mut citations = HashMap::<String, Vec<latex::CitationToken>>::new();
-        while let Some(token) = lexer.next_token() {
-            if let latex::Token::Citation(citation) = token {
-                citations
-                    .entry(citation.key.to_owned())
-                    .or_default()
-                    .push(citation);
-            }
-        }
-        let mut data = HashMap::new();
-        for key in citations.keys() {
-            let cite_cmds = citations
-                .get(key)
-                .map_or_else(Vec::new, |v| v.iter().map(|s| s.cite_cmd.clone()).collect());
-            data.insert(
-                key.clone(),
-                LaTeXCitation {
-                    _key: key.to_owned(),
-                    _cite_cmds: cite_cmds,
-                },
-            );
-        }
+/// Collect citation tokens into citations, grouping the commands and locations of each key
+fn from_tokens<S>(tokens: impl Iterator<Item = latex::CitationToken>) -> Citations<S>
+where
+    S: Source<CitationType = LaTeXCitation>,
+{
+    let mut citations = HashMap::<String, Vec<latex::CitationToken>>::new();
+    for citation in tokens {
+        citations
+            .entry(citation.key.to_owned())
+            .or_default()
+            .push(citation);
+    }
+    let mut data = HashMap::new();
+    for (key, tokens) in citations {
+        let cite_cmds = tokens.iter().map(|t| t.cite_cmd.clone()).collect();
+        let refsections = tokens.iter().map(|t| t.refsection).collect();
+        let refsegments = tokens.iter().map(|t| t.refsegment).collect();
+        let locations = tokens.into_iter().map(|t| t.location).collect();
+        data.insert(
+            key.clone(),
+            LaTeXCitation {
+                key,
+                cite_cmds,
+                locations,
+                refsections,
+                refsegments,
+            },
+        );
+    }
+
+    Citations { data }
+}
 
-        Self { data }
+/// Implement the `gather` function for `Citations` for any source from which citations are read as the tokens of a document
+impl<S: DocumentSource> GatherCitations for Citations<S> {
+    fn gather(src: &CitationSource) -> Result<Self> {
+        Ok(from_tokens(S::tokens(src)?.into_iter().filter_map(
+            |token| match token {
+                latex::Token::Citation(citation) => Some(citation),
+                _ => None,
+            },
+        )))
     }
 }
 
 /// Implement the `gather` function for `Citations` for a bibliography
 impl GatherCitations for Citations<Bib> {
-    fn gather(src: &CitationSource) -> Self {
-        // TODO: instead of unwrapping source, write impl on CitationSource for validating the field
-        let bib = bib::parse_bib_from_file(src.bib_file.unwrap());
+    fn gather(src: &CitationSource) -> Result<Self> {
+        let bib = bib::parse_bib_citations_from_str(&src.bib_contents()?, src.bib_path()?)?;
         let mut data = HashMap::new();
-        for entry in bib.iter() {
-            data.insert(
-                entry.key.clone(),
-                BibCitation {
-                    key: entry.key.clone(),
-                    entry: entry.clone(),
-                },
-            );
+        for citation in bib {
+            data.insert(citation.key.clone(), citation);
         }
 
-        Self { data }
+        Ok(Self { data })
     }
 }
 
@@ -3,7 +3,10 @@
 //! Gather citations with shallow/hollow information from various sources.  This is a light wrapper around a hash set containing the keys we are citing from various sources.  _c.f._ [`full`](`super::full`).
 
 use super::gather::GatherCitations;
-use crate::source::{bib, latex, Abstract, Bib, CitationSource, LaTeX, Source};
+use crate::{
+    source::{bib, latex, Abstract, Bib, CitationSource, DocumentSource, Source},
+    Result,
+};
 use std::{collections::HashSet, marker::PhantomData};
 
 /// Collection of citation keys from some specified source
@@ -25,6 +28,12 @@ impl<S: Source> HollowCitations<S> {
     }
 }
 
+impl<S: Source> Default for HollowCitations<S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// Conveninent implementation of construction of `HollowCitations` from an iterable
 impl<I, S: Source> From<I> for HollowCitations<S>
 where
@@ -48,10 +57,18 @@ impl<S: Source> HollowCitations<S> {
         self.data.insert(citation)
     }
 
-    // NOTE: the following can be uncommented if/when needed
-    // pub fn count(&self) -> usize {
-    //     self.data.len()
-    // }
+    pub fn contains(&self, citation: &str) -> bool {
+        self.data.contains(citation)
+    }
+
+    pub fn count(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Add the citations of another `HollowCitations` object, as in a union of the two
+    pub fn extend<R: Source>(&mut self, other: HollowCitations<R>) {
+        self.data.extend(other.data);
+    }
 
     /// Create an `Abstract` `HollowCitations` struct from the set difference of two `HollowCitations` objects
     pub fn difference<R: Source>(&self, other: HollowCitations<R>) -> HollowCitations<Abstract> {
@@ -66,26 +83,22 @@ impl<S: Source> HollowCitations<S> {
     }
 }
 
-/// Implement the `gather` function for `HollowCitations` for LaTeX source code
-impl GatherCitations for HollowCitations<LaTeX> {
-    fn gather(src: &CitationSource) -> Self {
-        // TODO: instead of unwrapping source, write impl on CitationSource for validating the field
-        let mut lexer = latex::Lexer::from_str(src.latex_file.unwrap());
-        let mut citations = HollowCitations::<LaTeX>::new();
-        while let Some(token) = lexer.next_token() {
-            if let latex::Token::Citation(citation) = token {
-                citations.insert(citation.key);
-            }
-        }
-        citations
+/// Implement the `gather` function for `HollowCitations` for any source from which citations are read as the tokens of a document
+impl<S: DocumentSource> GatherCitations for HollowCitations<S> {
+    fn gather(src: &CitationSource) -> Result<Self> {
+        Ok(Self::from(S::tokens(src)?.into_iter().filter_map(
+            |token| match token {
+                latex::Token::Citation(citation) => Some(citation.key),
+                _ => None,
+            },
+        )))
     }
 }
 
 /// Implement the `gather` function for `HollowCitations` for a bibliography
 impl GatherCitations for HollowCitations<Bib> {
-    fn gather(src: &CitationSource) -> Self {
-        // TODO: instead of unwrapping source, write impl on CitationSource for validating the field
-        let bib = bib::parse_bib_from_file(src.bib_file.unwrap());
-        Self::from(bib.keys().map(|s| s.to_string()))
+    fn gather(src: &CitationSource) -> Result<Self> {
+        let bib = bib::parse_bib_from_str(&src.bib_contents()?, src.bib_path()?)?;
+        Ok(Self::from(bib.keys().map(|s| s.to_string())))
     }
 }
@@ -5,7 +5,9 @@
 mod full;
 mod gather;
 mod hollow;
+mod refsections;
 
 pub use full::Citations;
 pub use gather::{gather_citations, GatherCitations};
 pub use hollow::HollowCitations;
+pub use refsections::{Bibliographies, LocalBibliography};
@@ -0,0 +1,162 @@
+//! Gather the bibliography entries available to each refsection
+//!
+//! A biblatex refsection may have bibliography resources of its own (e.g., `\begin{refsection}[chapter2.bib]`), in addition to the global bibliography, which is available to every refsection.  A key cited within a refsection is therefore only defined if it is in the global bibliography or in one of the resources of that refsection, and an entry in a resource local to some refsections is only used if it is cited within one of them.  _c.f._ [`Refsection`](`crate::source::Refsection`).
+//!
+//! Even where an entry is defined, it is only printed if one of the bibliographies printed for the refsection in which it is cited selects it: a bibliography may only print entries cited within one refsegment of the refsection, or those with a keyword or of a type (e.g., `\printbibliography[segment=2]` or `\printbibliography[keyword=primary]`).  _c.f._ [`PrintedBibliography`](`crate::source::latex::PrintedBibliography`).
+
+use super::{gather_citations, Citations, GatherCitations};
+use crate::{
+    source::{latex::PrintedBibliography, Bib, BibCitation, CitationSource, LaTeXCitation},
+    Result,
+};
+use std::path::{Path, PathBuf};
+
+/// Bibliography resource that is local to some refsections
+pub struct LocalBibliography {
+    pub path: PathBuf,
+    pub entries: Citations<Bib>,
+
+    /// Refsections of which the resource is a local bibliography, in the order in which they start
+    pub refsections: Vec<usize>,
+}
+
+impl LocalBibliography {
+    /// Whether the resource is available to the given refsection
+    ///
+    /// Resources of refsection 0 are available to every refsection, like the global bibliography
+    pub fn available_to(&self, refsection: usize) -> bool {
+        self.refsections.contains(&0) || self.refsections.contains(&refsection)
+    }
+
+    /// Whether a citation is of an entry in the resource, within a refsection to which it is available
+    pub fn cited_by(&self, citation: &LaTeXCitation) -> bool {
+        citation
+            .refsections
+            .iter()
+            .any(|refsection| self.available_to(*refsection))
+    }
+}
+
+/// Collection of the global bibliography and any resources local to refsections, with the bibliographies printed from them
+pub struct Bibliographies {
+    pub global: Citations<Bib>,
+    pub local: Vec<LocalBibliography>,
+
+    /// Bibliographies printed by the document, in the order in which they are printed
+    pub printed: Vec<PrintedBibliography>,
+
+    /// Whether the refsegments in which keys are cited are known, which they are not where citations are read from an auxiliary or control file
+    pub refsegments_known: bool,
+}
+
+impl Bibliographies {
+    /// Whether the key is defined in a bibliography available to the given refsection
+    pub fn defines(&self, refsection: usize, key: &str) -> bool {
+        self.entry(refsection, key).is_some()
+    }
+
+    /// Entry of the key in a bibliography available to the given refsection, if any
+    pub fn entry(&self, refsection: usize, key: &str) -> Option<&BibCitation> {
+        self.global.get(key).or_else(|| {
+            self.local
+                .iter()
+                .filter(|bibliography| bibliography.available_to(refsection))
+                .find_map(|bibliography| bibliography.entries.get(key))
+        })
+    }
+
+    /// Whether the entry, cited within the given refsection and refsegment, is printed by any bibliography of the refsection
+    ///
+    /// A refsection for which no bibliography is printed with `\printbibliography` may be printed in ways that we do not read (e.g., with `\bibbysection`), so we assume that it prints every entry cited within it
+    pub fn prints(&self, refsection: usize, refsegment: usize, entry: &BibCitation) -> bool {
+        let mut printed = self
+            .printed
+            .iter()
+            .filter(|printed| printed.refsection == refsection)
+            .peekable();
+        printed.peek().is_none() || printed.any(|printed| self.selects(printed, refsegment, entry))
+    }
+
+    /// Whether the printed bibliography selects the entry, cited within the given refsegment of its refsection
+    fn selects(
+        &self,
+        printed: &PrintedBibliography,
+        refsegment: usize,
+        entry: &BibCitation,
+    ) -> bool {
+        if printed.other_filters {
+            return true;
+        }
+
+        let in_segment = !self.refsegments_known
+            || printed
+                .refsegment
+                .is_none_or(|segment| segment == refsegment);
+        let entry_type = entry.entry_type().to_biblatex().to_string().to_lowercase();
+        let keywords = entry.get("keywords").unwrap_or_default();
+        let has_keyword = |keyword: &String| keywords.split(',').any(|k| k.trim() == keyword);
+        in_segment
+            && printed.entry_type.as_ref().is_none_or(|t| *t == entry_type)
+            && printed
+                .not_entry_type
+                .as_ref()
+                .is_none_or(|t| *t != entry_type)
+            && printed.keyword.as_ref().is_none_or(has_keyword)
+            && !printed.not_keyword.as_ref().is_some_and(has_keyword)
+    }
+}
+
+/// Resolve a path so that the same file given in different ways compares equal
+fn canonical(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Implement the `gather` function for `Bibliographies`, reading local resources from the refsections of the source
+///
+/// Resources that are the global bibliography are not also read as local ones, and those that do not exist are skipped with a warning
+impl GatherCitations for Bibliographies {
+    fn gather(src: &CitationSource) -> Result<Self> {
+        let global = gather_citations::<Citations<Bib>>(src)?;
+        let global_path = canonical(Path::new(src.bib_path()?));
+
+        let mut local: Vec<LocalBibliography> = Vec::new();
+        for refsection in src.refsections()? {
+            for resource in refsection.resources {
+                let path = canonical(&resource);
+                if path == global_path {
+                    continue;
+                }
+
+                if let Some(bibliography) = local
+                    .iter_mut()
+                    .find(|bibliography| canonical(&bibliography.path) == path)
+                {
+                    bibliography.refsections.push(refsection.number);
+                    continue;
+                }
+
+                let Some(bib_file) = resource.to_str().filter(|_| resource.exists()) else {
+                    eprintln!(
+                        "[WARN] Could not find bibliography {resource:?} of refsection {}",
+                        refsection.number
+                    );
+                    continue;
+                };
+                local.push(LocalBibliography {
+                    entries: gather_citations::<Citations<Bib>>(&CitationSource::from_bib(
+                        bib_file,
+                    ))?,
+                    path: resource.clone(),
+                    refsections: vec![refsection.number],
+                });
+            }
+        }
+
+        Ok(Self {
+            global,
+            local,
+            printed: src.printed_bibliographies()?,
+            refsegments_known: src.aux_file.is_none() && src.bcf_file.is_none(),
+        })
+    }
+}
@@ -0,0 +1,256 @@
+//! Check citation commands suit their context within parentheses
+//!
+//! Narrative citation commands (e.g., `\textcite`, `\citet`) print the author outside of parentheses and the year within them, and parenthetical commands (e.g., `\parencite`, `\citep`) wrap the whole citation in parentheses, so neither reads well within a parenthetical remark: `(see \citep{smith2020})` prints "(see (Smith, 2020))".  Each package provides a variant without parentheses for this purpose: `\citealt` and `\citealp` in natbib, `\cite` in biblatex, `\nptextcite` in biblatex-apa (i.e., biblatex with `style=apa`), and `\nptextcite`, `\citeANP`, and `\citeNP` in apacite.  We detect the package from the `\usepackage` commands of the root document, and report any command with such a variant that is within parentheses, suggesting the variant to use instead.  Parentheses are counted from the start of the paragraph in which the command is, ignoring comments, escaped parentheses, and math.  This module contains the logic for this functionality, which can be accessed using the `--cite-commands` option of `citati check`.  As commands are only meaningful in LaTeX source, documents in other markup languages are not checked.
+
+use super::{
+    citations::{gather_citations, Citations},
+    diagnostics::{Check, Finding},
+    error::Result,
+    source::{CitationSource, LaTeX, LaTeXCitation, Location, Markup},
+};
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+lazy_static! {
+    static ref USEPACKAGE_REGEX: Regex =
+        Regex::new(r"\\usepackage\s*(?:\[([^\]]*)\])?\s*\{([^}]*)\}").unwrap();
+    static ref APA_STYLE_REGEX: Regex = Regex::new(r"\bstyle\s*=\s*apa\b").unwrap();
+}
+
+/// Package providing the citation commands of a document
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Package {
+    Natbib,
+    Biblatex,
+
+    /// biblatex with the APA style (`style=apa`) of biblatex-apa, which provides `\nptextcite`
+    BiblatexApa,
+    Apacite,
+}
+
+impl Package {
+    /// Name of the package, as it is loaded
+    pub fn name(&self) -> &'static str {
+        match self {
+            Package::Natbib => "natbib",
+            Package::Biblatex => "biblatex",
+            Package::BiblatexApa => "biblatex-apa",
+            Package::Apacite => "apacite",
+        }
+    }
+
+    /// Detect the package providing citation commands from the `\usepackage` commands in the LaTeX source, if any; the first of these packages loaded is used
+    ///
+    /// ```
+    /// use citati::cite_commands::Package;
+    ///
+    /// let src = "\\documentclass{article}\n\\usepackage[style=apa]{biblatex}\n";
+    /// assert_eq!(Package::detect(src), Some(Package::BiblatexApa));
+    /// assert_eq!(Package::detect("% \\usepackage{natbib}\n"), None);
+    /// ```
+    pub fn detect(src: &str) -> Option<Package> {
+        src.lines()
+            .map(|line| line.split('%').next().unwrap_or(""))
+            .flat_map(|line| USEPACKAGE_REGEX.captures_iter(line))
+            .find_map(|caps| {
+                let options = caps.get(1).map_or("", |m| m.as_str());
+                caps[2].split(',').find_map(|name| match name.trim() {
+                    "natbib" => Some(Package::Natbib),
+                    "biblatex" if APA_STYLE_REGEX.is_match(options) => Some(Package::BiblatexApa),
+                    "biblatex" => Some(Package::Biblatex),
+                    "apacite" => Some(Package::Apacite),
+                    _ => None,
+                })
+            })
+    }
+
+    /// Command that should be used in place of the given command within parentheses, if it prints parentheses of its own and the package has a variant without them
+    ///
+    /// Starred commands (e.g., `\citet*`, which lists all authors) keep their star
+    pub fn variant_within_parentheses(&self, cite_cmd: &str) -> Option<String> {
+        let (cmd, star) = match cite_cmd.strip_suffix('*') {
+            Some(cmd) => (cmd, "*"),
+            None => (cite_cmd, ""),
+        };
+        let variant = match (self, cmd) {
+            (Package::Natbib, "citet") => "citealt",
+            (Package::Natbib, "Citet") => "Citealt",
+            (Package::Natbib, "citep") => "citealp",
+            (Package::Natbib, "Citep") => "Citealp",
+            (Package::Biblatex, "textcite") => "cite",
+            (Package::Biblatex, "Textcite") => "Cite",
+            (Package::BiblatexApa, "textcite" | "Textcite") => "nptextcite",
+            (Package::Biblatex | Package::BiblatexApa, "parencite" | "autocite") => "cite",
+            (Package::Biblatex | Package::BiblatexApa, "Parencite" | "Autocite") => "Cite",
+            (Package::Apacite, "textcite") => "nptextcite",
+            (Package::Apacite, "citeA") => "citeANP",
+            (Package::Apacite, "CiteA") => "CiteANP",
+            (Package::Apacite, "cite") => "citeNP",
+            (Package::Apacite, "Cite") => "CiteNP",
+            (Package::Apacite, "citeyear") => "citeyearNP",
+            _ => return None,
+        };
+        Some(format!("{variant}{star}"))
+    }
+}
+
+/// Lines of the files in which citations are, read once each
+struct Files<'a> {
+    src: &'a CitationSource<'a>,
+    lines: HashMap<PathBuf, Option<Vec<String>>>,
+}
+
+impl<'a> Files<'a> {
+    fn new(src: &'a CitationSource<'a>) -> Self {
+        Self {
+            src,
+            lines: HashMap::new(),
+        }
+    }
+
+    /// Lines of the file, or nothing if it cannot be read.  The root document may be given in memory rather than read from its file
+    fn lines(&mut self, file: &Path) -> Option<&[String]> {
+        let src = self.src;
+        self.lines
+            .entry(file.to_path_buf())
+            .or_insert_with(|| {
+                let contents = if src.latex_file.is_some_and(|latex| Path::new(latex) == file) {
+                    src.latex_contents().ok()?.into_owned()
+                } else {
+                    fs::read_to_string(file).ok()?
+                };
+                Some(contents.lines().map(str::to_owned).collect())
+            })
+            .as_deref()
+    }
+}
+
+/// Whether the command citing at the location is within parentheses
+///
+/// The citation's location is that of its key, so we look back along the line for the command itself, and count parentheses from the start of its paragraph (i.e., after the last blank line) up to it
+fn within_parentheses(lines: &[String], location: &Location, cite_cmd: &str) -> bool {
+    let Some(index) = location.line.checked_sub(1).filter(|i| *i < lines.len()) else {
+        return false;
+    };
+    let line = &lines[index];
+    let key_start = line
+        .char_indices()
+        .nth(location.column.saturating_sub(1))
+        .map_or(line.len(), |(i, _)| i);
+    let cmd_start = line[..key_start]
+        .rfind(&format!("\\{cite_cmd}"))
+        .unwrap_or(key_start);
+
+    let start = lines[..index]
+        .iter()
+        .rposition(|line| line.trim().is_empty())
+        .map_or(0, |i| i + 1);
+    let mut depth: usize = 0;
+    let mut in_math = false;
+    for (i, line) in lines[start..=index].iter().enumerate() {
+        let text = if start + i == index {
+            &line[..cmd_start]
+        } else {
+            line
+        };
+        let mut escaped = false;
+        for c in text.chars() {
+            if escaped {
+                escaped = false;
+                continue;
+            }
+            match c {
+                '\\' => escaped = true,
+                '%' => break,
+                '$' => in_math = !in_math,
+                '(' if !in_math => depth += 1,
+                ')' if !in_math => depth = depth.saturating_sub(1),
+                _ => {}
+            }
+        }
+    }
+    depth > 0
+}
+
+/// Defines the finding reported for a citation command within parentheses, at the given locations
+fn report_cite_command(
+    citation: &LaTeXCitation,
+    cite_cmd: &str,
+    variant: &str,
+    package: Package,
+    locations: Vec<Location>,
+) -> Finding {
+    let mut finding = Finding::new(
+        Check::CiteCommands,
+        &citation.key,
+        format!(
+            "\\{cite_cmd} is within parentheses; use \\{variant} instead ({})",
+            package.name()
+        ),
+    );
+    finding
+        .values
+        .insert("command".to_owned(), cite_cmd.to_owned());
+    finding
+        .values
+        .insert("suggestion".to_owned(), variant.to_owned());
+    finding.locations = locations;
+    finding
+}
+
+/// List (in alphabetical order) any citations in LaTeX source using a command that prints its own parentheses within parentheses
+///
+/// Nothing is reported if the document is not LaTeX, or if it does not load natbib, biblatex, or apacite
+///
+/// ```
+/// use citati::{cite_commands, source::CitationSource};
+///
+/// let src = CitationSource::new("document.tex", "references.bib")
+///     .with_latex_src("\\usepackage{natbib}\nAs \\citet{smith2020} show (see also \\citet{doe2019}).\n");
+/// let findings = cite_commands::check_cite_commands(&src)?;
+/// assert_eq!(findings.len(), 1);
+/// assert_eq!(findings[0].key, "doe2019");
+/// assert_eq!(findings[0].values["suggestion"], "citealt");
+/// # Ok::<(), citati::Error>(())
+/// ```
+pub fn check_cite_commands(src: &CitationSource) -> Result<Vec<Finding>> {
+    if src.markup() != Markup::LaTeX {
+        return Ok(Vec::new());
+    }
+    let Some(package) = Package::detect(&src.latex_contents()?) else {
+        return Ok(Vec::new());
+    };
+
+    let citations = gather_citations::<Citations<LaTeX>>(src)?;
+    let mut files = Files::new(src);
+    let mut findings = Vec::new();
+    for citation in citations.list_sorted() {
+        // Locations within parentheses for each command, in the order in which commands are first used
+        let mut commands: Vec<(&str, String, Vec<Location>)> = Vec::new();
+        for (cite_cmd, location) in citation.cite_cmds.iter().zip(citation.locations.iter()) {
+            let Some(variant) = package.variant_within_parentheses(cite_cmd) else {
+                continue;
+            };
+            let Some(lines) = files.lines(&location.file) else {
+                continue;
+            };
+            if !within_parentheses(lines, location, cite_cmd) {
+                continue;
+            }
+            match commands.iter_mut().find(|(cmd, _, _)| cmd == cite_cmd) {
+                Some((_, _, locations)) => locations.push(location.clone()),
+                None => commands.push((cite_cmd, variant, vec![location.clone()])),
+            }
+        }
+
+        findings.extend(commands.into_iter().map(|(cite_cmd, variant, locations)| {
+            report_cite_command(citation, cite_cmd, &variant, package, locations)
+        }));
+    }
+    Ok(findings)
+}
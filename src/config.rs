@@ -0,0 +1,93 @@
+//! Project configuration file
+//!
+//! Defaults for the options of `citati check` can be given in a TOML file named `citati.toml` (or `.citati.toml`, to keep it out of sight) in the directory in which citati is run, so that the documents, bibliography, checks, and search paths of a project need not be given on every run.  Options given on the command line take precedence over those of the configuration file, as do the `TEXINPUTS` and `BIBINPUTS` environment variables over its search paths (as they do over `texmf.cnf` in kpathsea).  The search paths and bibliography are also those of `citati lsp`.  Paths are relative to the directory in which citati is run, as are those given on the command line.
+//!
+//! ```toml
+//! files = ["thesis/main.tex", "papers/*.tex"]
+//! bibliography = "references.bib"
+//! checks = ["unused", "missing", "pages"]
+//! format = "pretty"
+//! texinputs = ".:styles//"
+//! bibinputs = "bib//"
+//! ```
+
+use crate::{
+    diagnostics::Check,
+    error::{Error, Result},
+    output::Format,
+};
+use serde::Deserialize;
+use std::{fs, path::Path};
+
+/// Names of the configuration file, in order of precedence
+pub const CONFIG_FILES: [&str; 2] = ["citati.toml", ".citati.toml"];
+
+/// Defaults for options not given on the command line, each of which is optional
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Documents, glob patterns, or directories to check, as given with `--file`
+    pub files: Option<Vec<String>>,
+
+    /// Bib file, as given with `--bibliography`
+    pub bibliography: Option<String>,
+
+    /// Checks to run if none are selected on the command line, by the names with which their findings are reported
+    pub checks: Option<Vec<Check>>,
+
+    /// Output format of findings, as given with `--format`
+    pub format: Option<Format>,
+
+    /// Search path for files included with `\input{}`, as given with `--texinputs`
+    pub texinputs: Option<String>,
+
+    /// Search path for bib files, as given with `--bibinputs`
+    pub bibinputs: Option<String>,
+}
+
+impl Config {
+    /// Read the configuration file in the directory, if there is one, or return an empty configuration otherwise
+    pub fn find(dir: &Path) -> Result<Self> {
+        let mut found = CONFIG_FILES
+            .iter()
+            .map(|name| dir.join(name))
+            .filter(|path| path.is_file());
+        let Some(path) = found.next() else {
+            return Ok(Self::default());
+        };
+        if let Some(ignored) = found.next() {
+            eprintln!(
+                "[WARN] Configuration file {:?} is ignored, as {:?} is read instead",
+                ignored.display().to_string(),
+                path.display().to_string()
+            );
+        }
+        Self::from_file(&path)
+    }
+
+    /// Read the configuration file
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let src = fs::read_to_string(path).map_err(Error::io(format!(
+            "Could not read configuration file {:?}",
+            path.display().to_string()
+        )))?;
+        let config: Self = toml::from_str(&src).map_err(|e| {
+            Error::Parse(format!(
+                "Could not parse configuration file {:?}: {e}",
+                path.display().to_string()
+            ))
+        })?;
+
+        // As on the command line, unused suppressions are found by running
+        // every other check, so cannot be selected with them
+        if let Some(checks) = &config.checks {
+            if checks.contains(&Check::UnusedSuppression) && checks.len() > 1 {
+                return Err(Error::Invalid(format!(
+                    "Check \"unused-suppression\" cannot be selected with other checks in configuration file {:?}",
+                    path.display().to_string()
+                )));
+            }
+        }
+        Ok(config)
+    }
+}
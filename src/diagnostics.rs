@@ -0,0 +1,210 @@
+//! Findings reported by checks
+//!
+//! Rather than printing directly, each check produces a list of [`Finding`]s, which are then rendered by one of the [`output`](`crate::output`) formats.  A finding records which [`Check`] produced it, the citation key it concerns, the relevant fields and their values, and where in the source files the problem is.
+
+use crate::source::Location;
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fmt};
+
+/// The checks that citati can perform, each of which produce findings
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Check {
+    /// Bib entries that are not cited in the LaTeX source; see [`unused`](`crate::unused`)
+    Unused,
+
+    /// Citations in the LaTeX source that are not defined in the bib file; see [`missing`](`crate::missing`)
+    Missing,
+
+    /// Bib entries with malformatted `pages`; see [`pages`](`crate::pages`)
+    Pages,
+
+    /// Article entries missing required fields; see [`article`](`crate::fields::article`)
+    Articles,
+
+    /// Citation commands that print their own parentheses within parentheses; see [`cite_commands`](`crate::cite_commands`)
+    CiteCommands,
+
+    /// Suppression comments that do not match any finding; see [`suppress`](`crate::suppress`)
+    UnusedSuppression,
+}
+
+impl Check {
+    /// All checks, in the order in which they are documented
+    pub const ALL: [Check; 6] = [
+        Check::Unused,
+        Check::Missing,
+        Check::Pages,
+        Check::Articles,
+        Check::CiteCommands,
+        Check::UnusedSuppression,
+    ];
+
+    /// Stable, machine-readable name of the check
+    pub fn name(&self) -> &'static str {
+        match self {
+            Check::Unused => "unused",
+            Check::Missing => "missing",
+            Check::Pages => "pages",
+            Check::Articles => "articles",
+            Check::CiteCommands => "cite-commands",
+            Check::UnusedSuppression => "unused-suppression",
+        }
+    }
+
+    /// Look up a check by its [`name`](`Check::name`)
+    pub fn from_name(name: &str) -> Option<Check> {
+        Check::ALL.into_iter().find(|check| check.name() == name)
+    }
+
+    /// Short, human-readable description of what the check looks for
+    pub fn description(&self) -> &'static str {
+        match self {
+            Check::Unused => "Bibliography entry is not cited in the LaTeX source",
+            Check::Missing => "Citation in the LaTeX source is not defined in the bibliography",
+            Check::Pages => "Page range is not two numbers separated by an en dash",
+            Check::Articles => "Article entry is missing required fields",
+            Check::CiteCommands => "Citation command prints its own parentheses within parentheses",
+            Check::UnusedSuppression => "Suppression comment does not match any finding",
+        }
+    }
+
+    /// The severity with which findings of this check are reported
+    pub fn severity(&self) -> Severity {
+        match self {
+            Check::Missing => Severity::Error,
+            Check::Unused
+            | Check::Pages
+            | Check::Articles
+            | Check::CiteCommands
+            | Check::UnusedSuppression => Severity::Warning,
+        }
+    }
+}
+
+impl fmt::Display for Check {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// How serious a finding is
+///
+/// Variants are ordered from least to most severe
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// A single problem found by a check
+#[derive(Clone, Debug, Serialize)]
+pub struct Finding {
+    pub check: Check,
+    pub severity: Severity,
+    pub key: String,
+
+    /// Human-readable description of the problem
+    pub message: String,
+
+    /// Fields of the bib entry that the finding concerns
+    pub fields: Vec<String>,
+
+    /// Values of the fields that the finding concerns (where present)
+    pub values: BTreeMap<String, String>,
+
+    /// Where the problem is in the source files
+    pub locations: Vec<Location>,
+}
+
+impl Finding {
+    /// Construct a new finding for the given check and citation key, with the check's default severity
+    pub fn new(check: Check, key: &str, message: String) -> Self {
+        Self {
+            check,
+            severity: check.severity(),
+            key: key.to_owned(),
+            message,
+            fields: Vec::new(),
+            values: BTreeMap::new(),
+            locations: Vec::new(),
+        }
+    }
+
+    /// Stable identifier of the finding
+    ///
+    /// The fingerprint depends only on the check, key, fields, and values of the finding, and not on its location, so that it does not change when unrelated edits move the entry or citation around the file
+    pub fn fingerprint(&self) -> String {
+        let mut parts = vec![self.check.name(), &self.key];
+        parts.extend(self.fields.iter().map(String::as_str));
+        for (field, value) in self.values.iter() {
+            parts.push(field);
+            parts.push(value);
+        }
+        fingerprint(&parts)
+    }
+}
+
+/// Compute a stable hexadecimal fingerprint of the given parts
+///
+/// We use 64-bit FNV-1a rather than [`std::hash`], whose output is not guaranteed to be the same between Rust releases.  Parts are separated by a null byte so that, e.g., `["ab", "c"]` and `["a", "bc"]` differ
+pub fn fingerprint(parts: &[&str]) -> String {
+    const OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+    const PRIME: u64 = 0x100000001b3;
+
+    let mut hash = OFFSET_BASIS;
+    for part in parts {
+        for byte in part.bytes().chain(std::iter::once(0)) {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(PRIME);
+        }
+    }
+    format!("{hash:016x}")
+}
+
+/// Plain, single-line representation of a finding
+///
+/// This is the output format citati has always used, so that existing scripts continue to work
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.check {
+            Check::Unused | Check::Missing => write!(f, "{}", self.key),
+            Check::Pages => write!(
+                f,
+                "{} ({:?})",
+                self.key,
+                self.values.get("pages").cloned().unwrap_or_default()
+            ),
+            Check::Articles => write!(f, "{} (missing: {})", self.key, self.fields.join(", ")),
+            Check::CiteCommands => write!(
+                f,
+                "{} (\\{}; use \\{})",
+                self.key,
+                self.values.get("command").cloned().unwrap_or_default(),
+                self.values.get("suggestion").cloned().unwrap_or_default()
+            ),
+            Check::UnusedSuppression => {
+                let location = &self.locations[0];
+                write!(
+                    f,
+                    "{}:{} ({})",
+                    location.file.display(),
+                    location.line,
+                    self.values.get("directive").cloned().unwrap_or_default()
+                )
+            }
+        }
+    }
+}
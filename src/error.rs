@@ -0,0 +1,49 @@
+//! Errors of the library
+//!
+//! Reading, parsing, or writing source can fail (e.g., a file may not exist, or a bibliography may not be valid BibTeX), as can operations that are not possible with the source as given (e.g., renaming a key to one that is already defined).  Functions of the library return such failures as an [`Error`], and it is left to the caller to report them: the `citati` command-line tool prints them to standard error and exits with [`ERROR`](`crate::exit::ERROR`) status.
+
+use std::{fmt, io};
+
+/// Failure to read, parse, or write some source, or to carry out an operation on it
+#[derive(Debug)]
+pub enum Error {
+    /// A file (or standard input or output) could not be read or written, as described by `context` (e.g., `Could not read LaTeX file "main.tex"`)
+    Io { context: String, source: io::Error },
+
+    /// Source could not be parsed, as described by the message, which gives the location of the error where it is known
+    Parse(String),
+
+    /// The operation is not possible with the source as given (e.g., no bibliography file was given, or a key is already defined)
+    Invalid(String),
+}
+
+/// Result of an operation of the library
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+impl Error {
+    /// Construct an error from an I/O error, described by the context in which it happened
+    ///
+    /// This is intended for use with [`Result::map_err`], as in `fs::read_to_string(path).map_err(Error::io(format!("Could not read file {path:?}")))`
+    pub fn io(context: impl Into<String>) -> impl FnOnce(io::Error) -> Self {
+        let context = context.into();
+        move |source| Error::Io { context, source }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io { context, source } => write!(f, "{context}: {source}"),
+            Error::Parse(message) | Error::Invalid(message) => write!(f, "{message}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io { source, .. } => Some(source),
+            Error::Parse(_) | Error::Invalid(_) => None,
+        }
+    }
+}
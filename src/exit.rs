@@ -0,0 +1,12 @@
+//! Exit statuses of the program
+//!
+//! So that citati can be used to fail CI builds, the exit status reflects whether anything was found: [`SUCCESS`] if no findings were at or above the `--fail-on` severity, [`FINDINGS`] if some were, and [`ERROR`] if we could not perform the checks at all (e.g., a file could not be read, in which case the library returns an [`Error`](`crate::Error`), which the command-line tool reports before exiting).  Usage errors are handled by [`clap`], which also exits with status 2.
+
+/// No findings at or above the `--fail-on` severity (or `--warn-only` was given)
+pub const SUCCESS: i32 = 0;
+
+/// Some findings at or above the `--fail-on` severity
+pub const FINDINGS: i32 = 1;
+
+/// Usage or I/O error
+pub const ERROR: i32 = 2;
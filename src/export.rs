@@ -0,0 +1,77 @@
+//! Export the cited subset of the bibliography
+//!
+//! When sharing a document (e.g., submitting to a journal), it is often useful to include only the bibliography entries that are actually cited, rather than a personal bibliography of several hundred entries.  This module writes a copy of the bibliography with all entries that are not cited in the LaTeX source removed, which can be accessed using the `citati export` subcommand.  Entries that cited entries depend on through their `crossref` or `xdata` fields are kept too, as is every entry if the source has `\nocite{*}`.  As this is built on the lossless [`cst`](`crate::source::bib::cst`) layer, the remaining entries (and any `@string` definitions, etc.) are written exactly as they appear in the original file.
+
+use super::{
+    citations::{gather_citations, HollowCitations},
+    error::{Error, Result},
+    source::{bib, CitationSource, LaTeX},
+};
+use std::{collections::HashSet, fs, io::Write};
+
+/// Fields whose values are the keys of other entries (separated by commas) from which an entry inherits data
+const PARENT_FIELDS: [&str; 2] = ["crossref", "xdata"];
+
+/// Write the entries of the bib file that are cited in the LaTeX source to `output`, or to standard output if not given
+pub fn export_cited(src: &CitationSource, output: Option<&str>) -> Result<()> {
+    let citations = gather_citations::<HollowCitations<LaTeX>>(src)?;
+    let mut document =
+        bib::parse_bib_document_from_str(src.bib_contents()?.into_owned(), src.bib_path()?)?;
+
+    // Keep cited entries, and the entries from which they inherit data,
+    // recursively, as the exported bibliography would otherwise not resolve
+    let mut kept: HashSet<String> = document
+        .entries()
+        .iter()
+        .map(|entry| document.key(entry).to_owned())
+        .filter(|key| citations.contains(key) || citations.contains("*"))
+        .collect();
+    let mut pending: Vec<String> = kept.iter().cloned().collect();
+    while let Some(key) = pending.pop() {
+        let Some(entry) = document.entries().iter().find(|e| document.key(e) == key) else {
+            continue;
+        };
+        for name in PARENT_FIELDS {
+            let Some(value) = document
+                .field(entry, name)
+                .and_then(|field| document.content(field))
+            else {
+                continue;
+            };
+            for parent in document.text(&value).split(',').map(str::trim) {
+                if !parent.is_empty() && kept.insert(parent.to_owned()) {
+                    pending.push(parent.to_owned());
+                }
+            }
+        }
+    }
+
+    // Find entries that are not kept before deleting them, as edits invalidate borrows of the document
+    let unkept: Vec<String> = document
+        .entries()
+        .iter()
+        .map(|entry| document.key(entry).to_owned())
+        .filter(|key| !kept.contains(key))
+        .collect();
+    for key in unkept.iter() {
+        document.delete_entry(key);
+    }
+
+    let exported = document.entries().len();
+    match output {
+        Some(output) => {
+            fs::write(output, document.src()).map_err(Error::io(format!(
+                "Could not write bibliography file {output:?}"
+            )))?;
+            eprintln!("[INFO] Exported {exported} entries to bibliography file {output:?}");
+        }
+        None => {
+            let mut stdout = std::io::stdout().lock();
+            stdout
+                .write_all(document.src().as_bytes())
+                .map_err(Error::io("Could not write to standard output"))?;
+        }
+    }
+
+    Ok(())
+}
@@ -1,9 +1,11 @@
 //! Check presence of required fields in article entries
 //!
-//! Check all bibliography entries of type `article` have required fields.  This module contains logic for this functionality, which can be accessed using the [`--article`](`crate::Group::article`) option.  Unfortunately, some journals will have non-standard article reporting, so this will produce some false positives.
+//! Check all bibliography entries of type `article` have required fields.  This module contains logic for this functionality, which can be accessed using the `--articles` option of `citati check`.  Unfortunately, some journals will have non-standard article reporting, so this will produce some false positives; these can be silenced with a `% citati-ignore: articles` comment before the entry (see [`directive`](`crate::source::directive`)).
 
 use crate::{
     citations::{gather_citations, Citations},
+    diagnostics::{Check, Finding},
+    error::Result,
     source::{Bib, BibCitation, CitationSource},
 };
 use biblatex::EntryType;
@@ -14,31 +16,40 @@ lazy_static! {
         &["volume", "number", "pages", "doi"];
 }
 
-/// Construct user-friendly string to report missing article fields
-fn report_article(citation: &BibCitation) -> String {
+/// Construct finding to report missing article fields
+fn report_article(citation: &BibCitation) -> Finding {
     let missing_fields: Vec<&str> = REQUIRED_ARTICLE_FIELDS
         .iter()
         .filter(|f| !citation.has_field(f))
         .cloned()
         .collect();
 
-    if missing_fields.is_empty() {
-        eprintln!("[ERROR] Cannot report missing article fields when none are missing");
-        std::process::exit(1);
-    }
+    // Only articles missing some fields are reported
+    assert!(
+        !missing_fields.is_empty(),
+        "Cannot report missing article fields when none are missing"
+    );
 
-    format!("{} (missing: {})", citation.key, missing_fields.join(", "))
+    let mut finding = Finding::new(
+        Check::Articles,
+        &citation.key,
+        format!("Article is missing fields: {}", missing_fields.join(", ")),
+    );
+    finding.fields = missing_fields.iter().map(|f| f.to_string()).collect();
+    finding.locations.push(citation.location.clone());
+    finding
 }
 
 /// Check for missing article fields
-pub fn check_article_fields(bib_file: &str) {
-    let src = CitationSource::from_bib(bib_file);
-    let bib_entries = gather_citations::<Citations<Bib>>(&src);
+pub fn check_article_fields(src: &CitationSource) -> Result<Vec<Finding>> {
+    let bib_entries = gather_citations::<Citations<Bib>>(src)?;
     let articles_with_missing_fields = bib_entries.filter(|c| {
         c.entry_type() == EntryType::Article && !c.has_fields(&REQUIRED_ARTICLE_FIELDS)
     });
 
-    for citation in articles_with_missing_fields.list_sorted() {
-        println!("{}", report_article(citation));
-    }
+    Ok(articles_with_missing_fields
+        .list_sorted()
+        .into_iter()
+        .map(report_article)
+        .collect())
 }
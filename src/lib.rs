@@ -0,0 +1,76 @@
+//! Citation helper for BibTeX
+//!
+//! Look through citations in LaTeX/bibliography source and perform various checks for correctness.  Name derived from цитаты (_tsitaty_): quotes/citations.  As well as the `citati` command-line tool, the functionality is available as a library, so that other Rust tools (e.g., a preprocessor in a publishing pipeline) can reuse the LaTeX [`Lexer`](`source::latex::Lexer`), citation collections, and checks.
+//!
+//! ## Overview
+//!
+//!   - [`source`] reads LaTeX source and bibliography files, including the lossless [`cst`](`source::bib::cst`) layer for editing bibliography files without reformatting them;
+//!   - [`citations`] gathers citations from a [`CitationSource`](`source::CitationSource`) into collections, either [`Citations`](`citations::Citations`) with full information or [`HollowCitations`](`citations::HollowCitations`) with just their keys;
+//!   - [`unused`], [`missing`], [`pages`], [`fields`], and [`cite_commands`] are the checks, each of which produce a list of [`Finding`](`diagnostics::Finding`)s (see [`diagnostics`]);
+//!   - [`project`] runs the checks over many documents sharing a bibliography;
+//!   - [`stats`] summarises how a document cites its bibliography;
+//!   - [`suppress`] removes findings silenced by suppression comments; and
+//!   - [`tidy`] formats bibliography source.
+//!
+//! ## Example
+//!
+//! ```
+//! use citati::{diagnostics::Check, missing, source::CitationSource, unused};
+//!
+//! // Sources are usually read from files, but can also be given in memory
+//! let src = CitationSource::new("document.tex", "references.bib")
+//!     .with_latex_src("See \\cite{smith2020} and \\cite{doe2019}.\n")
+//!     .with_bib_src("@book{smith2020, title = {A}}\n@book{unused, title = {B}}\n");
+//!
+//! let findings = unused::unused_citations(&src)?;
+//! assert_eq!(findings[0].check, Check::Unused);
+//! assert_eq!(findings[0].key, "unused");
+//!
+//! let findings = missing::missing_citations(&src)?;
+//! assert_eq!(findings[0].key, "doe2019");
+//! assert_eq!(findings[0].locations[0].line, 1);
+//!
+//! // Errors reading the source are returned rather than reported
+//! let src = CitationSource::new("document.tex", "nonexistent.bib")
+//!     .with_latex_src("See \\cite{smith2020}.\n");
+//! assert!(missing::missing_citations(&src).is_err());
+//! # Ok::<(), citati::Error>(())
+//! ```
+//!
+//! ## Stability
+//!
+//! citati has not yet reached v1.0.0.  The modules documented here are its public API: we try to avoid breaking changes to them, and any that are necessary will be noted in the release notes and accompanied by a minor version bump.  Modules that are hidden from the documentation (e.g., output formats and baselines) only exist to support the command-line tool, and may change in any release.
+//!
+//! Errors reading, parsing, or writing source files are returned to the caller as an [`Error`], rather than reported to the user: only the command-line tool prints them and exits.
+
+pub mod citations;
+pub mod cite_commands;
+pub mod diagnostics;
+pub mod error;
+pub mod fields;
+pub mod missing;
+pub mod pages;
+pub mod project;
+pub mod source;
+pub mod stats;
+pub mod suppress;
+pub mod tidy;
+pub mod unused;
+
+pub use error::{Error, Result};
+
+// Modules supporting the command-line tool, which are not part of the public API
+#[doc(hidden)]
+pub mod baseline;
+#[doc(hidden)]
+pub mod config;
+#[doc(hidden)]
+pub mod exit;
+#[doc(hidden)]
+pub mod export;
+#[doc(hidden)]
+pub mod lsp;
+#[doc(hidden)]
+pub mod output;
+#[doc(hidden)]
+pub mod rename;
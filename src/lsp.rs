@@ -0,0 +1,440 @@
+//! Report findings to editors as a language server
+//!
+//! `citati lsp` speaks the [Language Server Protocol](https://microsoft.github.io/language-server-protocol/) over standard input and output, so that editors can show the findings of `citati check` (with its default checks) inline while the document is written.  Documents are checked whenever they are opened, changed, or saved, using the text in the editor rather than that on disk, and findings are published as diagnostics of the files in which they are located: citations in the document (or a file it includes), and entries in its bibliography.  Documents that are open at the same time and share a bibliography are checked together, as are many documents given to `citati check` (see [`project`](`crate::project`)).  The bibliography of a document is that given to the server, if any, otherwise `references.bib` in the directory of the document; if it is open in the editor, its text there is used too.  Only full synchronisation of documents is supported, and diagnostics are all that the server provides.
+
+use crate::{
+    diagnostics::{Finding, Severity},
+    error::{Error, Result},
+    fields, pages, project,
+    source::CitationSource,
+    suppress::Suppressions,
+};
+use clap::{crate_name, crate_version};
+use serde_json::{json, Value};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    io::{BufRead, Write},
+    path::{Path, PathBuf},
+};
+
+/// Name of the bibliography of a document, in its directory, if none is given to the server
+const DEFAULT_BIB_FILE: &str = "references.bib";
+
+/// JSON-RPC error code of a message that is not valid JSON
+const PARSE_ERROR: i64 = -32700;
+
+/// JSON-RPC error code of a request for a method that the server does not provide
+const METHOD_NOT_FOUND: i64 = -32601;
+
+/// LSP message type of errors logged to the editor
+const LOG_ERROR: u8 = 1;
+
+/// Options of the server, which apply to every document
+#[derive(Default)]
+pub struct Options {
+    /// Bibliography of every document, rather than `references.bib` in the directory of each
+    pub bib_file: Option<PathBuf>,
+
+    /// Search path for files included in the LaTeX source (see [`search`](`crate::source::search`))
+    pub texinputs: Option<String>,
+
+    /// Search path for bibliography files
+    pub bibinputs: Option<String>,
+}
+
+/// A file open in the editor
+struct OpenFile {
+    /// URI by which the editor refers to the file, to which diagnostics of the file are published
+    uri: String,
+    text: String,
+}
+
+struct Server<'a, W: Write> {
+    output: W,
+    options: &'a Options,
+
+    /// Files open in the editor, by path
+    files: BTreeMap<PathBuf, OpenFile>,
+
+    /// URIs of the files to which diagnostics were last published
+    published: BTreeSet<String>,
+
+    /// Whether positions are counted in UTF-16 code units (the default of the protocol) rather than characters
+    utf16: bool,
+
+    /// Whether the editor has asked the server to shut down
+    shutdown: bool,
+}
+
+/// Serve editors over the given input and output until asked to exit
+///
+/// Returns an error if a message cannot be read or written, or if the editor closes the connection without first asking the server to shut down
+pub fn serve(mut input: impl BufRead, output: impl Write, options: &Options) -> Result<()> {
+    let mut server = Server {
+        output,
+        options,
+        files: BTreeMap::new(),
+        published: BTreeSet::new(),
+        utf16: true,
+        shutdown: false,
+    };
+
+    while let Some(body) = read_message(&mut input)? {
+        match serde_json::from_slice::<Value>(&body) {
+            Ok(message) => {
+                if server.handle(&message)? {
+                    break;
+                }
+            }
+            Err(e) => server.send(&json!({
+                "jsonrpc": "2.0",
+                "id": Value::Null,
+                "error": { "code": PARSE_ERROR, "message": format!("Could not parse message: {e}") },
+            }))?,
+        }
+    }
+
+    if server.shutdown {
+        Ok(())
+    } else {
+        Err(Error::Invalid(
+            "Language server exited without being shut down".to_owned(),
+        ))
+    }
+}
+
+/// Read the content of the next message, with its headers removed, if any is left
+fn read_message(input: &mut impl BufRead) -> Result<Option<Vec<u8>>> {
+    let mut length = None;
+    loop {
+        let mut line = String::new();
+        let n = input
+            .read_line(&mut line)
+            .map_err(Error::io("Could not read language server message"))?;
+        if n == 0 {
+            return Ok(None);
+        }
+
+        // Headers end at an empty line; only the length of the content matters
+        let line = line.trim_end();
+        if line.is_empty() && length.is_some() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("Content-Length") {
+                length = value.trim().parse::<usize>().ok();
+            }
+        }
+    }
+
+    let mut body = vec![0; length.unwrap_or_default()];
+    input
+        .read_exact(&mut body)
+        .map_err(Error::io("Could not read language server message"))?;
+    Ok(Some(body))
+}
+
+impl<W: Write> Server<'_, W> {
+    /// Write a message to the editor
+    fn send(&mut self, message: &Value) -> Result<()> {
+        let body = message.to_string();
+        write!(self.output, "Content-Length: {}\r\n\r\n{body}", body.len())
+            .and_then(|_| self.output.flush())
+            .map_err(Error::io("Could not write language server message"))
+    }
+
+    fn respond(&mut self, id: &Value, result: Value) -> Result<()> {
+        self.send(&json!({ "jsonrpc": "2.0", "id": id, "result": result }))
+    }
+
+    fn notify(&mut self, method: &str, params: Value) -> Result<()> {
+        self.send(&json!({ "jsonrpc": "2.0", "method": method, "params": params }))
+    }
+
+    /// Handle a message from the editor, returning whether the server should exit
+    fn handle(&mut self, message: &Value) -> Result<bool> {
+        let Some(method) = message["method"].as_str() else {
+            // Responses to requests, which we never make
+            return Ok(false);
+        };
+        let params = &message["params"];
+        let id = message.get("id");
+
+        match (method, id) {
+            ("initialize", Some(id)) => {
+                // Prefer counting positions in characters, as our locations
+                // do, where the editor can
+                let encodings = &params["capabilities"]["general"]["positionEncodings"];
+                self.utf16 = !encodings
+                    .as_array()
+                    .is_some_and(|encodings| encodings.iter().any(|e| e == "utf-32"));
+                let encoding = if self.utf16 { "utf-16" } else { "utf-32" };
+                self.respond(
+                    id,
+                    json!({
+                        "capabilities": {
+                            "positionEncoding": encoding,
+                            "textDocumentSync": { "openClose": true, "change": 1, "save": { "includeText": true } },
+                        },
+                        "serverInfo": { "name": crate_name!(), "version": crate_version!() },
+                    }),
+                )?;
+            }
+            ("shutdown", Some(id)) => {
+                self.shutdown = true;
+                self.respond(id, Value::Null)?;
+            }
+            ("exit", None) => return Ok(true),
+            ("textDocument/didOpen", None) => {
+                let document = &params["textDocument"];
+                if let Some(text) = document["text"].as_str() {
+                    self.update(&document["uri"], text);
+                    self.publish()?;
+                }
+            }
+            ("textDocument/didChange", None) => {
+                // We only ask for full synchronisation, so the last change
+                // has the whole text of the document
+                let changes = params["contentChanges"].as_array();
+                if let Some(text) = changes.and_then(|c| c.last()?["text"].as_str()) {
+                    self.update(&params["textDocument"]["uri"], text);
+                    self.publish()?;
+                }
+            }
+            ("textDocument/didSave", None) => {
+                if let Some(text) = params["text"].as_str() {
+                    self.update(&params["textDocument"]["uri"], text);
+                }
+                self.publish()?;
+            }
+            ("textDocument/didClose", None) => {
+                if let Some(path) = params["textDocument"]["uri"].as_str().and_then(path_from_uri)
+                {
+                    self.files.remove(&path);
+                }
+                self.publish()?;
+            }
+            (method, Some(id)) => self.send(&json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": { "code": METHOD_NOT_FOUND, "message": format!("Unsupported method {method:?}") },
+            }))?,
+
+            // Other notifications (e.g., `initialized`) need nothing doing
+            (_, None) => {}
+        }
+
+        Ok(false)
+    }
+
+    /// Record the text of a file open in the editor
+    fn update(&mut self, uri: &Value, text: &str) {
+        if let Some(uri) = uri.as_str() {
+            if let Some(path) = path_from_uri(uri) {
+                let file = OpenFile {
+                    uri: uri.to_owned(),
+                    text: text.to_owned(),
+                };
+                self.files.insert(path, file);
+            }
+        }
+    }
+
+    /// Bibliography of the document at the given path
+    fn bib_file(&self, document: &Path) -> PathBuf {
+        match &self.options.bib_file {
+            Some(bib_file) => absolute(bib_file),
+            None => document.with_file_name(DEFAULT_BIB_FILE),
+        }
+    }
+
+    /// Check the open documents, and publish diagnostics of every file in which something was found, clearing those of files in which nothing now is
+    fn publish(&mut self) -> Result<()> {
+        // Documents sharing a bibliography are checked together
+        let mut projects: BTreeMap<PathBuf, Vec<&Path>> = BTreeMap::new();
+        for path in self.files.keys() {
+            if path.extension().is_some_and(|ext| ext == "bib") {
+                continue;
+            }
+            projects.entry(self.bib_file(path)).or_default().push(path);
+        }
+        let (findings, errors): (Vec<_>, Vec<_>) = projects
+            .iter()
+            .map(|(bib_file, documents)| self.check(bib_file, documents))
+            .partition(Result::is_ok);
+        let findings: Vec<Finding> = findings.into_iter().flat_map(Result::unwrap).collect();
+
+        // Problems reading the source are logged rather than shown as
+        // diagnostics, as they have no location
+        for e in errors.into_iter().filter_map(Result::err) {
+            let message = e.to_string();
+            let params = json!({ "type": LOG_ERROR, "message": message });
+            self.notify("window/logMessage", params)?;
+        }
+
+        // Findings within cells of Jupyter notebooks have no position in the
+        // file that the editor could show
+        let mut diagnostics: BTreeMap<String, Vec<Value>> = BTreeMap::new();
+        for finding in findings.iter() {
+            for location in finding.locations.iter().filter(|l| l.cell.is_none()) {
+                let path = absolute(&location.file);
+                let character = self.character(&path, location.line, location.column);
+                let position = json!({ "line": location.line - 1, "character": character });
+                let uri = match self.files.get(&path) {
+                    Some(file) => file.uri.clone(),
+                    None => uri_from_path(&path),
+                };
+                diagnostics.entry(uri).or_default().push(json!({
+                    "range": { "start": position, "end": position },
+                    "severity": match finding.severity {
+                        Severity::Error => 1,
+                        Severity::Warning => 2,
+                    },
+                    "code": finding.check.name(),
+                    "source": crate_name!(),
+                    "message": finding.message,
+                }));
+            }
+        }
+
+        let cleared: Vec<String> = self
+            .published
+            .iter()
+            .filter(|uri| !diagnostics.contains_key(*uri))
+            .cloned()
+            .collect();
+        for uri in cleared {
+            let params = json!({ "uri": uri, "diagnostics": [] });
+            self.notify("textDocument/publishDiagnostics", params)?;
+        }
+        self.published = diagnostics.keys().cloned().collect();
+        for (uri, diagnostics) in diagnostics {
+            let params = json!({ "uri": uri, "diagnostics": diagnostics });
+            self.notify("textDocument/publishDiagnostics", params)?;
+        }
+
+        Ok(())
+    }
+
+    /// Findings of the default checks of `citati check` in the documents, which share the bibliography, with suppressed findings removed
+    fn check(&self, bib_file: &Path, documents: &[&Path]) -> Result<Vec<Finding>> {
+        let bib_file = bib_file.display().to_string();
+        let documents: Vec<String> = documents.iter().map(|d| d.display().to_string()).collect();
+        let srcs: Vec<CitationSource> = documents
+            .iter()
+            .map(|document| {
+                let mut src = CitationSource::new(document, &bib_file)
+                    .with_latex_src(&self.files[Path::new(document)].text);
+                if let Some(bib) = self.files.get(Path::new(&bib_file)) {
+                    src = src.with_bib_src(&bib.text);
+                }
+                if let Some(texinputs) = self.options.texinputs.as_deref() {
+                    src = src.with_texinputs(texinputs);
+                }
+                if let Some(bibinputs) = self.options.bibinputs.as_deref() {
+                    src = src.with_bibinputs(bibinputs);
+                }
+                src
+            })
+            .collect();
+
+        let mut findings = project::unused_citations(&srcs)?;
+        findings.extend(project::missing_citations(&srcs)?);
+        findings.extend(pages::check_bib_pages(&srcs[0])?);
+        findings.extend(fields::article::check_article_fields(&srcs[0])?);
+        findings.extend(project::cite_commands(&srcs)?);
+        Ok(Suppressions::gather_all(&srcs)?.apply(findings))
+    }
+
+    /// Position within its line of the given (one-based) column of a file, in the units of the editor
+    ///
+    /// Counting UTF-16 code units needs the text of the line, which is read from the file if it is not open in the editor; if it cannot be read, we count characters instead
+    fn character(&self, path: &Path, line: usize, column: usize) -> usize {
+        let column = column.saturating_sub(1);
+        if !self.utf16 {
+            return column;
+        }
+
+        let text = match self.files.get(path) {
+            Some(file) => Some(file.text.clone()),
+            None => fs::read_to_string(path).ok(),
+        };
+        text.as_deref()
+            .and_then(|text| text.lines().nth(line.saturating_sub(1)))
+            .map_or(column, |line| {
+                line.chars().take(column).map(char::len_utf16).sum()
+            })
+    }
+}
+
+/// Path of a file with the given `file:` URI, if it is one
+///
+/// ```
+/// use citati::lsp::path_from_uri;
+/// use std::path::PathBuf;
+///
+/// let path = path_from_uri("file:///home/user/My%20Thesis/main.tex");
+/// assert_eq!(path, Some(PathBuf::from("/home/user/My Thesis/main.tex")));
+/// assert_eq!(path_from_uri("untitled:Untitled-1"), None);
+/// ```
+pub fn path_from_uri(uri: &str) -> Option<PathBuf> {
+    let path = uri.strip_prefix("file://")?;
+
+    let mut bytes = Vec::with_capacity(path.len());
+    let mut rest = path.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        let escaped = tail
+            .get(..2)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (byte, escaped) {
+            (b'%', Some(escaped)) => {
+                bytes.push(escaped);
+                rest = &tail[2..];
+            }
+            _ => {
+                bytes.push(byte);
+                rest = tail;
+            }
+        }
+    }
+    let path = String::from_utf8(bytes).ok()?;
+
+    // Paths on Windows start with a drive letter (e.g., `file:///C:/main.tex`)
+    let path = match path.strip_prefix('/') {
+        Some(rest) if rest.get(1..2) == Some(":") => rest.to_owned(),
+        _ => path,
+    };
+    Some(PathBuf::from(path))
+}
+
+/// `file:` URI of the given absolute path, escaping characters that cannot appear in a URI
+///
+/// ```
+/// use citati::lsp::uri_from_path;
+/// use std::path::Path;
+///
+/// let uri = uri_from_path(Path::new("/home/user/My Thesis/main.tex"));
+/// assert_eq!(uri, "file:///home/user/My%20Thesis/main.tex");
+/// ```
+pub fn uri_from_path(path: &Path) -> String {
+    let path = path.to_string_lossy().replace('\\', "/");
+    let mut uri = String::from("file://");
+    if !path.starts_with('/') {
+        uri.push('/');
+    }
+    for byte in path.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~/:".contains(&byte) {
+            uri.push(byte as char);
+        } else {
+            uri.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    uri
+}
+
+/// The path made absolute relative to the working directory, where it can be
+fn absolute(path: &Path) -> PathBuf {
+    std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf())
+}
@@ -1,16 +1,48 @@
-use clap::{crate_authors, crate_name, crate_version, ArgAction, Args, Parser};
+use citati::{
+    baseline, config::Config, diagnostics::Check, diagnostics::Severity, exit, export, fields, lsp,
+    output, output::Format, pages, project, rename, source::latex, source::org, source::search,
+    source::typst, source::CitationSource, source::Markup, stats, suppress::Suppressions, tidy,
+};
+use clap::{crate_authors, crate_name, crate_version, ArgAction, Args, Parser, Subcommand};
+use std::{
+    env,
+    fmt::Display,
+    path::{Path, PathBuf},
+};
 
-mod citations;
-mod fields;
-mod pages;
-mod source;
-mod unused;
+/// File name given on the command line to read from standard input
+const STDIN: &str = "-";
+
+/// Virtual path at which findings in standard input are reported
+const STDIN_PATH: &str = "<stdin>";
+
+/// Bib file that is read if none is given
+const DEFAULT_BIB_FILE: &str = "references.bib";
+
+/// Document that is checked if none is given
+const DEFAULT_LATEX_FILE: &str = "document.tex";
+
+/// Report an error to the user and exit with [`exit::ERROR`] status
+fn error(msg: impl Display) -> ! {
+    eprintln!("[ERROR] {msg}");
+    std::process::exit(exit::ERROR)
+}
+
+/// Exit with [`exit::ERROR`] status if the library could not carry out an operation
+trait OrExit<T> {
+    fn or_exit(self) -> T;
+}
+
+impl<T> OrExit<T> for citati::Result<T> {
+    fn or_exit(self) -> T {
+        self.unwrap_or_else(|e| error(e))
+    }
+}
 
 // TODO:
 //   - Add --article functionality
 //   - Add --collection functionality
 //   - Add --book functionality
-//   - Add --count functionality
 //   - Add checker for journals to be capitalised appropriately
 //   - Port to Rust
 //   - Check no duplicate IDs
@@ -19,7 +51,6 @@ mod unused;
 //   - Check correct capitalisation of journal
 //   - FIX UNUSED COMMAND
 //   - check no . at end of title
-//   - Check that inside parentheses we are using `nptextcite`
 //   - Make citations an enum type from string
 
 #[derive(Parser)]
@@ -28,12 +59,269 @@ mod unused;
     author = crate_authors!(", "),
     version = crate_version!(),
     arg_required_else_help = true,
+    args_conflicts_with_subcommands = true,
 )]
 /// Citation helper for BibTex
 ///
 /// Look through citations in LaTeX/bibliography source and perform various checks for correctness.  Name derived from цитаты (_tsitaty_): quotes/citations.
 struct Cli {
-    /// LaTeX file
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Options of the `check` subcommand, which are accepted without it for backwards compatibility
+    #[clap(flatten, next_help_heading = "Deprecated options (use `citati check`)")]
+    legacy: CheckArgs,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Check LaTeX source and bib file for problems [default: the checks of citati.toml, otherwise all checks except --unused-suppressions]
+    Check(CheckArgs),
+
+    /// Format bib file consistently, similar to bibtex-tidy
+    Fmt(FmtArgs),
+
+    /// Write a copy of the bib file containing only the entries cited in the LaTeX source
+    Export(ExportArgs),
+
+    /// Rename a citation key in both the bib file and LaTeX source
+    Rename(RenameArgs),
+
+    /// Report how often each key is cited, and summarise the cited works by type, year, and journal
+    Stats(StatsArgs),
+
+    /// Run a language server over standard input and output, reporting the findings of check to editors as diagnostics
+    Lsp(LspArgs),
+}
+
+/// Options for checking the LaTeX source and bib file
+#[derive(Args)]
+pub struct CheckArgs {
+    /// LaTeX (or Markdown, Typst, Org, reStructuredText, or Jupyter notebook) files, glob patterns (e.g., 'chapters/*.tex'), or directories in which to find root documents, or - to read from standard input.  Citations are merged across all documents [default: the files of citati.toml, otherwise document.tex]
+    #[arg(
+        short = 'f',
+        long = "file",
+        action = ArgAction::Append,
+        num_args = 1..,
+        value_name = "latex file",
+    )]
+    latex_files: Vec<String>,
+
+    /// BibTeX file, or - to read from standard input [default: the bibliography of citati.toml, or of a Typst or Org document, otherwise references.bib]
+    #[arg(
+        short = 'b',
+        long = "bibliography",
+        action = ArgAction::Set,
+        num_args = 0..=1,
+        value_name = "bib file",
+        default_missing_value = DEFAULT_BIB_FILE,
+    )]
+    bib_file: Option<String>,
+
+    /// Read citations from this auxiliary file written by LaTeX (e.g., document.aux), rather than from the LaTeX source, for --unused and --missing
+    #[arg(
+        long = "aux",
+        action = ArgAction::Set,
+        value_name = "aux file",
+    )]
+    aux_file: Option<String>,
+
+    /// Read citations from this control file written by biblatex (e.g., document.bcf), rather than from the LaTeX source, for --unused and --missing
+    #[arg(
+        long = "bcf",
+        action = ArgAction::Set,
+        value_name = "bcf file",
+        conflicts_with = "aux_file",
+    )]
+    bcf_file: Option<String>,
+
+    /// Search path for files included with \input{}, as in kpathsea (e.g., '.:styles//') [default: $TEXINPUTS, otherwise the texinputs of citati.toml]
+    #[arg(
+        long = "texinputs",
+        action = ArgAction::Set,
+        value_name = "search path",
+    )]
+    texinputs: Option<String>,
+
+    /// Search path for bib files that are not in the directory of the document, as in kpathsea [default: $BIBINPUTS, otherwise the bibinputs of citati.toml]
+    #[arg(
+        long = "bibinputs",
+        action = ArgAction::Set,
+        value_name = "search path",
+    )]
+    bibinputs: Option<String>,
+
+    /// Also read citations from raw LaTeX cells of Jupyter notebooks, as well as from their Markdown cells
+    #[arg(
+        long = "latex-cells",
+        action = ArgAction::SetTrue,
+        num_args = 0,
+        default_value_t = false,
+    )]
+    latex_cells: bool,
+
+    /// Output format of findings [default: the format of citati.toml, otherwise pretty if writing to a terminal, otherwise plain]
+    #[arg(
+        long = "format",
+        action = ArgAction::Set,
+        value_enum,
+        value_name = "format",
+    )]
+    format: Option<Format>,
+
+    /// Exit with non-zero status if there are findings of this severity or higher
+    #[arg(
+        long = "fail-on",
+        action = ArgAction::Set,
+        value_enum,
+        value_name = "severity",
+        default_value_t = Severity::Warning,
+        conflicts_with = "warn_only",
+    )]
+    fail_on: Severity,
+
+    /// Always exit with zero status when checks complete, regardless of findings
+    #[arg(
+        long = "warn-only",
+        action = ArgAction::SetTrue,
+        num_args = 0,
+        default_value_t = false,
+    )]
+    warn_only: bool,
+
+    /// Only report findings that are not recorded in this baseline file
+    #[arg(
+        long = "baseline",
+        action = ArgAction::Set,
+        value_name = "baseline file",
+        conflicts_with = "write_baseline",
+    )]
+    baseline: Option<String>,
+
+    /// Record current findings to this baseline file, rather than reporting them
+    #[arg(
+        long = "write-baseline",
+        action = ArgAction::Set,
+        value_name = "baseline file",
+    )]
+    write_baseline: Option<String>,
+
+    /// Rewrite malformatted pages in the bib file in place, where possible, before reporting remaining findings
+    #[arg(
+        long = "fix",
+        action = ArgAction::SetTrue,
+        num_args = 0,
+        default_value_t = false,
+    )]
+    fix: bool,
+
+    /// With --fix, print a unified diff of the changes instead of writing them
+    #[arg(
+        long = "dry-run",
+        action = ArgAction::SetTrue,
+        num_args = 0,
+        default_value_t = false,
+        requires = "fix",
+    )]
+    dry_run: bool,
+
+    #[clap(flatten)]
+    group: Group,
+}
+
+/// Options for formatting the bib file; see [`tidy`]
+#[derive(Args)]
+pub struct FmtArgs {
+    /// BibTeX file, or - to read from standard input
+    #[arg(
+        short = 'b',
+        long = "bibliography",
+        action = ArgAction::Set,
+        num_args = 0..=1,
+        value_name = "bib file",
+        default_value = "references.bib",
+    )]
+    bib_file: String,
+
+    /// Do not write the bib file; instead, print a diff and exit with non-zero status if it is not formatted
+    #[arg(
+        long = "check",
+        action = ArgAction::SetTrue,
+        num_args = 0,
+        default_value_t = false,
+    )]
+    check: bool,
+
+    /// Number of spaces by which to indent fields
+    #[arg(
+        long = "indent",
+        action = ArgAction::Set,
+        value_name = "spaces",
+        default_value_t = 2,
+    )]
+    indent: usize,
+
+    /// Do not align the values of fields within each entry
+    #[arg(
+        long = "no-align",
+        action = ArgAction::SetTrue,
+        num_args = 0,
+        default_value_t = false,
+    )]
+    no_align: bool,
+
+    /// Delimiters to use for field values
+    #[arg(
+        long = "delimiters",
+        action = ArgAction::Set,
+        value_enum,
+        value_name = "delimiters",
+        default_value_t = tidy::Delimiters::Braces,
+    )]
+    delimiters: tidy::Delimiters,
+
+    /// Sort entries by the given keys, in order of precedence (e.g., `--sort year,key`)
+    #[arg(
+        long = "sort",
+        action = ArgAction::Append,
+        value_enum,
+        value_name = "keys",
+        value_delimiter = ',',
+    )]
+    sort: Vec<tidy::SortKey>,
+
+    /// Order fields as in bibtex-tidy (title, shorttitle, author, year, ...), placing any other fields after these
+    #[arg(
+        long = "sort-fields",
+        action = ArgAction::SetTrue,
+        num_args = 0,
+        default_value_t = false,
+    )]
+    sort_fields: bool,
+
+    /// Order fields as given, optionally only for the given entry type (e.g., `--field-order article=author,title,journal`).  Can be given more than once
+    #[arg(
+        long = "field-order",
+        action = ArgAction::Append,
+        value_name = "[type=]fields",
+        value_parser = tidy::parse_field_order,
+    )]
+    field_order: Vec<tidy::FieldOrder>,
+
+    /// Keep fields with empty values, rather than removing them
+    #[arg(
+        long = "keep-empty-fields",
+        action = ArgAction::SetTrue,
+        num_args = 0,
+        default_value_t = false,
+    )]
+    keep_empty_fields: bool,
+}
+
+/// Options for exporting cited entries of the bib file; see [`export`]
+#[derive(Args)]
+pub struct ExportArgs {
+    /// LaTeX file, or - to read from standard input
     #[arg(
         short = 'f',
         long = "file",
@@ -44,7 +332,7 @@ struct Cli {
     )]
     latex_file: String,
 
-    /// BibTeX file
+    /// BibTeX file, or - to read from standard input
     #[arg(
         short = 'b',
         long = "bibliography",
@@ -55,17 +343,138 @@ struct Cli {
     )]
     bib_file: String,
 
-    #[clap(flatten)]
-    group: Group,
+    /// File to which to write cited entries [default: standard output]
+    #[arg(
+        short = 'o',
+        long = "output",
+        action = ArgAction::Set,
+        value_name = "output file",
+    )]
+    output: Option<String>,
 }
 
-/// Group containing individual functional units for the program.
-///
-/// We only want to allow one functional check at a time.  The following group, which is flattened in the main Cli struct, should provide such functionality.
+/// Options for renaming a citation key; see [`rename`]
+#[derive(Args)]
+pub struct RenameArgs {
+    /// Current citation key
+    #[arg(value_name = "old key")]
+    old: String,
+
+    /// New citation key
+    #[arg(value_name = "new key")]
+    new: String,
+
+    /// LaTeX file, or - to read from standard input
+    #[arg(
+        short = 'f',
+        long = "file",
+        action = ArgAction::Set,
+        num_args = 0..=1,
+        value_name = "latex file",
+        default_value = "document.tex",
+    )]
+    latex_file: String,
+
+    /// BibTeX file, or - to read from standard input
+    #[arg(
+        short = 'b',
+        long = "bibliography",
+        action = ArgAction::Set,
+        num_args = 0..=1,
+        value_name = "bib file",
+        default_value = "references.bib",
+    )]
+    bib_file: String,
+
+    /// Print a unified diff of the changes instead of writing them
+    #[arg(
+        long = "dry-run",
+        action = ArgAction::SetTrue,
+        num_args = 0,
+        default_value_t = false,
+    )]
+    dry_run: bool,
+}
+
+/// Options for reporting citation statistics; see [`stats`]
+#[derive(Args)]
+pub struct StatsArgs {
+    /// LaTeX (or Markdown, Typst, Org, reStructuredText, or Jupyter notebook) file, or - to read from standard input
+    #[arg(
+        short = 'f',
+        long = "file",
+        action = ArgAction::Set,
+        num_args = 0..=1,
+        value_name = "latex file",
+        default_value = "document.tex",
+    )]
+    latex_file: String,
+
+    /// BibTeX file, or - to read from standard input
+    #[arg(
+        short = 'b',
+        long = "bibliography",
+        action = ArgAction::Set,
+        num_args = 0..=1,
+        value_name = "bib file",
+        default_value = "references.bib",
+    )]
+    bib_file: String,
+
+    /// Number of the most-cited works to list
+    #[arg(
+        long = "top",
+        action = ArgAction::Set,
+        value_name = "n",
+        default_value_t = stats::DEFAULT_TOP,
+    )]
+    top: usize,
+
+    /// Output format of statistics
+    #[arg(
+        long = "format",
+        action = ArgAction::Set,
+        value_enum,
+        value_name = "format",
+        default_value_t = stats::Format::Plain,
+    )]
+    format: stats::Format,
+}
+
+/// Options for the language server; see [`lsp`]
+#[derive(Args)]
+pub struct LspArgs {
+    /// BibTeX file of every document [default: the bibliography of citati.toml, otherwise references.bib in the directory of each document]
+    #[arg(
+        short = 'b',
+        long = "bibliography",
+        action = ArgAction::Set,
+        value_name = "bib file",
+    )]
+    bib_file: Option<String>,
+
+    /// Search path for files included with \input{}, as in kpathsea (e.g., '.:styles//') [default: $TEXINPUTS, otherwise the texinputs of citati.toml]
+    #[arg(
+        long = "texinputs",
+        action = ArgAction::Set,
+        value_name = "search path",
+    )]
+    texinputs: Option<String>,
+
+    /// Search path for bib files that are not in the directory of the document, as in kpathsea [default: $BIBINPUTS, otherwise the bibinputs of citati.toml]
+    #[arg(
+        long = "bibinputs",
+        action = ArgAction::Set,
+        value_name = "search path",
+    )]
+    bibinputs: Option<String>,
+}
+
+/// Group containing individual checks for the program.
 ///
-/// <https://stackoverflow.com/a/76315811>
+/// Originally, we only allowed one functional check at a time (<https://stackoverflow.com/a/76315811>).  Now that checks are run by the `check` subcommand, any number of them can be selected, and if none are, all of them are run.  The exception is `--unused-suppressions`, which has to run all checks itself.
 #[derive(Args)]
-#[group(required = true, multiple = false)]
+#[group(required = false, multiple = true)]
 pub struct Group {
     /// Show bib keys of citations in bib file that are not used in LaTeX source
     #[arg(
@@ -77,6 +486,16 @@ pub struct Group {
     )]
     unused: bool,
 
+    /// Show citation keys used in LaTeX source that are not defined in bib file
+    #[arg(
+        short = 'm',
+        long = "missing",
+        action = ArgAction::SetTrue,
+        num_args = 0,
+        default_value_t = false,
+    )]
+    missing: bool,
+
     /// Show bib keys of citations in bib file that do not use proper formatting for pages
     #[arg(
         short = 'p',
@@ -97,6 +516,25 @@ pub struct Group {
     )]
     articles: bool,
 
+    /// Show citations in LaTeX source within parentheses whose command prints its own parentheses (e.g., `\citep` rather than `\citealp`)
+    #[arg(
+        long = "cite-commands",
+        action = ArgAction::SetTrue,
+        num_args = 0,
+        default_value_t = false,
+    )]
+    cite_commands: bool,
+
+    /// Show suppression comments in LaTeX source or bib file that do not match any finding
+    #[arg(
+        long = "unused-suppressions",
+        action = ArgAction::SetTrue,
+        num_args = 0,
+        default_value_t = false,
+        conflicts_with_all = ["unused", "missing", "pages", "articles", "cite_commands", "article"],
+    )]
+    unused_suppressions: bool,
+
     /* Deprecated */
     #[arg(
         long = "article",
@@ -108,26 +546,399 @@ pub struct Group {
     article: bool,
 }
 
-fn main() {
-    let cli = Cli::parse();
+impl Group {
+    /// Whether no checks were selected, in which case all of them are run
+    fn is_empty(&self) -> bool {
+        !(self.unused
+            || self.missing
+            || self.pages
+            || self.articles
+            || self.cite_commands
+            || self.unused_suppressions
+            || self.article)
+    }
+
+    /// Select the check, as its option would
+    fn select(&mut self, check: Check) {
+        match check {
+            Check::Unused => self.unused = true,
+            Check::Missing => self.missing = true,
+            Check::Pages => self.pages = true,
+            Check::Articles => self.articles = true,
+            Check::CiteCommands => self.cite_commands = true,
+            Check::UnusedSuppression => self.unused_suppressions = true,
+        }
+    }
+}
+
+/// Read standard input if it was given (as `-`) in place of any of the files, or nothing otherwise
+///
+/// Standard input can only be read once, so only one of the files can be given as `-`
+fn read_stdin_for(files: &[&str]) -> String {
+    match files.iter().filter(|file| **file == STDIN).count() {
+        0 => String::new(),
+        1 => std::io::read_to_string(std::io::stdin())
+            .unwrap_or_else(|e| error(format!("Could not read standard input: {e}"))),
+        _ => error("Only one file can be read from standard input"),
+    }
+}
+
+/// Construct the citation source from the files given on the command line, using `stdin` in place of a file given as `-`
+fn citation_source<'a>(
+    latex_file: &'a str,
+    bib_file: &'a str,
+    stdin: &'a str,
+) -> CitationSource<'a> {
+    let path = |file: &'a str| if file == STDIN { STDIN_PATH } else { file };
+    let mut src = CitationSource::new(path(latex_file), path(bib_file));
+    if latex_file == STDIN {
+        src = src.with_latex_src(stdin);
+    }
+    if bib_file == STDIN {
+        src = src.with_bib_src(stdin);
+    }
+    src
+}
+
+/// The root document of the LaTeX file given on the command line, which is the file itself unless it names its root with a magic comment
+fn root_document(latex_file: &str) -> String {
+    if latex_file == STDIN {
+        return latex_file.to_owned();
+    }
+    latex::root_document(Path::new(latex_file))
+        .display()
+        .to_string()
+}
+
+/// Warn if the bib file is not one of the datasources listed in the control file, as biber would not read it
+fn warn_unlisted_bib_file(src: &CitationSource) {
+    let bib_file = src.bib_path().or_exit();
+    if bib_file == STDIN_PATH {
+        return;
+    }
+    let bcf_file = src.bcf_path().or_exit();
+    let control_file = src.control_file().or_exit();
+    let canonical = |path: &Path| path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    let bib_path = canonical(Path::new(bib_file));
+    if control_file
+        .datasources
+        .iter()
+        .any(|datasource| canonical(&datasource.path) == bib_path)
+    {
+        return;
+    }
+
+    let datasources: Vec<String> = control_file
+        .datasources
+        .iter()
+        .map(|datasource| format!("{:?}", datasource.path.display().to_string()))
+        .collect();
+    eprintln!(
+        "[WARN] Bibliography {bib_file:?} is not a datasource of control file {bcf_file:?} (datasources: {})",
+        datasources.join(", ")
+    );
+}
+
+/// The bib file to read if none is given: that given to a Typst or Org document, otherwise references.bib
+fn default_bib_file(latex_file: &str) -> String {
+    let path = Path::new(latex_file);
+    if !path.exists() {
+        return DEFAULT_BIB_FILE.to_owned();
+    }
+
+    let bibliographies = match Markup::from_path(path) {
+        Markup::Typst => {
+            typst::parse_typst_from_file(latex_file)
+                .or_exit()
+                .bibliographies
+        }
+        Markup::Org => {
+            org::parse_org_from_file(latex_file)
+                .or_exit()
+                .bibliographies
+        }
+        Markup::LaTeX | Markup::Markdown | Markup::Rst | Markup::Notebook => Vec::new(),
+    };
+    match bibliographies.as_slice() {
+        [] => DEFAULT_BIB_FILE.to_owned(),
+        [bib_file] => bib_file.display().to_string(),
+        [bib_file, ..] => {
+            eprintln!(
+                "[WARN] Document {latex_file:?} has more than one bibliography; only {:?} is checked",
+                bib_file.display().to_string()
+            );
+            bib_file.display().to_string()
+        }
+    }
+}
+
+/// The bib file to read: that given, if it exists, otherwise the first of its name in the search path for bib files of the document
+fn find_bib_file(latex_file: &str, bib_file: String, bibinputs: Option<&str>) -> String {
+    if bib_file == STDIN || Path::new(&bib_file).is_file() {
+        return bib_file;
+    }
+
+    let mut src = CitationSource::from_latex(latex_file);
+    if let Some(bibinputs) = bibinputs {
+        src = src.with_bibinputs(bibinputs);
+    }
+    src.bib_search_path()
+        .find(Path::new(&bib_file))
+        .map_or(bib_file, |found| found.display().to_string())
+}
+
+/// Run the selected checks and report findings, exiting with non-zero status if there are any at or above the severity to fail on
+fn check(mut args: CheckArgs) -> ! {
+    // Options not given on the command line are those of the configuration
+    // file, if any
+    let config = Config::find(Path::new(".")).or_exit();
+    if args.group.is_empty() {
+        match config.checks.as_deref() {
+            Some(checks) if !checks.is_empty() => {
+                for check in checks {
+                    args.group.select(*check);
+                }
+            }
+            _ => {
+                args.group.unused = true;
+                args.group.missing = true;
+                args.group.pages = true;
+                args.group.articles = true;
+                args.group.cite_commands = true;
+            }
+        }
+    }
+    if args.latex_files.is_empty() {
+        args.latex_files = config
+            .files
+            .unwrap_or_else(|| vec![DEFAULT_LATEX_FILE.to_owned()]);
+    }
+    args.bib_file = args.bib_file.or(config.bibliography);
+    args.format = args.format.or(config.format);
+
+    // Search paths not given as options are those of kpathsea's environment
+    // variables, if set, otherwise those of the configuration file
+    args.texinputs = args
+        .texinputs
+        .or_else(|| env::var(search::TEXINPUTS).ok())
+        .or(config.texinputs);
+    args.bibinputs = args
+        .bibinputs
+        .or_else(|| env::var(search::BIBINPUTS).ok())
+        .or(config.bibinputs);
+
+    let latex_files = project::expand_documents(&args.latex_files).or_exit();
+    if latex_files.len() > 1 && (args.aux_file.is_some() || args.bcf_file.is_some()) {
+        error("--aux and --bcf can only be given with a single document");
+    }
+
+    let bib_file = args
+        .bib_file
+        .clone()
+        .unwrap_or_else(|| default_bib_file(&latex_files[0]));
+    let bib_file = find_bib_file(&latex_files[0], bib_file, args.bibinputs.as_deref());
+    let mut files: Vec<&str> = latex_files.iter().map(String::as_str).collect();
+    files.push(&bib_file);
+    let stdin = read_stdin_for(&files);
+
+    let mut srcs = Vec::new();
+    for latex_file in latex_files.iter() {
+        let mut src = citation_source(latex_file, &bib_file, &stdin);
+        if let Some(aux_file) = args.aux_file.as_deref() {
+            src = src.with_aux_file(aux_file);
+        }
+        if let Some(bcf_file) = args.bcf_file.as_deref() {
+            src = src.with_bcf_file(bcf_file);
+            warn_unlisted_bib_file(&src);
+        }
+        if let Some(texinputs) = args.texinputs.as_deref() {
+            src = src.with_texinputs(texinputs);
+        }
+        if let Some(bibinputs) = args.bibinputs.as_deref() {
+            src = src.with_bibinputs(bibinputs);
+        }
+        if args.latex_cells {
+            src = src.with_latex_cells();
+        }
+        srcs.push(src);
+    }
 
-    if cli.group.unused {
-        unused::unused_citations(&cli.latex_file, &cli.bib_file);
+    // Checks of the bib file alone are the same for every document
+    let src = &srcs[0];
+    let mut findings = Vec::new();
+
+    // Fix what we can before checking, except where suppressed.  A dry run
+    // only shows the changes we would make, exiting with non-zero status if
+    // there are any
+    if args.fix {
+        let suppressions = Suppressions::gather_all(&srcs).or_exit();
+        let changed = pages::fix_bib_pages(src, suppressions, args.dry_run).or_exit();
+        if args.dry_run {
+            std::process::exit(if changed {
+                exit::FINDINGS
+            } else {
+                exit::SUCCESS
+            });
+        }
     }
 
-    if cli.group.pages {
-        pages::check_bib_pages(&cli.bib_file);
+    if args.group.unused {
+        findings.extend(project::unused_citations(&srcs).or_exit());
     }
 
-    if cli.group.article || cli.group.articles {
-        if cli.group.article {
+    if args.group.missing {
+        findings.extend(project::missing_citations(&srcs).or_exit());
+    }
+
+    if args.group.pages {
+        findings.extend(pages::check_bib_pages(src).or_exit());
+    }
+
+    if args.group.article || args.group.articles {
+        if args.group.article {
             eprintln!(
                 "[WARN] --article option is deprecated since v0.3.2.  Use --articles instead"
             );
         }
 
-        fields::article::check_article_fields(&cli.bib_file);
+        findings.extend(fields::article::check_article_fields(src).or_exit());
+    }
+
+    if args.group.cite_commands {
+        findings.extend(project::cite_commands(&srcs).or_exit());
+    }
+
+    // Remove findings silenced by suppression comments.  Directives are
+    // gathered from all sources whichever checks run, as a directive in the
+    // LaTeX source (e.g., `% citati-disable pages`) may silence a check of
+    // the bib file
+    let findings = if args.group.unused_suppressions {
+        project::unused_suppressions(&srcs).or_exit()
+    } else {
+        Suppressions::gather_all(&srcs).or_exit().apply(findings)
+    };
+
+    if let Some(path) = args.write_baseline {
+        baseline::write_baseline(&path, &findings).or_exit();
+        eprintln!(
+            "[INFO] Recorded {} finding(s) to baseline file {path:?}",
+            findings.len()
+        );
+        std::process::exit(exit::SUCCESS);
+    }
+
+    // Only report new findings relative to the baseline, if given
+    let findings = match args.baseline {
+        Some(path) => baseline::filter_baseline(&path, findings).or_exit(),
+        None => findings,
+    };
+
+    let format = args.format.unwrap_or_else(Format::detect);
+    output::render(format, &findings, srcs[0].bib_path().or_exit());
+
+    let failed = findings.iter().any(|f| f.severity >= args.fail_on);
+    if failed && !args.warn_only {
+        std::process::exit(exit::FINDINGS);
     }
 
-    std::process::exit(0);
+    std::process::exit(exit::SUCCESS);
+}
+
+/// Format the bib file, exiting with non-zero status if checking and the file is not formatted
+fn fmt(args: FmtArgs) -> ! {
+    let mut field_orders = Vec::new();
+    if args.sort_fields {
+        field_orders.push(tidy::default_field_order());
+    }
+    field_orders.extend(args.field_order);
+
+    let options = tidy::Options {
+        indent: args.indent,
+        align: !args.no_align,
+        delimiters: args.delimiters,
+        sort: args.sort,
+        field_orders,
+        remove_empty_fields: !args.keep_empty_fields,
+    };
+
+    let stdin = read_stdin_for(&[&args.bib_file]);
+    let src = match args.bib_file.as_str() {
+        STDIN => CitationSource::from_bib(STDIN_PATH).with_bib_src(&stdin),
+        bib_file => CitationSource::from_bib(bib_file),
+    };
+    let changed = tidy::format_bib_file(&src, &options, args.check).or_exit();
+    std::process::exit(if changed && args.check {
+        exit::FINDINGS
+    } else {
+        exit::SUCCESS
+    });
+}
+
+fn main() {
+    let cli = Cli::parse();
+
+    match cli.command {
+        Some(Command::Check(args)) => check(args),
+        Some(Command::Fmt(args)) => fmt(args),
+        Some(Command::Export(args)) => {
+            let latex_file = root_document(&args.latex_file);
+            let stdin = read_stdin_for(&[&latex_file, &args.bib_file]);
+            let src = citation_source(&latex_file, &args.bib_file, &stdin);
+            export::export_cited(&src, args.output.as_deref()).or_exit()
+        }
+        Some(Command::Rename(args)) => {
+            if args.latex_file == STDIN || args.bib_file == STDIN {
+                error("Cannot rename citations in standard input");
+            }
+            rename::rename_citation(
+                &root_document(&args.latex_file),
+                &args.bib_file,
+                &args.old,
+                &args.new,
+                args.dry_run,
+            )
+            .or_exit()
+        }
+
+        Some(Command::Stats(args)) => {
+            let latex_file = root_document(&args.latex_file);
+            let stdin = read_stdin_for(&[&latex_file, &args.bib_file]);
+            let src = citation_source(&latex_file, &args.bib_file, &stdin);
+            let stats = stats::citation_stats(&src, args.top).or_exit();
+            match args.format {
+                stats::Format::Plain | stats::Format::Pretty => print!("{stats}"),
+                stats::Format::Json => {
+                    let json = serde_json::to_string_pretty(&stats)
+                        .unwrap_or_else(|e| error(format!("Could not serialise statistics: {e}")));
+                    println!("{json}");
+                }
+            }
+        }
+
+        Some(Command::Lsp(args)) => {
+            let config = Config::find(Path::new(".")).or_exit();
+            let options = lsp::Options {
+                bib_file: args.bib_file.or(config.bibliography).map(PathBuf::from),
+                texinputs: args
+                    .texinputs
+                    .or_else(|| env::var(search::TEXINPUTS).ok())
+                    .or(config.texinputs),
+                bibinputs: args
+                    .bibinputs
+                    .or_else(|| env::var(search::BIBINPUTS).ok())
+                    .or(config.bibinputs),
+            };
+            lsp::serve(std::io::stdin().lock(), std::io::stdout().lock(), &options).or_exit();
+            std::process::exit(exit::SUCCESS);
+        }
+
+        // Options given without a subcommand are those of `check`, as they
+        // were before subcommands were introduced
+        None => {
+            eprintln!(
+                "[WARN] Options without a subcommand are deprecated.  Use `citati check` instead"
+            );
+            check(cli.legacy)
+        }
+    }
 }
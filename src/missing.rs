@@ -0,0 +1,143 @@
+//! Check LaTeX source for any citations not defined in bibliography
+//!
+//! Check for any citations that are used in the LaTeX source but have no corresponding entry in the bibliography.  This is the converse of [`unused`](`crate::unused`).  This module contains the logic for this functionality, which can be accessed using the `--missing` option of `citati check`.  Citations can be read from the auxiliary file written by LaTeX (see [`auxiliary`](`crate::source::auxiliary`)) instead of the LaTeX source, using its `--aux` option, or from the control file written by biblatex (see [`bcf`](`crate::source::bcf`)), using its `--bcf` option.  Documents in other markup languages are read alike (see [`Document`](`crate::source::Document`)).  Keys cited within a biblatex refsection are resolved against the global bibliography and any bibliographies local to that refsection (see [`Bibliographies`](`crate::citations::Bibliographies`)).  Keys that are defined are still missing from the document if none of the bibliographies printed for the refsection in which they are cited selects them (e.g., by refsegment or keyword).
+
+use super::{
+    citations::{gather_citations, Bibliographies, Citations},
+    diagnostics::{Check, Finding},
+    error::Result,
+    source::{CitationSource, Document, LaTeXCitation, Location, Source},
+};
+
+/// Defines the finding reported for citations from LaTeX source that are not defined in the bib file
+///
+/// Only the locations at which the key is not defined are reported.  Where bibliographies are local to refsections, we name the refsections in which those locations are
+fn report_missing(
+    citation: &LaTeXCitation,
+    locations: Vec<Location>,
+    refsections: &[usize],
+) -> Finding {
+    let message = if refsections.is_empty() {
+        format!(
+            "{} is cited but not defined in the bibliography",
+            citation.key
+        )
+    } else {
+        let numbers: Vec<String> = refsections.iter().map(|n| n.to_string()).collect();
+        format!(
+            "{} is cited but not defined in the bibliography of refsection{} {}",
+            citation.key,
+            if numbers.len() == 1 { "" } else { "s" },
+            numbers.join(", ")
+        )
+    };
+    let mut finding = Finding::new(Check::Missing, &citation.key, message);
+    finding.locations = locations;
+    finding
+}
+
+/// Defines the finding reported for citations of entries that are defined, but that no bibliography printed for the refsections in which they are cited selects
+fn report_unprinted(
+    citation: &LaTeXCitation,
+    locations: Vec<Location>,
+    refsections: &[usize],
+) -> Finding {
+    let message = if refsections.is_empty() {
+        format!(
+            "{} is cited but not printed in any bibliography",
+            citation.key
+        )
+    } else {
+        let numbers: Vec<String> = refsections.iter().map(|n| n.to_string()).collect();
+        format!(
+            "{} is cited but not printed in any bibliography of refsection{} {}",
+            citation.key,
+            if numbers.len() == 1 { "" } else { "s" },
+            numbers.join(", ")
+        )
+    };
+    let mut finding = Finding::new(Check::Missing, &citation.key, message);
+    finding.locations = locations;
+    finding
+}
+
+/// Findings for citations that are not defined in the bibliographies available to the refsections in which they are
+fn undefined<S>(citations: &Citations<S>, bibliographies: &Bibliographies) -> Vec<Finding>
+where
+    S: Source<CitationType = LaTeXCitation>,
+{
+    citations
+        .list_sorted()
+        .into_iter()
+        // `\nocite{*}` is not a citation of an entry with key `*`
+        .filter(|citation| citation.key != "*")
+        .filter_map(|citation| {
+            let (locations, mut refsections): (Vec<Location>, Vec<usize>) = citation
+                .locations
+                .iter()
+                .zip(citation.refsections.iter())
+                .filter(|(_, refsection)| !bibliographies.defines(**refsection, &citation.key))
+                .map(|(location, refsection)| (location.clone(), *refsection))
+                .unzip();
+            if locations.is_empty() {
+                return None;
+            }
+
+            // Refsections are only worth naming where they have their own bibliographies
+            if bibliographies.local.is_empty() {
+                refsections.clear();
+            }
+            refsections.retain(|refsection| *refsection != 0);
+            refsections.sort();
+            refsections.dedup();
+            Some(report_missing(citation, locations, &refsections))
+        })
+        .collect()
+}
+
+/// Findings for citations of entries that are defined, but that are not printed by any bibliography of the refsections (and refsegments) in which they are cited
+fn unprinted<S>(citations: &Citations<S>, bibliographies: &Bibliographies) -> Vec<Finding>
+where
+    S: Source<CitationType = LaTeXCitation>,
+{
+    citations
+        .list_sorted()
+        .into_iter()
+        .filter(|citation| citation.key != "*")
+        .filter_map(|citation| {
+            let (locations, mut refsections): (Vec<Location>, Vec<usize>) = citation
+                .locations
+                .iter()
+                .zip(citation.refsections.iter().zip(citation.refsegments.iter()))
+                .filter(|(_, (refsection, refsegment))| {
+                    bibliographies
+                        .entry(**refsection, &citation.key)
+                        .is_some_and(|entry| {
+                            !bibliographies.prints(**refsection, **refsegment, entry)
+                        })
+                })
+                .map(|(location, (refsection, _))| (location.clone(), *refsection))
+                .unzip();
+            if locations.is_empty() {
+                return None;
+            }
+
+            refsections.retain(|refsection| *refsection != 0);
+            refsections.sort();
+            refsections.dedup();
+            Some(report_unprinted(citation, locations, &refsections))
+        })
+        .collect()
+}
+
+/// List (in alphabetical order) any citations from LaTeX source that are missing from the bib source, or from the bibliographies printed by the document
+///
+/// Citations are read from the control file, auxiliary file, or document of the source (see [`Document`])
+pub fn missing_citations(src: &CitationSource) -> Result<Vec<Finding>> {
+    let bibliographies = gather_citations::<Bibliographies>(src)?;
+    let citations = gather_citations::<Citations<Document>>(src)?;
+    let mut findings = undefined(&citations, &bibliographies);
+    findings.extend(unprinted(&citations, &bibliographies));
+    findings.sort_by(|a, b| a.key.cmp(&b.key));
+    Ok(findings)
+}
@@ -0,0 +1,51 @@
+//! GitHub Actions workflow commands
+//!
+//! Each finding is written as a [workflow command](https://docs.github.com/en/actions/writing-workflows/choosing-what-your-workflow-does/workflow-commands-for-github-actions) (e.g., `::warning file=references.bib,line=12,col=3,title=pages::...`), which GitHub displays as an annotation on the relevant line.  Findings with several locations (e.g., a missing key cited in many places) produce one annotation per location.  Lines within a cell of a Jupyter notebook are not lines of the notebook file, so findings there are annotated on the file as a whole, with their position in the cell (e.g., `cell 2, 3:5`) given in the message.
+
+use crate::diagnostics::{Finding, Severity};
+
+/// Escape data (i.e., the message) of a workflow command
+fn escape_data(s: &str) -> String {
+    s.replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+/// Escape the value of a property of a workflow command
+fn escape_property(s: &str) -> String {
+    escape_data(s).replace(':', "%3A").replace(',', "%2C")
+}
+
+/// Workflow command corresponding to a finding's severity
+fn command(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Warning => "warning",
+        Severity::Error => "error",
+    }
+}
+
+pub fn render(findings: &[Finding]) {
+    for finding in findings {
+        let command = command(finding.severity);
+        let title = escape_property(finding.check.name());
+        let message = escape_data(&finding.message);
+
+        if finding.locations.is_empty() {
+            println!("::{command} title={title}::{message}");
+        }
+
+        for location in finding.locations.iter() {
+            let file = escape_property(&location.file.to_string_lossy());
+            match location.cell {
+                Some(_) => println!(
+                    "::{command} file={file},title={title}::{message} ({})",
+                    escape_data(&location.position()),
+                ),
+                None => println!(
+                    "::{command} file={file},line={},col={},title={title}::{message}",
+                    location.line, location.column,
+                ),
+            }
+        }
+    }
+}
@@ -0,0 +1,136 @@
+//! GitLab Code Quality report
+//!
+//! Findings are written as a JSON array in the [Code Quality report format](https://docs.gitlab.com/ee/ci/testing/code_quality.html#code-quality-report-format), which GitLab displays in merge request widgets and diffs.  Each location of a finding is a separate issue, as GitLab only supports one location per issue.
+
+use crate::diagnostics::{fingerprint, Finding, Severity};
+use crate::source::Location;
+use serde_json::{json, Value};
+
+/// Code Quality severity corresponding to a finding's severity
+fn severity(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Warning => "minor",
+        Severity::Error => "major",
+    }
+}
+
+/// Construct a Code Quality issue for a finding at the given location
+///
+/// GitLab requires fingerprints to be unique within the report, so, unlike [`Finding::fingerprint`], these also depend on location.  The column is included, as the same key may be cited more than once on a line, as is the cell of a Jupyter notebook, within which lines are counted.  GitLab requires a line, but lines within a cell are not lines of the notebook file, so issues in a cell are on the first line of the file, and give their position within the cell in the description
+fn issue(finding: &Finding, location: &Location) -> Value {
+    let path = location.file.to_string_lossy().replace('\\', "/");
+    let finding_fingerprint = finding.fingerprint();
+    let cell = location.cell.map(|cell| cell.to_string());
+    let line = location.line.to_string();
+    let column = location.column.to_string();
+    let mut parts = vec![finding_fingerprint.as_str(), &path];
+    parts.extend(cell.as_deref());
+    parts.extend([line.as_str(), &column]);
+    let (description, line) = match location.cell {
+        Some(_) => (format!("{} ({})", finding.message, location.position()), 1),
+        None => (finding.message.clone(), location.line),
+    };
+    report(finding, description, fingerprint(&parts), &path, line)
+}
+
+/// Construct a Code Quality issue for a finding that has no location, on the first line of the bib file, as GitLab requires every issue to have a path and line
+fn issue_without_location(finding: &Finding, bib_file: &str) -> Value {
+    let path = bib_file.replace('\\', "/");
+    let fingerprint = fingerprint(&[&finding.fingerprint(), &path]);
+    report(finding, finding.message.clone(), fingerprint, &path, 1)
+}
+
+/// Code Quality issue with the given description, fingerprint, and location
+fn report(
+    finding: &Finding,
+    description: String,
+    fingerprint: String,
+    path: &str,
+    line: usize,
+) -> Value {
+    json!({
+        "description": description,
+        "check_name": finding.check.name(),
+        "fingerprint": fingerprint,
+        "severity": severity(finding.severity),
+        "location": {
+            "path": path,
+            "lines": { "begin": line },
+        },
+    })
+}
+
+/// Code Quality issues for the findings, one for each location of each finding, and one for each finding without a location
+fn issues(findings: &[Finding], bib_file: &str) -> Vec<Value> {
+    findings
+        .iter()
+        .flat_map(|finding| {
+            if finding.locations.is_empty() {
+                return vec![issue_without_location(finding, bib_file)];
+            }
+            finding
+                .locations
+                .iter()
+                .map(|location| issue(finding, location))
+                .collect()
+        })
+        .collect()
+}
+
+pub fn render(findings: &[Finding], bib_file: &str) {
+    let issues = issues(findings, bib_file);
+    println!("{}", serde_json::to_string_pretty(&issues).unwrap());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagnostics::Check;
+    use std::path::Path;
+
+    fn finding(key: &str, locations: Vec<Location>) -> Finding {
+        let mut finding = Finding::new(Check::Missing, key, format!("{key} is missing"));
+        finding.locations = locations;
+        finding
+    }
+
+    #[test]
+    fn findings_without_a_location_are_on_the_bib_file() {
+        let issues = issues(&[finding("nokey", Vec::new())], "refs/references.bib");
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0]["description"], "nokey is missing");
+        assert_eq!(
+            issues[0]["location"],
+            json!({ "path": "refs/references.bib", "lines": { "begin": 1 } })
+        );
+    }
+
+    #[test]
+    fn each_location_is_an_issue_with_a_distinct_fingerprint() {
+        let locations = vec![
+            Location::new(Path::new("document.tex"), 3, 5),
+            Location::new(Path::new("document.tex"), 3, 17),
+        ];
+        let issues = issues(&[finding("nokey", locations)], "references.bib");
+        assert_eq!(issues.len(), 2);
+        assert_eq!(issues[0]["location"]["path"], "document.tex");
+        assert_eq!(issues[0]["location"]["lines"]["begin"], 3);
+        assert_ne!(issues[0]["fingerprint"], issues[1]["fingerprint"]);
+    }
+
+    #[test]
+    fn cells_are_on_the_first_line_of_the_notebook() {
+        let mut location = Location::new(Path::new("paper.ipynb"), 2, 11);
+        location.cell = Some(3);
+        let found = issues(
+            &[finding("nokey", vec![location.clone()])],
+            "references.bib",
+        );
+        assert_eq!(found[0]["description"], "nokey is missing (cell 3, 2:11)");
+        assert_eq!(found[0]["location"]["lines"]["begin"], 1);
+
+        location.cell = Some(4);
+        let other = issues(&[finding("nokey", vec![location])], "references.bib");
+        assert_ne!(found[0]["fingerprint"], other[0]["fingerprint"]);
+    }
+}
@@ -0,0 +1,82 @@
+//! JSON and newline-delimited JSON output
+//!
+//! # Schema
+//!
+//! The schema is versioned by [`SCHEMA_VERSION`].  Fields may be added to objects without changing the version, so consumers should ignore fields they do not recognise; removing or changing the meaning of a field will increment the version.
+//!
+//! With `--format json`, a single document is written:
+//!
+//! ```json
+//! {
+//!   "version": 1,
+//!   "tool": { "name": "citati", "version": "0.3.3" },
+//!   "findings": [ <finding>, ... ]
+//! }
+//! ```
+//!
+//! With `--format ndjson`, each finding is written as a single line, with the schema version included in every line:
+//!
+//! ```json
+//! { "version": 1, <finding fields>... }
+//! ```
+//!
+//! Each finding has the following fields:
+//!
+//!   - `check` (string): name of the check that produced the finding (`"unused"`, `"missing"`, `"pages"`, `"articles"`, `"cite-commands"`, `"unused-suppression"`);
+//!   - `severity` (string): how serious the finding is (`"warning"` or `"error"`);
+//!   - `key` (string): the citation key the finding concerns;
+//!   - `message` (string): human-readable description of the problem;
+//!   - `fields` (array of strings): bib fields the finding concerns (e.g., the missing fields of an article);
+//!   - `values` (object of strings): values of the fields the finding concerns, where they are present (e.g., `{"pages": "12-34"}`);
+//!   - `locations` (array of objects): where the problem is, each with `file` (string, as given on the command line or resolved from it), `line` (one-based integer), and `column` (one-based integer, in characters), and, within a Jupyter notebook, `cell` (one-based integer, the cell within which the line and column are counted; absent elsewhere).
+
+use crate::diagnostics::Finding;
+use clap::{crate_name, crate_version};
+use serde::Serialize;
+
+/// Version of the JSON output schema
+pub const SCHEMA_VERSION: u32 = 1;
+
+#[derive(Serialize)]
+struct Tool {
+    name: &'static str,
+    version: &'static str,
+}
+
+#[derive(Serialize)]
+struct Document<'a> {
+    version: u32,
+    tool: Tool,
+    findings: &'a [Finding],
+}
+
+#[derive(Serialize)]
+struct Line<'a> {
+    version: u32,
+    #[serde(flatten)]
+    finding: &'a Finding,
+}
+
+/// Write all findings as a single JSON document
+pub fn render(findings: &[Finding]) {
+    let document = Document {
+        version: SCHEMA_VERSION,
+        tool: Tool {
+            name: crate_name!(),
+            version: crate_version!(),
+        },
+        findings,
+    };
+    println!("{}", serde_json::to_string_pretty(&document).unwrap());
+}
+
+/// Write each finding as a JSON object on its own line
+pub fn render_ndjson(findings: &[Finding]) {
+    for finding in findings {
+        let line = Line {
+            version: SCHEMA_VERSION,
+            finding,
+        };
+        println!("{}", serde_json::to_string(&line).unwrap());
+    }
+}
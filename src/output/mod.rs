@@ -0,0 +1,78 @@
+//! Render findings in various output formats
+//!
+//! Checks produce [`Finding`]s; this module decides how they are presented.  The [`Plain`](`Format::Plain`) format is the simple, line-based output citati has always had, and is used by default when standard output is not a terminal; when it is, we default to [`Pretty`](`Format::Pretty`) output.  Other formats are intended to be consumed by scripts and other tools.  The format is chosen using the `--format` option.
+
+mod github;
+mod gitlab;
+mod json;
+mod plain;
+mod pretty;
+mod sarif;
+
+use crate::diagnostics::Finding;
+use clap::ValueEnum;
+use serde::Deserialize;
+use std::{
+    env,
+    io::{self, IsTerminal},
+};
+
+/// Output formats in which findings can be rendered
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    /// One finding per line, in a short human-readable form
+    Plain,
+
+    /// Findings grouped by check and file, with a summary
+    Pretty,
+
+    /// A single JSON document containing all findings
+    Json,
+
+    /// Newline-delimited JSON: one finding per line
+    Ndjson,
+
+    /// SARIF 2.1.0 log, for code-scanning dashboards
+    Sarif,
+
+    /// GitHub Actions workflow commands, shown as annotations
+    Github,
+
+    /// GitLab Code Quality report
+    Gitlab,
+}
+
+impl Format {
+    /// Choose the default format, depending on whether we are writing to a terminal
+    pub fn detect() -> Self {
+        if io::stdout().is_terminal() {
+            Format::Pretty
+        } else {
+            Format::Plain
+        }
+    }
+}
+
+/// Whether we should colour output
+///
+/// We only use colour when writing to a terminal, and never when the [`NO_COLOR`](https://no-color.org/) environment variable is set (to a non-empty value)
+fn use_colour() -> bool {
+    let no_colour = env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+    io::stdout().is_terminal() && !no_colour
+}
+
+/// Write findings to standard output in the given format
+///
+/// Formats that require every finding to have a path (i.e., GitLab's) report findings without a location against the bib file
+pub fn render(format: Format, findings: &[Finding], bib_file: &str) {
+    match format {
+        Format::Plain => plain::render(findings),
+        Format::Pretty => pretty::render(findings, use_colour()),
+        Format::Json => json::render(findings),
+        Format::Ndjson => json::render_ndjson(findings),
+        Format::Sarif => sarif::render(findings),
+        Format::Github => github::render(findings),
+        Format::Gitlab => gitlab::render(findings, bib_file),
+    }
+}
@@ -0,0 +1,11 @@
+//! Plain, line-based output
+//!
+//! Each finding is written on its own line using its [`Display`](`std::fmt::Display`) implementation (e.g., `key (missing: volume, doi)`).
+
+use crate::diagnostics::Finding;
+
+pub fn render(findings: &[Finding]) {
+    for finding in findings {
+        println!("{finding}");
+    }
+}
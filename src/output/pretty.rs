@@ -0,0 +1,142 @@
+//! Grouped, colourised output for the terminal
+//!
+//! Findings are grouped by check and then by file, each with its location, key, and message, and the offending field values highlighted.  A summary of the number of findings of each check is written at the end.  Colour is only used when [`use_colour`](`super::use_colour`) permits it.
+
+use crate::diagnostics::{Check, Finding, Severity};
+use crate::source::Location;
+use std::collections::BTreeMap;
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const DIM: &str = "\x1b[2m";
+const RED: &str = "\x1b[31m";
+const YELLOW: &str = "\x1b[33m";
+const CYAN: &str = "\x1b[36m";
+
+/// Findings of one check, grouped by the file of each of their locations
+type FileGroups<'a> = BTreeMap<String, Vec<(&'a Finding, Option<&'a Location>)>>;
+
+/// Helper to (optionally) wrap text in ANSI escape codes
+struct Painter {
+    colour: bool,
+}
+
+impl Painter {
+    fn paint(&self, style: &str, text: &str) -> String {
+        if self.colour {
+            format!("{style}{text}{RESET}")
+        } else {
+            text.to_owned()
+        }
+    }
+
+    fn severity(&self, severity: Severity) -> &'static str {
+        match severity {
+            Severity::Warning => YELLOW,
+            Severity::Error => RED,
+        }
+    }
+}
+
+/// Pluralise the severity of a number of findings (e.g., "1 warning", "2 errors")
+fn count(n: usize, severity: Severity) -> String {
+    let plural = if n == 1 { "" } else { "s" };
+    format!("{n} {severity}{plural}")
+}
+
+/// Describe the offending fields of a finding, with their values highlighted
+fn details(painter: &Painter, finding: &Finding) -> Option<String> {
+    let style = painter.severity(finding.severity);
+    if !finding.values.is_empty() {
+        let values: Vec<String> = finding
+            .values
+            .iter()
+            .map(|(field, value)| {
+                format!("{field} = {}", painter.paint(style, &format!("{value:?}")))
+            })
+            .collect();
+        Some(values.join(", "))
+    } else if !finding.fields.is_empty() {
+        Some(format!(
+            "missing {}",
+            painter.paint(style, &finding.fields.join(", "))
+        ))
+    } else {
+        None
+    }
+}
+
+pub fn render(findings: &[Finding], colour: bool) {
+    let painter = Painter { colour };
+
+    // Group findings by check, and then by file.  Findings with many locations
+    // are listed once under each of their locations
+    let mut groups: BTreeMap<Check, FileGroups> = BTreeMap::new();
+    for finding in findings {
+        let by_file = groups.entry(finding.check).or_default();
+        if finding.locations.is_empty() {
+            by_file
+                .entry(String::new())
+                .or_default()
+                .push((finding, None));
+        }
+        for location in finding.locations.iter() {
+            let file = location.file.display().to_string();
+            by_file
+                .entry(file)
+                .or_default()
+                .push((finding, Some(location)));
+        }
+    }
+
+    for (check, by_file) in groups.iter() {
+        let n = findings.iter().filter(|f| f.check == *check).count();
+        println!(
+            "{} {}",
+            painter.paint(BOLD, check.name()),
+            painter.paint(DIM, &format!("({})", count(n, check.severity())))
+        );
+
+        for (file, entries) in by_file.iter() {
+            let file = if file.is_empty() { "<unknown>" } else { file };
+            println!("  {}", painter.paint(CYAN, file));
+
+            for (finding, location) in entries.iter() {
+                let position = location.map_or_else(String::new, Location::position);
+                println!(
+                    "    {}  {}  {}",
+                    painter.paint(DIM, &format!("{position:>8}")),
+                    painter.paint(BOLD, &finding.key),
+                    finding.message
+                );
+                if let Some(details) = details(&painter, finding) {
+                    println!("    {:>8}  {details}", "");
+                }
+            }
+        }
+        println!();
+    }
+
+    // Summarise the number of findings per check, aligned on the longest name
+    // of any check
+    println!("{}", painter.paint(BOLD, "Summary"));
+    let width = Check::ALL
+        .iter()
+        .map(|check| check.name().chars().count())
+        .max()
+        .unwrap_or(0);
+    for check in Check::ALL.iter() {
+        let n = findings.iter().filter(|f| f.check == *check).count();
+        if n > 0 {
+            let summary = count(n, check.severity());
+            println!(
+                "  {:<width$} {}",
+                check.name(),
+                painter.paint(painter.severity(check.severity()), &summary)
+            );
+        }
+    }
+    if findings.is_empty() {
+        println!("  No problems found");
+    }
+}
@@ -0,0 +1,84 @@
+//! SARIF output for code-scanning tools
+//!
+//! Findings are written as a [SARIF 2.1.0](https://docs.oasis-open.org/sarif/sarif/v2.1.0/sarif-v2.1.0.html) log with a single run.  Each [`Check`] is described as a rule in the tool's driver (whether or not it produced any results), and each finding becomes a result with physical locations pointing into the `.tex` and `.bib` files, so that forges can display them inline.
+
+use crate::diagnostics::{Check, Finding, Severity};
+use crate::source::Location;
+use clap::{crate_name, crate_version};
+use serde_json::{json, Value};
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+const INFORMATION_URI: &str = "https://github.com/jakewilliami/citati";
+
+/// SARIF level corresponding to a finding's severity
+fn level(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Warning => "warning",
+        Severity::Error => "error",
+    }
+}
+
+/// SARIF rule metadata describing a check
+fn rule(check: Check) -> Value {
+    json!({
+        "id": check.name(),
+        "shortDescription": { "text": check.description() },
+        "defaultConfiguration": { "level": level(check.severity()) },
+    })
+}
+
+/// SARIF physical location of a finding
+///
+/// URIs are relative references using forward slashes, as required by the specification.  Lines within a cell of a Jupyter notebook are not lines of the notebook file, so such locations have no region, and give their position within the cell as a message instead
+fn physical_location(location: &Location) -> Value {
+    let uri = location.file.to_string_lossy().replace('\\', "/");
+    if location.cell.is_some() {
+        return json!({
+            "physicalLocation": {
+                "artifactLocation": { "uri": uri },
+            },
+            "message": { "text": location.position() },
+        });
+    }
+    json!({
+        "physicalLocation": {
+            "artifactLocation": { "uri": uri },
+            "region": {
+                "startLine": location.line,
+                "startColumn": location.column,
+            },
+        },
+    })
+}
+
+fn result(finding: &Finding) -> Value {
+    let rule_index = Check::ALL.iter().position(|c| *c == finding.check).unwrap();
+    json!({
+        "ruleId": finding.check.name(),
+        "ruleIndex": rule_index,
+        "level": level(finding.severity),
+        "message": { "text": finding.message },
+        "locations": finding.locations.iter().map(physical_location).collect::<Vec<_>>(),
+        "properties": { "key": finding.key },
+    })
+}
+
+pub fn render(findings: &[Finding]) {
+    let log = json!({
+        "$schema": SARIF_SCHEMA,
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": crate_name!(),
+                    "version": crate_version!(),
+                    "informationUri": INFORMATION_URI,
+                    "rules": Check::ALL.iter().copied().map(rule).collect::<Vec<_>>(),
+                },
+            },
+            "columnKind": "unicodeCodePoints",
+            "results": findings.iter().map(result).collect::<Vec<_>>(),
+        }],
+    });
+    println!("{}", serde_json::to_string_pretty(&log).unwrap());
+}
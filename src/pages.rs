@@ -1,43 +1,135 @@
 //! Check formatting of `pages` field in bibliography
 //!
-//! Check all bibliography entries have correct formatting of pages; that is, two numbers separated by an en dash.  This module contains the logic for this functionality, which can be accessed using the [`--pages`](`crate::Group::pages`) option.  Unfortunately, some journals will have non-standard page numbering, so this will produce some false postiives.
+//! Check all bibliography entries have correct formatting of pages; that is, two numbers separated by an en dash.  This module contains the logic for this functionality, which can be accessed using the `--pages` option of `citati check`.  Simple page ranges with the wrong separator (e.g., `12-34`, `12 - 34`, or `12—34`) can be rewritten in place using its `--fix` option.  Unfortunately, some journals will have non-standard page numbering, so this will produce some false postiives; these can be silenced with a `% citati-ignore: pages` comment before the entry (see [`directive`](`crate::source::directive`)).
 
 use super::{
     citations::{gather_citations, Citations},
-    source::{Bib, BibCitation, CitationSource},
+    diagnostics::{Check, Finding},
+    error::{Error, Result},
+    source::{bib, Bib, BibCitation, CitationSource},
+    suppress::Suppressions,
 };
 use lazy_static::lazy_static;
 use regex::Regex;
-use std::char;
+use similar::TextDiff;
+use std::{char, collections::HashSet};
 
 lazy_static! {
     static ref EN_DASH_CHAR: char = char::from_u32(0x2013).unwrap();
     static ref BIB_PAGES_RE: Regex =
         Regex::new(&format!(r"^\d+(--|{})\d+$", *EN_DASH_CHAR)).unwrap();
+
+    /// Page ranges that we know how to fix: two numbers separated by any number of hyphens or dashes, with optional whitespace
+    static ref FIXABLE_PAGES_RE: Regex =
+        Regex::new(r"^\s*(\d+)\s*(?:-+|[\u{2010}-\u{2015}\u{2212}])\s*(\d+)\s*$").unwrap();
 }
 
-/// Defines formatting for reporting citations from bib file with malformatted `pages` field
-fn report_pages(citation: &BibCitation) -> String {
-    format!(
-        "{} ({:?})",
-        citation.key,
-        citation.get("pages").unwrap_or_default()
-    )
+/// Defines the finding reported for citations from bib file with malformatted `pages` field
+fn report_pages(citation: &BibCitation) -> Finding {
+    let pages = citation.get("pages").unwrap_or_default();
+    let mut finding = Finding::new(
+        Check::Pages,
+        &citation.key,
+        format!("Page range {pages:?} should be two numbers separated by an en dash (--)"),
+    );
+    finding.fields.push("pages".to_owned());
+    finding.values.insert("pages".to_owned(), pages);
+    finding.locations.push(citation.field_location("pages"));
+    finding
 }
 
 /// List (in alohabetical order) any citations from the bibliography that have malformatted `pages` field
 ///
 /// Pages should typically be separated by an en dash
-pub fn check_bib_pages(bib_file: &str) {
-    let src = CitationSource::from_bib(bib_file);
-    let bib_entries = gather_citations::<Citations<Bib>>(&src);
+pub fn check_bib_pages(src: &CitationSource) -> Result<Vec<Finding>> {
+    let bib_entries = gather_citations::<Citations<Bib>>(src)?;
     let citations_with_bad_bib_pages = bib_entries.filter(|c| {
         // TODO: use c.entry.pages() rather than c.get("pages")?
         c.get("pages")
-            .map_or(false, |pages| !BIB_PAGES_RE.is_match(&pages))
+            .is_some_and(|pages| !BIB_PAGES_RE.is_match(&pages))
     });
 
-    for citation in citations_with_bad_bib_pages.list_sorted() {
-        println!("{}", report_pages(citation));
+    Ok(citations_with_bad_bib_pages
+        .list_sorted()
+        .into_iter()
+        .map(report_pages)
+        .collect())
+}
+
+/// Correctly formatted page range for the given value of `pages`, if it is malformatted and we know how to fix it
+fn fixed_pages(pages: &str) -> Option<String> {
+    if BIB_PAGES_RE.is_match(pages) {
+        return None;
     }
+
+    FIXABLE_PAGES_RE
+        .captures(pages)
+        .map(|caps| format!("{}--{}", &caps[1], &caps[2]))
+}
+
+/// Rewrite malformatted `pages` fields in the bibliography file to use `--`
+///
+/// Only the values of the `pages` fields are changed; the rest of the file is preserved byte-for-byte.  Entries whose finding is silenced by one of the given `suppressions` are left as they are.  If `dry_run` is set, a unified diff of the changes is printed instead of writing them (which is required if the bibliography was given in memory rather than read from a file).  Returns whether any changes were (or would be) made
+pub fn fix_bib_pages(
+    src: &CitationSource,
+    mut suppressions: Suppressions,
+    dry_run: bool,
+) -> Result<bool> {
+    let bib_file = src.bib_path()?;
+    if src.bib_src.is_some() && !dry_run {
+        return Err(Error::Invalid(format!(
+            "Cannot fix bibliography {bib_file:?} as it was not read from a file; use --dry-run to show fixes"
+        )));
+    }
+    let mut document =
+        bib::parse_bib_document_from_str(src.bib_contents()?.into_owned(), bib_file)?;
+    let original = document.src().to_owned();
+
+    // Only fix entries whose finding would be reported
+    let reported: HashSet<String> = suppressions
+        .apply(check_bib_pages(src)?)
+        .into_iter()
+        .map(|finding| finding.key)
+        .collect();
+
+    // Find fixes before making them, as edits invalidate borrows of the document
+    let fixes: Vec<(String, String, String)> = document
+        .entries()
+        .iter()
+        .filter(|entry| reported.contains(document.key(entry)))
+        .filter_map(|entry| {
+            let field = document.field(entry, "pages")?;
+            let pages = document.text(&document.content(field)?);
+            let fixed = fixed_pages(pages)?;
+            Some((document.key(entry).to_owned(), pages.to_owned(), fixed))
+        })
+        .collect();
+
+    if fixes.is_empty() {
+        return Ok(false);
+    }
+
+    for (key, _, pages) in fixes.iter() {
+        document.set_field(key, "pages", pages).map_err(|e| {
+            Error::Invalid(format!(
+                "Could not fix pages of {key} in bibliography file {bib_file:?}: {e}"
+            ))
+        })?;
+    }
+
+    if dry_run {
+        let diff = TextDiff::from_lines(original.as_str(), document.src());
+        print!(
+            "{}",
+            diff.unified_diff()
+                .header(&format!("a/{bib_file}"), &format!("b/{bib_file}"))
+        );
+    } else {
+        for (key, old, new) in fixes.iter() {
+            eprintln!("[INFO] Fixed pages of {key}: {old:?} -> {new:?}");
+        }
+        bib::write_bib_document_to_file(bib_file, &document)?;
+    }
+
+    Ok(true)
 }
@@ -0,0 +1,217 @@
+//! Check many documents against one bibliography
+//!
+//! A project (e.g., a thesis, or a collection of papers sharing a bibliography) may have many documents, some of which are not reachable from any other through `\input{}`.  Documents can be given as files, as glob patterns (e.g., `papers/*.tex`), or as directories, within which we detect root documents: LaTeX files containing `\documentclass`, and those named by `% !TeX root` magic comments (see [`magic_root`](`crate::source::latex::magic_root`)), where files with such comments are not themselves roots.  Citations are merged across all documents, so that an entry is only unused if no document cites it, and a key is missing wherever any document cites it.
+
+use crate::{
+    citations::{gather_citations, HollowCitations},
+    cite_commands,
+    diagnostics::Finding,
+    error::{Error, Result},
+    fields, missing, pages,
+    source::{latex, CitationSource, Document, Markup},
+    suppress::Suppressions,
+    unused,
+};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Characters that make a document given on the command line a glob pattern
+const GLOB_CHARS: &[char] = &['*', '?', '['];
+
+/// Expand the documents given (as files, glob patterns, or directories) into the paths of the documents to check, in the order in which they are given
+///
+/// Files given explicitly are kept as they are, whether or not they exist, so that they are reported as they would be were they the only document, unless they name their root document with a magic comment (see [`root_document`](`crate::source::latex::root_document`)), in which case the root is checked in their place, as it is for files matching a glob pattern.  Each document is only checked once, however many times (and by however many paths, e.g., `main.tex` and `./main.tex`) it is given
+pub fn expand_documents(documents: &[String]) -> Result<Vec<String>> {
+    let mut paths = Vec::new();
+    let mut seen = Vec::new();
+    for document in documents {
+        let path = Path::new(document);
+        let expanded = if path.is_dir() {
+            let roots = find_roots(path);
+            if roots.is_empty() {
+                eprintln!("[WARN] Could not find any root documents in directory {document:?}");
+            }
+            roots
+        } else if document.contains(GLOB_CHARS) {
+            let matches = glob::glob(document)
+                .map_err(|e| Error::Invalid(format!("Invalid pattern {document:?}: {e}")))?
+                .filter_map(std::result::Result::ok)
+                .filter(|path| path.is_file())
+                .map(|path| root(&path))
+                .collect::<Vec<_>>();
+            if matches.is_empty() {
+                eprintln!("[WARN] No files match pattern {document:?}");
+            }
+            matches
+        } else {
+            let root = root(path);
+            if root != path {
+                eprintln!(
+                    "[INFO] Checking {:?}, the root document of {document:?}",
+                    root.display().to_string()
+                );
+            }
+            vec![root]
+        };
+
+        for path in expanded {
+            let normalised = latex::normalise(&path);
+            if !seen.contains(&normalised) {
+                seen.push(normalised);
+                paths.push(path.display().to_string());
+            }
+        }
+    }
+
+    if paths.is_empty() {
+        return Err(Error::Invalid("No documents to check".to_owned()));
+    }
+    Ok(paths)
+}
+
+/// Root document of the document, which is the document itself unless it is a LaTeX file naming its root with a magic comment
+fn root(document: &Path) -> PathBuf {
+    if Markup::from_path(document) == Markup::LaTeX {
+        latex::root_document(document)
+    } else {
+        document.to_path_buf()
+    }
+}
+
+/// Find the root documents of the LaTeX files within the directory (and its subdirectories, other than hidden ones), sorted by path
+pub fn find_roots(dir: &Path) -> Vec<PathBuf> {
+    let mut roots = Vec::new();
+    for file in latex_files(dir) {
+        let Ok(src) = fs::read_to_string(&file) else {
+            continue;
+        };
+        let root = latex::root_document(&file);
+        if root != file {
+            roots.push(root);
+        } else if latex::magic_root(&src).is_none() && has_documentclass(&src) {
+            roots.push(latex::normalise(&file));
+        }
+    }
+
+    roots.sort();
+    roots.dedup();
+    roots
+}
+
+/// Paths of the LaTeX files within the directory and its (non-hidden) subdirectories
+fn latex_files(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut files = Vec::new();
+    for entry in entries.filter_map(std::result::Result::ok) {
+        let path = entry.path();
+        if entry.file_name().to_string_lossy().starts_with('.') {
+            continue;
+        }
+        if path.is_dir() {
+            files.extend(latex_files(&path));
+        } else if path.extension().is_some_and(|ext| ext == "tex") {
+            files.push(path);
+        }
+    }
+    files
+}
+
+/// Whether the LaTeX source declares a document class outside of comments, and so is a root document
+fn has_documentclass(src: &str) -> bool {
+    src.lines().any(|line| {
+        let code = line.split('%').next().unwrap_or("");
+        code.contains("\\documentclass")
+    })
+}
+
+/// Bib entries that are not cited by any of the documents
+///
+/// The citations of every document are gathered together and compared with the bibliography once, so that an entry is only unused if no document cites it.  Entries of bibliographies local to refsections are only used if they are cited within those refsections, so these are checked for each document, and merged as for [`missing_citations`]
+pub fn unused_citations(srcs: &[CitationSource]) -> Result<Vec<Finding>> {
+    let Some(src) = srcs.first() else {
+        return Ok(Vec::new());
+    };
+    let mut citations = HollowCitations::<Document>::new();
+    for src in srcs {
+        citations.extend(gather_citations::<HollowCitations<Document>>(src)?);
+    }
+    let mut findings = unused::unused_globally(src, citations)?;
+
+    let local = srcs
+        .iter()
+        .map(unused::unused_local_citations)
+        .collect::<Result<Vec<_>>>()?;
+    findings.extend(merge(local.into_iter().flatten()));
+    Ok(findings)
+}
+
+/// Citations in any of the documents that are not defined in the bibliography
+///
+/// Findings for the same key in many documents are merged, and a citation in a file included by many documents is only reported once
+pub fn missing_citations(srcs: &[CitationSource]) -> Result<Vec<Finding>> {
+    let findings = srcs
+        .iter()
+        .map(missing::missing_citations)
+        .collect::<Result<Vec<_>>>()?;
+    Ok(merge(findings.into_iter().flatten()))
+}
+
+/// Citation commands within parentheses in any of the documents that print their own parentheses
+///
+/// As for [`missing_citations`], findings for the same command and key in many documents are merged
+pub fn cite_commands(srcs: &[CitationSource]) -> Result<Vec<Finding>> {
+    let findings = srcs
+        .iter()
+        .map(cite_commands::check_cite_commands)
+        .collect::<Result<Vec<_>>>()?;
+    Ok(merge(findings.into_iter().flatten()))
+}
+
+/// Suppression directives in any of the documents, or in the bibliography, that do not match any finding
+///
+/// This is [`suppress::unused_suppressions`](`crate::suppress::unused_suppressions`) for many documents
+pub fn unused_suppressions(srcs: &[CitationSource]) -> Result<Vec<Finding>> {
+    let mut findings = Vec::new();
+    findings.extend(unused_citations(srcs)?);
+    findings.extend(missing_citations(srcs)?);
+    findings.extend(cite_commands(srcs)?);
+    if let Some(src) = srcs.first() {
+        findings.extend(pages::check_bib_pages(src)?);
+        findings.extend(fields::article::check_article_fields(src)?);
+    }
+
+    let mut suppressions = Suppressions::gather_all(srcs)?;
+    suppressions.apply(findings);
+    Ok(suppressions.unused())
+}
+
+/// Whether two findings report the same problem, ignoring their locations
+fn same_finding(a: &Finding, b: &Finding) -> bool {
+    a.check == b.check && a.key == b.key && a.message == b.message
+}
+
+/// Merge findings of the same problem, keeping the first occurrence of each location
+fn merge(findings: impl Iterator<Item = Finding>) -> Vec<Finding> {
+    let mut merged: Vec<Finding> = Vec::new();
+    for finding in findings {
+        match merged
+            .iter_mut()
+            .find(|existing| same_finding(existing, &finding))
+        {
+            Some(existing) => {
+                for location in finding.locations {
+                    if !existing.locations.contains(&location) {
+                        existing.locations.push(location);
+                    }
+                }
+            }
+            None => merged.push(finding),
+        }
+    }
+    merged
+}
@@ -0,0 +1,177 @@
+//! Rename a citation key throughout the LaTeX source and bibliography
+//!
+//! Renaming a key by hand means editing its bib entry and every citation of it, which may be spread across several files included with `\input`.  This module renames the key of the bib entry (using the lossless [`cst`](`crate::source::bib::cst`) layer, so the rest of the file is untouched), any `crossref` or `xdata` fields of other entries that refer to it, and each citation of it, at the locations found by the LaTeX [`Lexer`](`crate::source::latex::Lexer`).  This functionality can be accessed using the `citati rename` subcommand.
+
+use super::{
+    citations::{gather_citations, Citations},
+    error::{Error, Result},
+    source::{bib, CitationSource, LaTeX, LineIndex},
+};
+use similar::TextDiff;
+use std::{collections::BTreeMap, fs, path::PathBuf};
+
+/// Characters that cannot appear in a citation key, as they delimit keys or fields in LaTeX source or bibliography files
+const INVALID_KEY_CHARS: &[char] = &[',', '{', '}', '%', '#', '(', ')', '"', '='];
+
+/// Fields whose values are the keys of other entries, separated by commas, which must be renamed with those entries
+const KEY_FIELDS: [&str; 2] = ["crossref", "xdata"];
+
+/// Check that the key can be written to LaTeX source and bibliography files as-is
+fn validate_key(key: &str) -> Result<()> {
+    if key.is_empty() {
+        return Err(Error::Invalid("Citation keys cannot be empty".to_owned()));
+    }
+    if let Some(c) = key
+        .chars()
+        .find(|c| c.is_whitespace() || INVALID_KEY_CHARS.contains(c))
+    {
+        return Err(Error::Invalid(format!(
+            "Citation key {key:?} cannot contain {c:?}"
+        )));
+    }
+    Ok(())
+}
+
+/// Value of a comma-separated list of keys with any that are `old` replaced by `new`, keeping the whitespace around each, or `None` if there are none to replace
+fn rename_in_list(value: &str, old: &str, new: &str) -> Option<String> {
+    let parts: Vec<&str> = value.split(',').collect();
+    if !parts.iter().any(|part| part.trim() == old) {
+        return None;
+    }
+    let renamed: Vec<String> = parts
+        .into_iter()
+        .map(|part| {
+            if part.trim() == old {
+                part.replacen(old, new, 1)
+            } else {
+                part.to_owned()
+            }
+        })
+        .collect();
+    Some(renamed.join(","))
+}
+
+/// Print a unified diff of the changes to the given file
+fn print_diff(file: &str, old: &str, new: &str) {
+    let diff = TextDiff::from_lines(old, new);
+    print!(
+        "{}",
+        diff.unified_diff()
+            .header(&format!("a/{file}"), &format!("b/{file}"))
+    );
+}
+
+/// Rename the citation key `old` to `new` in the bib file and all LaTeX source files
+///
+/// If `dry_run` is set, a unified diff of the changes is printed instead of writing them
+pub fn rename_citation(
+    latex_file: &str,
+    bib_file: &str,
+    old: &str,
+    new: &str,
+    dry_run: bool,
+) -> Result<()> {
+    validate_key(new)?;
+    let mut document = bib::parse_bib_document_from_file(bib_file)?;
+    let bib_src = document.src().to_owned();
+    if document.entries().iter().any(|e| document.key(e) == new) {
+        return Err(Error::Invalid(format!(
+            "An entry with key {new:?} already exists in bibliography file {bib_file:?}"
+        )));
+    }
+    let renamed = document.rename_key(old, new).map_err(|e| {
+        Error::Invalid(format!(
+            "Could not rename {old:?} to {new:?} in bibliography file {bib_file:?}: {e}"
+        ))
+    })?;
+    if !renamed {
+        return Err(Error::Invalid(format!(
+            "No entry with key {old:?} in bibliography file {bib_file:?}"
+        )));
+    }
+
+    // Find references to the key before renaming them, as edits invalidate borrows of the document
+    let references: Vec<(String, &str, String)> = document
+        .entries()
+        .iter()
+        .flat_map(|entry| {
+            let document = &document;
+            KEY_FIELDS.into_iter().filter_map(move |name| {
+                let field = document.field(entry, name)?;
+                let value = document.text(&document.content(field)?);
+                let renamed = rename_in_list(value, old, new)?;
+                Some((document.key(entry).to_owned(), name, renamed))
+            })
+        })
+        .collect();
+    for (key, name, value) in references.iter() {
+        document.set_field(key, name, value).map_err(|e| {
+            Error::Invalid(format!(
+                "Could not rename {old:?} to {new:?} in {name} of {key} in bibliography file {bib_file:?}: {e}"
+            ))
+        })?;
+    }
+
+    // Group the locations of citations by file, so that we only read and write each file once
+    let src = CitationSource::new(latex_file, bib_file);
+    let citations = gather_citations::<Citations<LaTeX>>(&src)?;
+    let mut files = BTreeMap::<PathBuf, Vec<(usize, usize)>>::new();
+    for location in citations.get(old).map_or(&[][..], |c| &c.locations) {
+        files
+            .entry(location.file.clone())
+            .or_default()
+            .push((location.line, location.column));
+    }
+
+    let mut latex_edits = Vec::new();
+    for (file, positions) in files {
+        let latex_src = fs::read_to_string(&file)
+            .map_err(Error::io(format!("Could not read LaTeX file {file:?}")))?;
+        let index = LineIndex::new(&latex_src);
+        let mut offsets: Vec<usize> = positions
+            .into_iter()
+            .map(|(line, column)| {
+                index
+                    .offset(line, column)
+                    .filter(|&offset| latex_src[offset..].starts_with(old))
+                    .ok_or_else(|| {
+                        Error::Invalid(format!(
+                            "Could not find citation {old:?} at {}:{line}:{column}",
+                            file.display()
+                        ))
+                    })
+            })
+            .collect::<Result<_>>()?;
+
+        // Replace from the end of the file, so that earlier offsets remain valid
+        offsets.sort_unstable();
+        let mut renamed = latex_src.clone();
+        for offset in offsets.iter().rev() {
+            renamed.replace_range(*offset..*offset + old.len(), new);
+        }
+        latex_edits.push((file, latex_src, renamed, offsets.len()));
+    }
+
+    if dry_run {
+        print_diff(bib_file, &bib_src, document.src());
+        for (file, latex_src, renamed, _) in latex_edits.iter() {
+            print_diff(&file.display().to_string(), latex_src, renamed);
+        }
+        return Ok(());
+    }
+
+    bib::write_bib_document_to_file(bib_file, &document)?;
+    eprintln!("[INFO] Renamed {old} to {new} in bibliography file {bib_file:?}");
+    for (key, name, _) in references.iter() {
+        eprintln!(
+            "[INFO] Renamed {old} to {new} in {name} of {key} in bibliography file {bib_file:?}"
+        );
+    }
+    for (file, _, renamed, n) in latex_edits {
+        fs::write(&file, renamed)
+            .map_err(Error::io(format!("Could not write LaTeX file {file:?}")))?;
+        eprintln!("[INFO] Renamed {n} citation(s) of {old} to {new} in LaTeX file {file:?}");
+    }
+
+    Ok(())
+}
@@ -0,0 +1,75 @@
+//! Read citations from auxiliary (.aux) files written by LaTeX
+//!
+//! After a build, LaTeX records every citation in the document's `.aux` file: BibTeX's `\citation{key}`, and biblatex's `\abx@aux@cite{key}` (or `\abx@aux@cite{refsection}{key}` in recent versions).  As these are written by LaTeX itself, every macro, `\include`, and conditional has already been resolved, so they are the ground truth of what the document cites, unlike the [`Lexer`](`super::latex::Lexer`), which only approximates it.  Each `\include`d file has its own `.aux` file, which is read from the main one via `\@input{file.aux}`.  Citations are reported at their locations in the `.aux` files.
+
+use super::{latex::CitationToken, location::Location};
+use crate::{Error, Result};
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+lazy_static! {
+    static ref AUX_CITATION_RE: Regex = Regex::new(r"^\\citation\{([^}]*)\}").unwrap();
+    static ref AUX_ABX_CITE_RE: Regex =
+        Regex::new(r"^\\abx@aux@cite(?:\{(\d+)\})?\{([^}]*)\}").unwrap();
+    static ref AUX_INPUT_RE: Regex = Regex::new(r"^\\@input\{([^}]*)\}").unwrap();
+}
+
+/// Parse the citations recorded in the `.aux` file, and in any `.aux` files it includes
+pub fn parse_aux_citations_from_file(aux_file: &str) -> Result<Vec<CitationToken>> {
+    let mut citations = Vec::new();
+    parse_aux_file(Path::new(aux_file), &mut citations)?;
+    Ok(citations)
+}
+
+fn parse_aux_file(aux_file: &Path, citations: &mut Vec<CitationToken>) -> Result<()> {
+    let src = fs::read_to_string(aux_file).map_err(Error::io(format!(
+        "Could not read auxiliary file {aux_file:?}"
+    )))?;
+
+    for (i, line) in src.lines().enumerate() {
+        // Included `.aux` files are written relative to the directory of the main one
+        if let Some(caps) = AUX_INPUT_RE.captures(line) {
+            let included = aux_file
+                .parent()
+                .unwrap_or(Path::new(""))
+                .join(PathBuf::from(&caps[1]));
+            if included.exists() {
+                parse_aux_file(&included, citations)?;
+            }
+            continue;
+        }
+
+        // Only biblatex records the refsection of each citation
+        let (cite_cmd, keys, refsection) = if let Some(caps) = AUX_CITATION_RE.captures(line) {
+            ("citation", caps.get(1).unwrap(), 0)
+        } else if let Some(caps) = AUX_ABX_CITE_RE.captures(line) {
+            let refsection = caps.get(1).map_or(0, |m| m.as_str().parse().unwrap_or(0));
+            ("abx@aux@cite", caps.get(2).unwrap(), refsection)
+        } else {
+            continue;
+        };
+
+        let mut offset = keys.start();
+        for key in keys.as_str().split(',') {
+            let trimmed = key.trim();
+            if !trimmed.is_empty() {
+                let start = offset + key.find(trimmed).unwrap();
+                let column = line[..start].chars().count() + 1;
+                citations.push(CitationToken {
+                    key: trimmed.to_owned(),
+                    cite_cmd: cite_cmd.to_owned(),
+                    location: Location::new(aux_file, i + 1, column),
+                    refsection,
+                    refsegment: 0,
+                });
+            }
+            offset += key.len() + 1;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,164 @@
+//! Read biblatex control (.bcf) files
+//!
+//! When a document using biblatex is built, LaTeX writes a `.bcf` control file for biber, which is XML listing the bibliography files (datasources) and the keys cited in each refsection (including those cited with `\nocite`).  Reading this file tells us exactly what biber sees, without having to interpret the LaTeX source.  See §4.5 of the biber manual for a description of the format.
+
+use super::{
+    latex::{CitationToken, Refsection},
+    location::{LineIndex, Location},
+};
+use crate::{Error, Result};
+use roxmltree::{Document, Node};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// A bibliography file listed in a refsection of the control file
+#[derive(Clone, Debug)]
+pub struct Datasource {
+    /// Refsection in which the datasource is used
+    pub section: usize,
+
+    /// Path of the datasource, relative to the control file
+    pub path: PathBuf,
+}
+
+/// A key cited in a refsection of the control file
+#[derive(Clone, Debug)]
+pub struct Citekey {
+    pub key: String,
+
+    /// Whether the key was only cited with `\nocite`
+    pub nocite: bool,
+    pub location: Location,
+}
+
+/// The keys cited in a refsection of the control file
+#[derive(Clone, Debug)]
+pub struct Section {
+    pub number: usize,
+    pub citekeys: Vec<Citekey>,
+}
+
+/// Contents of a biblatex control file
+#[derive(Clone, Debug)]
+pub struct ControlFile {
+    pub datasources: Vec<Datasource>,
+    pub sections: Vec<Section>,
+}
+
+impl ControlFile {
+    /// Every key cited in the control file, in the order in which they appear
+    ///
+    /// Keys cited with `\nocite` are given the citation command `nocite`, and all others `cite`, as biblatex does not record the actual command used
+    pub fn citations(&self) -> Vec<CitationToken> {
+        self.sections
+            .iter()
+            .flat_map(|section| {
+                section.citekeys.iter().map(|citekey| CitationToken {
+                    key: citekey.key.clone(),
+                    cite_cmd: if citekey.nocite { "nocite" } else { "cite" }.to_owned(),
+                    location: citekey.location.clone(),
+                    refsection: section.number,
+                    refsegment: 0,
+                })
+            })
+            .collect()
+    }
+
+    /// The datasources of each refsection
+    ///
+    /// Datasources of refsection 0 are those given globally (e.g., with `\addbibresource`)
+    pub fn refsections(&self) -> Vec<Refsection> {
+        let mut refsections: Vec<Refsection> = Vec::new();
+        for datasource in self.datasources.iter() {
+            let path = datasource.path.clone();
+            match refsections
+                .iter_mut()
+                .find(|refsection| refsection.number == datasource.section)
+            {
+                Some(refsection) => refsection.resources.push(path),
+                None => refsections.push(Refsection {
+                    number: datasource.section,
+                    resources: vec![path],
+                }),
+            }
+        }
+        refsections
+    }
+}
+
+/// Get the value of an attribute of a node, ignoring its namespace
+fn attribute<'a>(node: &Node<'a, '_>, name: &str) -> Option<&'a str> {
+    node.attributes()
+        .find(|attr| attr.name() == name)
+        .map(|attr| attr.value())
+}
+
+/// Get the numeric value of an attribute of a node, which defaults to zero
+fn number(node: &Node, name: &str) -> usize {
+    attribute(node, name)
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(0)
+}
+
+/// Parse the biblatex control file
+pub fn parse_bcf_from_file(bcf_file: &str) -> Result<ControlFile> {
+    let src = fs::read_to_string(bcf_file).map_err(Error::io(format!(
+        "Could not read control file {bcf_file:?}"
+    )))?;
+    parse_bcf_from_str(&src, bcf_file)
+}
+
+/// Parse biblatex control file source, with a (possibly virtual) path `bcf_file` at which locations are reported
+pub fn parse_bcf_from_str(src: &str, bcf_file: &str) -> Result<ControlFile> {
+    let path = Path::new(bcf_file);
+    let document = Document::parse(src)
+        .map_err(|e| Error::Parse(format!("Could not parse control file {bcf_file:?}: {e}")))?;
+    let index = LineIndex::new(src);
+    let base_path = path.parent().unwrap_or(Path::new(""));
+
+    let mut control_file = ControlFile {
+        datasources: Vec::new(),
+        sections: Vec::new(),
+    };
+
+    for node in document.root_element().children().filter(Node::is_element) {
+        match node.tag_name().name() {
+            "bibdata" => {
+                let section = number(&node, "section");
+                for datasource in node.children().filter(|n| n.has_tag_name("datasource")) {
+                    let Some(text) = datasource.text() else {
+                        continue;
+                    };
+                    control_file.datasources.push(Datasource {
+                        section,
+                        path: base_path.join(text.trim()),
+                    });
+                }
+            }
+            "section" => {
+                let citekeys = node
+                    .children()
+                    .filter(|n| n.has_tag_name("citekey"))
+                    .filter_map(|citekey| {
+                        let key = citekey.text()?.trim().to_owned();
+                        let start = citekey.first_child()?.range().start;
+                        Some(Citekey {
+                            key,
+                            nocite: attribute(&citekey, "nocite") == Some("1"),
+                            location: index.location(path, start),
+                        })
+                    })
+                    .collect();
+                control_file.sections.push(Section {
+                    number: number(&node, "number"),
+                    citekeys,
+                });
+            }
+            _ => {}
+        }
+    }
+
+    Ok(control_file)
+}
@@ -0,0 +1,636 @@
+//! Lossless, concrete syntax of bibliography source
+//!
+//! Parsing through [`biblatex::Bibliography`] loses whitespace, comments, and field order, so it cannot be used to edit bibliography files.  Instead, this module parses the source into a [`Document`] which keeps the source text as-is, along with the byte spans of each entry, its key, and its fields' names and values.  Targeted edits (setting, removing, or renaming a field; renaming a key; deleting an entry) only splice the relevant span of the source, so that serialising the document leaves every untouched region of the file byte-for-byte identical.
+//!
+//! The parser is deliberately lenient: anything outside of an entry is kept as-is, `%` comments are permitted between fields, and `@comment`, `@string`, and `@preamble` blocks are preserved but not exposed as entries.
+
+use std::{error::Error, fmt, ops::Range};
+
+/// A field of an entry, e.g., `pages = {12--34}`
+#[derive(Clone, Debug)]
+pub struct FieldNode {
+    /// From the start of the name to the end of the value (not including any trailing comma)
+    pub span: Range<usize>,
+    pub name: Range<usize>,
+
+    /// The value including its delimiters (and any `#` concatenations)
+    pub value: Range<usize>,
+
+    /// Offset of the comma following the field, if any
+    pub comma: Option<usize>,
+}
+
+/// An entry, e.g., `@article{key, ...}`
+#[derive(Clone, Debug)]
+pub struct EntryNode {
+    /// From the `@` to the closing delimiter (inclusive)
+    pub span: Range<usize>,
+    pub kind: Range<usize>,
+    pub key: Range<usize>,
+
+    /// Offset of the comma following the key, if any
+    pub key_comma: Option<usize>,
+    pub fields: Vec<FieldNode>,
+
+    /// Offset of the closing delimiter
+    pub close: usize,
+}
+
+/// Error encountered when parsing bibliography source, or when an edit would not preserve its syntax
+#[derive(Debug)]
+pub struct SyntaxError {
+    /// Byte offset in the source at which the error occurred
+    pub offset: usize,
+    pub message: String,
+}
+
+impl fmt::Display for SyntaxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (at byte {})", self.message, self.offset)
+    }
+}
+
+impl Error for SyntaxError {}
+
+/// Bibliography source, along with the spans of its entries
+pub struct Document {
+    src: String,
+    entries: Vec<EntryNode>,
+}
+
+/// Hand-written recursive descent parser over the source
+///
+/// All delimiters are ASCII, so we can scan bytes and still only ever slice the source at character boundaries
+struct Parser<'a> {
+    src: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(src: &'a str, pos: usize) -> Self {
+        Self {
+            src: src.as_bytes(),
+            pos,
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.src.get(self.pos).copied()
+    }
+
+    fn error(&self, message: &str) -> SyntaxError {
+        SyntaxError {
+            offset: self.pos,
+            message: message.to_owned(),
+        }
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), SyntaxError> {
+        if self.peek() == Some(byte) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected {:?}", byte as char)))
+        }
+    }
+
+    fn skip_line(&mut self) {
+        while let Some(b) = self.peek() {
+            self.pos += 1;
+            if b == b'\n' {
+                break;
+            }
+        }
+    }
+
+    /// Skip whitespace and `%` comments
+    fn skip_trivia(&mut self) {
+        while let Some(b) = self.peek() {
+            if b.is_ascii_whitespace() {
+                self.pos += 1;
+            } else if b == b'%' {
+                self.skip_line();
+            } else {
+                break;
+            }
+        }
+    }
+
+    /// Read an identifier (entry type or field name)
+    fn ident(&mut self) -> Range<usize> {
+        let start = self.pos;
+        while let Some(b) = self.peek() {
+            if b.is_ascii_whitespace() || b"{}()\"#%=,@".contains(&b) {
+                break;
+            }
+            self.pos += 1;
+        }
+        start..self.pos
+    }
+
+    /// Skip a balanced group starting at the current (opening) delimiter
+    fn balanced(&mut self, open: u8, close: u8) -> Result<(), SyntaxError> {
+        let start = self.pos;
+        let mut depth = 0;
+        while let Some(b) = self.peek() {
+            self.pos += 1;
+            if b == open {
+                depth += 1;
+            } else if b == close {
+                depth -= 1;
+                if depth == 0 {
+                    return Ok(());
+                }
+            }
+        }
+        self.pos = start;
+        Err(self.error("unbalanced delimiters"))
+    }
+
+    /// Skip a quoted string, within which braces must be balanced
+    fn quoted(&mut self) -> Result<(), SyntaxError> {
+        let start = self.pos;
+        self.pos += 1;
+        let mut depth = 0;
+        while let Some(b) = self.peek() {
+            self.pos += 1;
+            match b {
+                b'{' => depth += 1,
+                b'}' => depth -= 1,
+                b'"' if depth == 0 => return Ok(()),
+                _ => {}
+            }
+        }
+        self.pos = start;
+        Err(self.error("unterminated string"))
+    }
+
+    /// Read one part of a value: a braced group, quoted string, or bare number/abbreviation
+    fn value_part(&mut self, close: u8) -> Result<(), SyntaxError> {
+        match self.peek() {
+            Some(b'{') => self.balanced(b'{', b'}'),
+            Some(b'"') => self.quoted(),
+            _ => {
+                let start = self.pos;
+                while let Some(b) = self.peek() {
+                    if b.is_ascii_whitespace() || b == b',' || b == b'#' || b == close || b == b'}'
+                    {
+                        break;
+                    }
+                    self.pos += 1;
+                }
+                if self.pos == start {
+                    Err(self.error("expected field value"))
+                } else {
+                    Ok(())
+                }
+            }
+        }
+    }
+
+    /// Read a value, which may consist of parts concatenated with `#`
+    fn value(&mut self, close: u8) -> Result<Range<usize>, SyntaxError> {
+        let start = self.pos;
+        self.value_part(close)?;
+        let mut end = self.pos;
+        loop {
+            self.skip_trivia();
+            if self.peek() != Some(b'#') {
+                break;
+            }
+            self.pos += 1;
+            self.skip_trivia();
+            self.value_part(close)?;
+            end = self.pos;
+        }
+        Ok(start..end)
+    }
+
+    /// Read the next entry, skipping anything else; returns `None` at the end of the source
+    fn next_entry(&mut self) -> Result<Option<EntryNode>, SyntaxError> {
+        loop {
+            // Skip to the next `@`, ignoring any in `%` comments
+            while let Some(b) = self.peek() {
+                match b {
+                    b'@' => break,
+                    b'%' => self.skip_line(),
+                    _ => self.pos += 1,
+                }
+            }
+            if self.peek().is_none() {
+                return Ok(None);
+            }
+
+            let start = self.pos;
+            self.pos += 1;
+            self.skip_trivia();
+            let kind = self.ident();
+            self.skip_trivia();
+            let (open, close) = match self.peek() {
+                Some(b'{') => (b'{', b'}'),
+                Some(b'(') => (b'(', b')'),
+                _ => return Err(self.error("expected opening delimiter of entry")),
+            };
+
+            // Preserve special blocks without exposing them as entries
+            let kind_str = std::str::from_utf8(&self.src[kind.clone()]).unwrap();
+            if ["comment", "string", "preamble"]
+                .iter()
+                .any(|k| kind_str.eq_ignore_ascii_case(k))
+            {
+                self.balanced(open, close)?;
+                continue;
+            }
+
+            self.pos += 1;
+            return self.entry_body(start, kind, close).map(Some);
+        }
+    }
+
+    /// Read the key and fields of an entry, up to and including its closing delimiter
+    fn entry_body(
+        &mut self,
+        start: usize,
+        kind: Range<usize>,
+        close: u8,
+    ) -> Result<EntryNode, SyntaxError> {
+        self.skip_trivia();
+        let key_start = self.pos;
+        while let Some(b) = self.peek() {
+            if b.is_ascii_whitespace() || b == b',' || b == close {
+                break;
+            }
+            self.pos += 1;
+        }
+        let key = key_start..self.pos;
+
+        self.skip_trivia();
+        let key_comma = (self.peek() == Some(b',')).then_some(self.pos);
+        if key_comma.is_some() {
+            self.pos += 1;
+        }
+
+        let mut fields = Vec::new();
+        loop {
+            self.skip_trivia();
+            if self.peek() == Some(close) {
+                break;
+            }
+            if key_comma.is_none() {
+                return Err(self.error("expected comma after entry key"));
+            }
+
+            let name = self.ident();
+            if name.is_empty() {
+                return Err(self.error("expected field name"));
+            }
+            self.skip_trivia();
+            self.expect(b'=')?;
+            self.skip_trivia();
+            let value = self.value(close)?;
+
+            self.skip_trivia();
+            let comma = (self.peek() == Some(b',')).then_some(self.pos);
+            fields.push(FieldNode {
+                span: name.start..value.end,
+                name,
+                value,
+                comma,
+            });
+
+            if comma.is_some() {
+                self.pos += 1;
+            } else {
+                self.skip_trivia();
+                if self.peek() != Some(close) {
+                    return Err(self.error("expected comma or end of entry"));
+                }
+            }
+        }
+
+        let end = self.pos;
+        self.pos += 1;
+        Ok(EntryNode {
+            span: start..self.pos,
+            kind,
+            key,
+            key_comma,
+            fields,
+            close: end,
+        })
+    }
+}
+
+/// Shift a range that lies after an edit by the change in length of the source
+fn shift(range: &mut Range<usize>, delta: isize) {
+    range.start = range.start.wrapping_add_signed(delta);
+    range.end = range.end.wrapping_add_signed(delta);
+}
+
+impl Document {
+    /// Parse bibliography source into a document
+    ///
+    /// ```
+    /// use citati::source::bib::cst::Document;
+    ///
+    /// let src = "% Comments are kept\n@article{smith2020,\n  pages = {12-34},\n}\n";
+    /// let mut document = Document::parse(src.to_owned()).unwrap();
+    /// document.set_field("smith2020", "pages", "12--34").unwrap();
+    /// document.rename_key("smith2020", "smith2020a").unwrap();
+    /// assert_eq!(
+    ///     document.src(),
+    ///     "% Comments are kept\n@article{smith2020a,\n  pages = {12--34},\n}\n"
+    /// );
+    ///
+    /// // Edits that would break the syntax of the entry are rejected, leaving it as it was
+    /// assert!(document.rename_key("smith2020a", "smith 2020").is_err());
+    /// assert!(document.src().contains("@article{smith2020a,"));
+    /// ```
+    pub fn parse(src: String) -> Result<Self, SyntaxError> {
+        let mut parser = Parser::new(&src, 0);
+        let mut entries = Vec::new();
+        while let Some(entry) = parser.next_entry()? {
+            entries.push(entry);
+        }
+        Ok(Self { src, entries })
+    }
+
+    /// The (possibly edited) source of the document
+    pub fn src(&self) -> &str {
+        &self.src
+    }
+
+    pub fn entries(&self) -> &[EntryNode] {
+        &self.entries
+    }
+
+    /// Text of the source within the given span
+    pub fn text(&self, span: &Range<usize>) -> &str {
+        &self.src[span.clone()]
+    }
+
+    pub fn key(&self, entry: &EntryNode) -> &str {
+        self.text(&entry.key)
+    }
+
+    /// Find a field of an entry by (case insensitive) name
+    pub fn field<'e>(&self, entry: &'e EntryNode, name: &str) -> Option<&'e FieldNode> {
+        entry
+            .fields
+            .iter()
+            .find(|field| self.text(&field.name).eq_ignore_ascii_case(name))
+    }
+
+    /// Span of the content of a field's value, without its delimiters
+    ///
+    /// Returns `None` if the value consists of several concatenated parts, as these cannot be edited as a single piece of text
+    pub fn content(&self, field: &FieldNode) -> Option<Range<usize>> {
+        let value = self.text(&field.value);
+        let mut parser = Parser::new(value, 0);
+        parser.value_part(b'}').ok()?;
+        if parser.pos != value.len() {
+            return None;
+        }
+
+        let delimited = value.starts_with('{') || value.starts_with('"');
+        Some(if delimited {
+            field.value.start + 1..field.value.end - 1
+        } else {
+            field.value.clone()
+        })
+    }
+
+    fn entry_index(&self, key: &str) -> Option<usize> {
+        self.entries.iter().position(|e| self.key(e) == key)
+    }
+
+    /// Replace the given span of the source, which must lie within the entry at `index`
+    ///
+    /// The entry is re-parsed, and the spans of all following entries are shifted, so that untouched regions need not be re-parsed.  If the edited entry does not parse, or no longer ends where it should (e.g., as the text closes it early), the edit is undone and an error returned
+    fn splice(&mut self, index: usize, span: Range<usize>, text: &str) -> Result<(), SyntaxError> {
+        let delta = text.len() as isize - span.len() as isize;
+        let replaced = self.src[span.clone()].to_owned();
+        self.src.replace_range(span.clone(), text);
+
+        let entry = &self.entries[index];
+        let expected = entry.span.start..entry.span.end.wrapping_add_signed(delta);
+        let mut parser = Parser::new(&self.src, expected.start);
+        let error = match parser.next_entry() {
+            Ok(Some(edited)) if edited.span == expected => {
+                self.entries[index] = edited;
+                self.shift_entries(index + 1, delta);
+                return Ok(());
+            }
+            Ok(_) => "the entry would end elsewhere".to_owned(),
+            Err(e) => e.message,
+        };
+
+        self.src
+            .replace_range(span.start..span.start + text.len(), &replaced);
+        Err(SyntaxError {
+            offset: span.start,
+            message: format!(
+                "Cannot replace {replaced:?} with {text:?} in entry {}: {error}",
+                self.key(&self.entries[index])
+            ),
+        })
+    }
+
+    /// Shift the spans of entries from `index` onwards by the change in length of the source
+    fn shift_entries(&mut self, index: usize, delta: isize) {
+        let shift_offset = |offset: usize| offset.wrapping_add_signed(delta);
+        for entry in self.entries[index..].iter_mut() {
+            shift(&mut entry.span, delta);
+            shift(&mut entry.kind, delta);
+            shift(&mut entry.key, delta);
+            entry.key_comma = entry.key_comma.map(shift_offset);
+            entry.close = shift_offset(entry.close);
+            for field in entry.fields.iter_mut() {
+                shift(&mut field.span, delta);
+                shift(&mut field.name, delta);
+                shift(&mut field.value, delta);
+                field.comma = field.comma.map(shift_offset);
+            }
+        }
+    }
+
+    /// Line ending used by the source, so that inserted lines match the rest of the file
+    pub fn newline(&self) -> &'static str {
+        if self.src.contains("\r\n") {
+            "\r\n"
+        } else {
+            "\n"
+        }
+    }
+
+    /// Widen a span to whole lines if there is nothing but whitespace around it on those lines
+    fn whole_lines(&self, span: Range<usize>) -> Range<usize> {
+        let line_start = self.src[..span.start].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = self.src[span.end..]
+            .find('\n')
+            .map_or(self.src.len(), |i| span.end + i + 1);
+        let before = &self.src[line_start..span.start];
+        let after = &self.src[span.end..line_end];
+        if before.trim().is_empty() && after.trim().is_empty() {
+            line_start..line_end
+        } else {
+            span
+        }
+    }
+
+    /// Set the content of a field in an entry, adding the field if it is not present
+    ///
+    /// The delimiters of an existing value are kept; new fields are delimited by braces, and are added after the last field with the same indentation.  Returns whether the entry was found, or an error if the value would not preserve the syntax of the entry (e.g., if its braces are unbalanced), in which case the document is unchanged
+    pub fn set_field(&mut self, key: &str, name: &str, value: &str) -> Result<bool, SyntaxError> {
+        let Some(index) = self.entry_index(key) else {
+            return Ok(false);
+        };
+        let entry = &self.entries[index];
+
+        if let Some(field) = self.field(entry, name) {
+            let span = self.content(field).unwrap_or_else(|| field.value.clone());
+            let text = if span == field.value && !value.chars().all(|c| c.is_ascii_digit()) {
+                format!("{{{value}}}")
+            } else {
+                value.to_owned()
+            };
+            self.splice(index, span, &text)?;
+            return Ok(true);
+        }
+
+        let nl = self.newline();
+        match entry.fields.last() {
+            Some(last) => {
+                let line_start = self.src[..last.span.start].rfind('\n').map_or(0, |i| i + 1);
+                let indent = &self.src[line_start..last.span.start];
+                let indent = if indent.trim().is_empty() {
+                    indent
+                } else {
+                    " "
+                };
+                let (position, text) = match last.comma {
+                    Some(comma) => (comma + 1, format!("{nl}{indent}{name} = {{{value}}},")),
+                    None => (last.span.end, format!(",{nl}{indent}{name} = {{{value}}}")),
+                };
+                self.splice(index, position..position, &text)?;
+            }
+            None => {
+                let (position, text) = match entry.key_comma {
+                    Some(comma) => (comma + 1, format!("{nl}  {name} = {{{value}}}{nl}")),
+                    None => (entry.key.end, format!(",{nl}  {name} = {{{value}}}{nl}")),
+                };
+                self.splice(index, position..position, &text)?;
+            }
+        }
+        Ok(true)
+    }
+
+    /// Remove a field (and its trailing comma) from an entry, returning whether it was present
+    pub fn remove_field(&mut self, key: &str, name: &str) -> Result<bool, SyntaxError> {
+        let Some(index) = self.entry_index(key) else {
+            return Ok(false);
+        };
+        let entry = &self.entries[index];
+        let Some(position) = entry
+            .fields
+            .iter()
+            .position(|f| self.text(&f.name).eq_ignore_ascii_case(name))
+        else {
+            return Ok(false);
+        };
+
+        let field = &entry.fields[position];
+        let span = match field.comma {
+            Some(comma) => field.span.start..comma + 1,
+            None => {
+                // The last field may not have a trailing comma, in which case we
+                // remove the comma of the preceding field instead
+                match position.checked_sub(1).map(|i| &entry.fields[i]) {
+                    Some(previous) => previous.comma.unwrap()..field.span.end,
+                    None => field.span.clone(),
+                }
+            }
+        };
+        let span = self.whole_lines(span);
+        self.splice(index, span, "")?;
+        Ok(true)
+    }
+
+    /// Rename a field of an entry, returning whether it was present, or an error if the name would not preserve the syntax of the entry
+    pub fn rename_field(&mut self, key: &str, old: &str, new: &str) -> Result<bool, SyntaxError> {
+        let Some(index) = self.entry_index(key) else {
+            return Ok(false);
+        };
+        let entry = &self.entries[index];
+        let Some(position) = entry
+            .fields
+            .iter()
+            .position(|f| self.text(&f.name).eq_ignore_ascii_case(old))
+        else {
+            return Ok(false);
+        };
+        let span = entry.fields[position].name.clone();
+        self.rename(index, span, new, |entry| &entry.fields[position].name)?;
+        Ok(true)
+    }
+
+    /// Rename the key of an entry, returning whether it was present, or an error if the key would not preserve the syntax of the entry (e.g., if it contains whitespace or a comma)
+    pub fn rename_key(&mut self, old: &str, new: &str) -> Result<bool, SyntaxError> {
+        let Some(index) = self.entry_index(old) else {
+            return Ok(false);
+        };
+        let span = self.entries[index].key.clone();
+        self.rename(index, span, new, |entry| &entry.key)?;
+        Ok(true)
+    }
+
+    /// Replace a name (i.e., a key or field name) within the entry at `index`, checking that it is read back as a whole
+    ///
+    /// Names cannot be empty, and some parse without error but are not read back as given (e.g., a key containing `)` in an entry delimited by parentheses), in which case the edit is undone
+    fn rename(
+        &mut self,
+        index: usize,
+        span: Range<usize>,
+        new: &str,
+        name: impl Fn(&EntryNode) -> &Range<usize>,
+    ) -> Result<(), SyntaxError> {
+        let invalid = || SyntaxError {
+            offset: span.start,
+            message: format!("{new:?} is not a valid name in a bibliography entry"),
+        };
+        if new.is_empty() {
+            return Err(invalid());
+        }
+
+        let old = self.text(&span).to_owned();
+        self.splice(index, span.clone(), new)?;
+        if self.text(name(&self.entries[index])) == new {
+            return Ok(());
+        }
+
+        self.splice(index, span.start..span.start + new.len(), &old)?;
+        Err(invalid())
+    }
+
+    /// Delete an entry, returning whether it was present
+    ///
+    /// If the entry occupies whole lines, those lines are removed too
+    pub fn delete_entry(&mut self, key: &str) -> bool {
+        let Some(index) = self.entry_index(key) else {
+            return false;
+        };
+        let span = self.whole_lines(self.entries[index].span.clone());
+        let delta = -(span.len() as isize);
+        self.src.replace_range(span, "");
+        self.entries.remove(index);
+        self.shift_entries(index, delta);
+        true
+    }
+}
+
+/// Serialise the document back to source
+impl fmt::Display for Document {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.src)
+    }
+}
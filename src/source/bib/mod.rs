@@ -0,0 +1,267 @@
+//! Read/parse bibliography source
+//!
+//! Lightly wrapping the [`biblatex`]'s [`Bibliography`], we parse the given bibliography file, with some additional error handling to improve help messages.  For editing bibliography files without reformatting them, see the lossless [`cst`] layer.
+
+pub mod cst;
+
+use super::{
+    directive::{Directive, Scope},
+    location::{LineIndex, Location},
+};
+use crate::{Error, Result};
+use biblatex::{Bibliography, Entry, EntryType, ParseError, RawBibliography};
+use cst::Document;
+use std::{collections::HashMap, fmt::Display, fs, path::Path};
+
+#[derive(Clone)]
+pub struct BibCitation {
+    pub key: String,
+    pub entry: Entry,
+
+    /// Location of the start of the entry (i.e., its `@`) in the bibliography file
+    pub location: Location,
+
+    /// Locations of each field name in the entry, keyed by (lower case) field name
+    pub field_locations: HashMap<String, Location>,
+}
+
+impl BibCitation {
+    pub fn get(&self, field: &str) -> Option<String> {
+        self.entry.get_as::<String>(field).ok()
+    }
+
+    pub fn has_field(&self, field: &str) -> bool {
+        self.entry.get(field).is_some()
+    }
+
+    pub fn has_fields(&self, fields: &[&str]) -> bool {
+        fields.iter().all(|f| self.has_field(f))
+    }
+
+    pub fn entry_type(&self) -> EntryType {
+        self.entry.entry_type.clone()
+    }
+
+    /// Location of the given field if present, falling back to the location of the entry itself
+    pub fn field_location(&self, field: &str) -> Location {
+        self.field_locations
+            .get(field)
+            .unwrap_or(&self.location)
+            .clone()
+    }
+}
+
+// A helper trait to determine whether a character at which the cursor is
+// pointing at in some buffer is escaped using backslashes.
+pub(crate) trait LaTeXCharEscaped {
+    fn is_escaped(&self) -> bool;
+}
+
+impl LaTeXCharEscaped for str {
+    fn is_escaped(&self) -> bool {
+        // Count the number of consecutive backslashes before the character
+        // in order to determine whether the character has been escaped or not
+        let mut n = 0;
+        for c in self.chars().rev() {
+            if c == '\\' {
+                n += 1;
+            } else {
+                break;
+            }
+        }
+
+        // If the number of consecutive backslashes immediately preceeding
+        // the character is odd, then it has been escaped; otherwise, these
+        // are literal backslashes that have each been escaped an even number
+        // of times and so they are all treated as character literals
+        n % 2 == 1
+    }
+}
+
+/// Strip comments from bibliography source, preserving any suppression directives
+///
+/// Directives (see [`directive`](`super::directive`)) are returned alongside the stripped source, with their locations in `bib_file`
+fn strip_comments(src: &str, bib_file: &Path) -> (String, Vec<Directive>) {
+    let mut out = String::new();
+    let mut directives = Vec::new();
+
+    // We want to strip comments (indicated by %) from each line of source.
+    // If we encounter a % character, we can skip to the next line.
+    for (i, line) in src.lines().enumerate() {
+        let mut buf = String::new();
+
+        'chars: for (j, ch) in line.char_indices() {
+            if ch == '%' && !buf.is_escaped() {
+                // Before discarding the comment, check whether it is a directive
+                // that we need to keep hold of
+                let column = buf.chars().count() + 1;
+                let location = Location::new(bib_file, i + 1, column);
+                if let Some(directive) = Directive::parse(&line[j + 1..], location) {
+                    directives.push(directive);
+                }
+
+                // In this case, we have encountered a LaTeX-style comment.
+                // The other comment that we could have encountered is a legacy
+                // BibTeX-style comment (@Comment {}), however, I don't want to
+                // re-write the lexer that Typst provides and I don't typically
+                // use this style of comment, so I am happy to ignore it until
+                // or unless it becomes a problem...
+
+                // Trim superfluous whitespace from end of string preceeding
+                // comment if needed.  We do this in-place by truncating the
+                // string until we no longer find any whitespace.  This should
+                // handle unicode as we truncate (pop) character by character
+                // rather than using truncate with indices.
+                //
+                // See earlier versions in ec65e24, 6e70f50, and 783fa42
+                while buf.ends_with(char::is_whitespace) {
+                    buf.pop();
+                }
+
+                // Continue to the next line as a comment has been encountered
+                // and handled.  Technically we continue to the end of the `chars`
+                // loop as we still need to push the current line buffer to the
+                // source file, which happens at the end of each `chars` loop.
+                break 'chars;
+            } else {
+                // If no comment has been encountered, then we can push the present
+                // character to the line buffer, carefree.
+                buf.push(ch);
+            }
+        }
+
+        // Add the current line buffer to the end of the modified source file.
+        // We keep empty lines so that line numbers in the stripped source are
+        // the same as in the original file, which we need to report locations
+        out.push_str(&buf);
+        out.push('\n');
+    }
+
+    (out, directives)
+}
+
+/// Read the bibliography file into a string, without modification
+pub(crate) fn read_bib_file(bib_file: &str) -> Result<String> {
+    fs::read_to_string(bib_file).map_err(Error::io(format!(
+        "Could not read bibliography file {bib_file:?}"
+    )))
+}
+
+/// Error for a parse error, reported at its location in the bibliography file
+fn parse_error(bib_file: &str, src: &str, offset: usize, message: impl Display) -> Error {
+    let location = LineIndex::new(src).location(Path::new(bib_file), offset);
+    Error::Parse(format!(
+        "Could not parse bibliography file {}:{}:{}: {}",
+        bib_file, location.line, location.column, message
+    ))
+}
+
+/// Error for a parse error of biblatex
+fn biblatex_error(bib_file: &str, src: &str, err: ParseError) -> Error {
+    parse_error(bib_file, src, err.span.start, err.kind)
+}
+
+pub fn parse_bib_from_file(bib_file: &str) -> Result<Bibliography> {
+    parse_bib_from_str(&read_bib_file(bib_file)?, bib_file)
+}
+
+/// Parse bibliography source, with a (possibly virtual) path `bib_file` at which errors are reported
+pub fn parse_bib_from_str(src: &str, bib_file: &str) -> Result<Bibliography> {
+    // As of v0.3.3, we no longer warn the user about comments in their bibliography src
+    let (src, _) = strip_comments(src, Path::new(bib_file));
+
+    // Parse the file into a bibliography
+    Bibliography::parse(&src).map_err(|e| biblatex_error(bib_file, &src, e))
+}
+
+/// Parse the bibliography file into citations; see [`parse_bib_citations_from_str`]
+pub fn parse_bib_citations_from_file(bib_file: &str) -> Result<Vec<BibCitation>> {
+    parse_bib_citations_from_str(&read_bib_file(bib_file)?, bib_file)
+}
+
+/// Parse bibliography source into citations, retaining the location of each entry in the (possibly virtual) path `bib_file`
+///
+/// The resolved [`Bibliography`] does not keep track of where entries were defined, so we first parse the [`RawBibliography`], note the spans of entries and their fields, and only then resolve it
+pub fn parse_bib_citations_from_str(src: &str, bib_file: &str) -> Result<Vec<BibCitation>> {
+    let path = Path::new(bib_file);
+    let (src, _) = strip_comments(src, path);
+    let index = LineIndex::new(&src);
+
+    let raw = RawBibliography::parse(&src).map_err(|e| biblatex_error(bib_file, &src, e))?;
+    let mut locations = HashMap::new();
+    for entry in raw.entries.iter() {
+        let field_locations: HashMap<String, Location> = entry
+            .v
+            .fields
+            .iter()
+            .map(|pair| {
+                let field = pair.key.v.to_ascii_lowercase();
+                (field, index.location(path, pair.key.span.start))
+            })
+            .collect();
+        let location = index.location(path, entry.span.start);
+        locations.insert(entry.v.key.v.to_string(), (location, field_locations));
+    }
+
+    let bib = Bibliography::from_raw(raw).map_err(|e| biblatex_error(bib_file, &src, e))?;
+    Ok(bib
+        .into_vec()
+        .into_iter()
+        .map(|entry| {
+            let (location, field_locations) = locations.remove(&entry.key).unwrap();
+            BibCitation {
+                key: entry.key.clone(),
+                entry,
+                location,
+                field_locations,
+            }
+        })
+        .collect())
+}
+
+/// Parse suppression directives from the bibliography file; see [`parse_bib_directives_from_str`]
+pub fn parse_bib_directives_from_file(bib_file: &str) -> Result<Vec<Directive>> {
+    parse_bib_directives_from_str(&read_bib_file(bib_file)?, bib_file)
+}
+
+/// Parse suppression directives from bibliography source, with their locations in the (possibly virtual) path `bib_file`
+///
+/// Directives that apply to the next entry (i.e., `citati-ignore`) are resolved to the key of the first entry that starts after them.  If there is no such entry, the directive is left unresolved, and so will not match any findings
+pub fn parse_bib_directives_from_str(src: &str, bib_file: &str) -> Result<Vec<Directive>> {
+    let (src, mut directives) = strip_comments(src, Path::new(bib_file));
+    let index = LineIndex::new(&src);
+    let raw = RawBibliography::parse(&src).map_err(|e| biblatex_error(bib_file, &src, e))?;
+
+    for directive in directives.iter_mut() {
+        if directive.scope != Scope::NextEntry {
+            continue;
+        }
+
+        let next_entry = raw
+            .entries
+            .iter()
+            .find(|entry| index.line_col(entry.span.start).0 > directive.location.line);
+        if let Some(entry) = next_entry {
+            directive.scope = Scope::Entry(entry.v.key.v.to_string());
+        }
+    }
+
+    Ok(directives)
+}
+
+/// Parse the bibliography file into a lossless [`Document`], which can be edited without reformatting the file
+pub fn parse_bib_document_from_file(bib_file: &str) -> Result<Document> {
+    parse_bib_document_from_str(read_bib_file(bib_file)?, bib_file)
+}
+
+/// Parse bibliography source into a lossless [`Document`], with a (possibly virtual) path `bib_file` at which errors are reported
+pub fn parse_bib_document_from_str(src: String, bib_file: &str) -> Result<Document> {
+    Document::parse(src.clone()).map_err(|e| parse_error(bib_file, &src, e.offset, e.message))
+}
+
+/// Write a (possibly edited) [`Document`] back to the bibliography file
+pub fn write_bib_document_to_file(bib_file: &str, document: &Document) -> Result<()> {
+    fs::write(bib_file, document.src()).map_err(Error::io(format!(
+        "Could not write bibliography file {bib_file:?}"
+    )))
+}
@@ -0,0 +1,107 @@
+//! Parse inline suppression directives from comments
+//!
+//! Some checks produce false positives (e.g., for journals with non-standard page numbering), so findings can be silenced using comments in either the LaTeX or bibliography source:
+//!
+//!   - `% citati-ignore` or `% citati-ignore: pages, articles` before a bib entry ignores findings for that entry;
+//!   - `% citati-ignore-next-line` or `% citati-ignore-next-line: missing` ignores findings located on the following line;
+//!   - `% citati-disable unused` (or `% citati-disable: unused`) disables the given checks entirely.
+//!
+//! In Markdown, directives are given in HTML comments instead (e.g., `<!-- citati-ignore-next-line -->`), in Typst, in `//` or `/* */` comments, in Org, in `#` comments, in reStructuredText, in `..` comments, and in Jupyter notebooks, as in the language of the cell.  If no checks are given, the directive applies to all checks (except `unused-suppression`).  Unknown check names are ignored, with a warning.  Directives are preserved by [`strip_comments`](`super::bib`), the LaTeX [`Lexer`](`super::latex::Lexer`), and the parsers of other markup languages (e.g., [`markdown`](`super::markdown`)) rather than discarded with other comments, and are applied to findings in [`suppress`](`crate::suppress`).
+
+use super::location::Location;
+use crate::diagnostics::Check;
+
+const IGNORE: &str = "citati-ignore";
+const IGNORE_NEXT_LINE: &str = "citati-ignore-next-line";
+const DISABLE: &str = "citati-disable";
+
+/// What findings a directive applies to
+#[derive(Clone, Debug, PartialEq)]
+pub enum Scope {
+    /// The bib entry immediately following the directive, before it has been resolved
+    NextEntry,
+
+    /// The bib entry with the given key
+    Entry(String),
+
+    /// Findings located on the line following the directive
+    NextLine,
+
+    /// All findings
+    All,
+}
+
+/// Suppression directive found in a comment
+#[derive(Clone, Debug)]
+pub struct Directive {
+    pub scope: Scope,
+
+    /// Checks to which the directive applies; if none are given, it applies to all checks
+    pub checks: Option<Vec<Check>>,
+
+    /// Names given in the directive that are not checks, which are ignored
+    pub unknown_checks: Vec<String>,
+
+    /// Text of the directive, as written in the comment
+    pub text: String,
+
+    pub location: Location,
+}
+
+impl Directive {
+    /// Parse a directive from the text of a comment (i.e., without the leading `%`), if it contains one
+    pub fn parse(comment: &str, location: Location) -> Option<Self> {
+        let text = comment.trim();
+
+        // Check for the longest directive first, as `citati-ignore` is a prefix of `citati-ignore-next-line`
+        let (scope, rest) = if let Some(rest) = text.strip_prefix(IGNORE_NEXT_LINE) {
+            (Scope::NextLine, rest)
+        } else if let Some(rest) = text.strip_prefix(IGNORE) {
+            (Scope::NextEntry, rest)
+        } else if let Some(rest) = text.strip_prefix(DISABLE) {
+            (Scope::All, rest)
+        } else {
+            return None;
+        };
+
+        // The directive must be followed by a list of checks, or nothing at all
+        if !(rest.is_empty() || rest.starts_with([':', ' ', '\t'])) {
+            return None;
+        }
+
+        let names: Vec<&str> = rest
+            .trim_start_matches(':')
+            .split([',', ' ', '\t'])
+            .filter(|name| !name.is_empty())
+            .collect();
+        let checks = (!names.is_empty()).then(|| {
+            names
+                .iter()
+                .filter_map(|name| Check::from_name(name))
+                .collect()
+        });
+        let unknown_checks = names
+            .iter()
+            .filter(|name| Check::from_name(name).is_none())
+            .map(|name| name.to_string())
+            .collect();
+
+        Some(Self {
+            scope,
+            checks,
+            unknown_checks,
+            text: text.to_owned(),
+            location,
+        })
+    }
+
+    /// Whether the directive applies to the given check
+    ///
+    /// Directives that do not name any checks do not apply to [`UnusedSuppression`](`Check::UnusedSuppression`), otherwise they could suppress reports of themselves
+    pub fn applies_to(&self, check: Check) -> bool {
+        match &self.checks {
+            Some(checks) => checks.contains(&check),
+            None => check != Check::UnusedSuppression,
+        }
+    }
+}
@@ -1,165 +1,594 @@
 //! Read/parse LaTeX source code
 //!
-//! Implements a simple lexer for LaTeX source code with two tokens: [`Citation`](`super::latex::Token::Citation`)s and [`Other`](`super::latex::Token::Other`).  Using this we can continue reading from the lexer until we find no tokens left ([`None`]).  Note that we intentionally implement following `\input{}`s in LaTeX source to direct us to other source files.  We do not handle this for other commands that insert external content (e.g., `\include.*`, `\intput.+`).
+//! Implements a simple lexer for LaTeX source code with five tokens: [`Citation`](`super::latex::Token::Citation`)s, suppression [`Directive`](`super::latex::Token::Directive`)s, the starts of biblatex [`Refsection`](`super::latex::Token::Refsection`)s, [`Bibliography`](`super::latex::Token::Bibliography`)s printed by biblatex, and [`Other`](`super::latex::Token::Other`).  Using this we can continue reading from the lexer until we find no tokens left ([`None`]).  Note that we intentionally implement following `\input{}`s and `\include{}`s in LaTeX source to direct us to other source files, attributing citations to the file in which they are found.  We do not handle this for other commands that insert external content (e.g., `\includeonly`, `\import`).  A file that is part of a larger document can name the document's root file with a `% !TeX root` magic comment, which we follow with [`root_document`](`super::latex::root_document`).
 
+use super::{bib::LaTeXCharEscaped, directive::Directive, location::Location, search::SearchPath};
+use crate::{Error, Result};
 use lazy_static::lazy_static;
-use regex::Regex;
+use regex::{Captures, Regex};
 use std::{
+    collections::VecDeque,
     fs::{self, File},
     io::{BufRead, BufReader},
-    path::{Path, PathBuf},
+    path::{Component, Path, PathBuf},
+    rc::Rc,
 };
 
 lazy_static! {
-    static ref CITATION_REGEX: Regex = Regex::new(r"\\(\w*cite)\{([^}]+)\}").unwrap();
+    // Citation commands of LaTeX, biblatex (e.g., `\textcite`), natbib (e.g., `\citet*`), and apacite (e.g., `\citeNP`), with up to two optional arguments (e.g., `\parencite[see][p.~3]{smith2020}`)
+    pub(crate) static ref CITATION_REGEX: Regex = Regex::new(
+        r"\\(\w*[cC]ite(?:t|p|alt|alp|author|year|yearpar|title|date|url|NP|ANP|A|num)?\*?)(?:\[[^\]]*\]){0,2}\{([^}]+)\}"
+    )
+    .unwrap();
+    static ref REFSECTION_REGEX: Regex = Regex::new(
+        r"\\(?:(begin|end)\{(refsection|refsegment)\}|new(refsection|refsegment))(?:\[([^\]]*)\])?"
+    )
+    .unwrap();
+    static ref PRINTBIBLIOGRAPHY_REGEX: Regex =
+        Regex::new(r"\\printbibliography\b(?:\s*\[([^\]]*)\])?").unwrap();
+    // Commands that insert the contents of another file, as in `\input{chapter}` or `\include{chapters/intro}`
+    static ref INPUT_REGEX: Regex = Regex::new(r"\\(?:input|include)\{([^}]+)\}").unwrap();
+    static ref MAGIC_ROOT_REGEX: Regex =
+        Regex::new(r"(?i)^\s*%\s*!\s*TeX\s+root\s*=\s*(.*?)\s*$").unwrap();
+}
+
+/// Root document named by a `% !TeX root = ...` magic comment in the LaTeX source, if any
+///
+/// Editors such as TeXShop, TeXstudio, and VS Code (with LaTeX Workshop) use this comment to compile the whole document when a file that it includes (e.g., a chapter) is open.  Like them, we only look for it in the comments at the top of the file (which may be separated by blank lines), so that a comment quoting one later in the file (e.g., in a chapter explaining how the thesis is built) is not taken for it.  The path is as given, so is relative to the file containing the comment
+///
+/// ```
+/// use citati::source::latex;
+/// use std::path::PathBuf;
+///
+/// let src = "% !TEX root = ../main.tex\n\\chapter{Introduction}\n";
+/// assert_eq!(latex::magic_root(src), Some(PathBuf::from("../main.tex")));
+///
+/// let src = "\\chapter{Introduction}\n% !TEX root = ../main.tex\n";
+/// assert_eq!(latex::magic_root(src), None);
+/// ```
+pub fn magic_root(src: &str) -> Option<PathBuf> {
+    src.lines()
+        .take_while(|line| {
+            let line = line.trim_start();
+            line.is_empty() || line.starts_with('%')
+        })
+        .find_map(|line| MAGIC_ROOT_REGEX.captures(line))
+        .map(|caps| PathBuf::from(&caps[1]))
+        .filter(|root| !root.as_os_str().is_empty())
+}
+
+/// Root document of the LaTeX file, following [`magic_root`] comments from file to file
+///
+/// The file is its own root if it has no magic comment, or if it cannot be read.  Roots are resolved relative to the file naming them, and are normalised, so that, e.g., the root of `chapters/intro.tex` is `main.tex` rather than `chapters/../main.tex`.  Citations within the file are still attributed to it when lexing the root, as long as the root includes it with `\input{}` or `\include{}`
+pub fn root_document(latex_file: &Path) -> PathBuf {
+    let mut root = latex_file.to_path_buf();
+    let mut visited = vec![normalise(latex_file)];
+    while let Ok(src) = fs::read_to_string(&root) {
+        let Some(next) = magic_root(&src) else {
+            break;
+        };
+        let next = normalise(&root.parent().unwrap_or(Path::new("")).join(next));
+        if visited.contains(&next) {
+            eprintln!(
+                "[WARN] Root documents named from {:?} form a cycle; using {:?}",
+                latex_file.display().to_string(),
+                root.display().to_string()
+            );
+            break;
+        }
+        if !next.is_file() {
+            eprintln!(
+                "[WARN] Could not find root document {:?} named in {:?}",
+                next.display().to_string(),
+                root.display().to_string()
+            );
+            break;
+        }
+        visited.push(next.clone());
+        root = next;
+    }
+    root
+}
+
+/// Remove `.` components from the path, and `..` components along with the component before them, without consulting the file system
+pub(crate) fn normalise(path: &Path) -> PathBuf {
+    let mut normalised = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir
+                if matches!(
+                    normalised.components().next_back(),
+                    Some(Component::Normal(_))
+                ) =>
+            {
+                normalised.pop();
+            }
+            component => normalised.push(component),
+        }
+    }
+    normalised
 }
 
 /// Struct containing information about a citation from LaTeX
+#[derive(Clone)]
 pub struct LaTeXCitation {
-    pub _key: String,
+    pub key: String,
+
+    /// Commands with which the key is cited (e.g., `textcite`), in the same order as `locations`
+    pub cite_cmds: Vec<String>,
 
-    // TODO: make this `Vec<InTexCite>` or `Vec<CiteLoc>` so that we have more information about the position and span of the citation within LaTeX
-    // TODO: use an enum for difference cite commands?
-    // NOTE: the above TODO notes are only useful if we need that information somewhere downstream.  We barely need `cite_cmds`
-    pub _cite_cmds: Vec<String>,
+    /// Locations at which the key is cited, in the same order as `cite_cmds`
+    pub locations: Vec<Location>,
+
+    /// Refsections in which the key is cited, in the same order as `locations`
+    pub refsections: Vec<usize>,
+
+    /// Refsegments in which the key is cited, in the same order as `locations`
+    pub refsegments: Vec<usize>,
 }
 
 /// Token struct containing information about a citation found in LaTeX source
+#[derive(Clone)]
 pub struct CitationToken {
     pub key: String,
     pub cite_cmd: String,
+    pub location: Location,
+
+    /// Number of the biblatex refsection in which the citation is, where refsection 0 is the document outside of any refsection
+    pub refsection: usize,
+
+    /// Number of the biblatex refsegment in which the citation is, where refsegment 0 is the document outside of any refsegment
+    pub refsegment: usize,
+}
+
+/// The start of a biblatex refsection, from `\begin{refsection}` or `\newrefsection`
+///
+/// Refsections are numbered consecutively from 1 in the order in which they start.  Each may give bibliography resources that are local to it (e.g., `\begin{refsection}[chapter2.bib]`), in which case citations within the refsection are resolved against these as well as the global bibliography
+#[derive(Clone, Debug)]
+pub struct Refsection {
+    pub number: usize,
+
+    /// Paths of the local bibliography resources, as they are given (i.e., relative to the directory in which the document is compiled)
+    pub resources: Vec<PathBuf>,
+}
+
+/// A bibliography printed with biblatex's `\printbibliography`, and the options with which it selects the entries that it prints
+///
+/// A bibliography prints the entries cited in one refsection (that given with its `section` option, or otherwise the refsection in which it is), and possibly only those cited in one refsegment of it (its `segment` option).  Of these, it may only print entries of a type (`type`), not of a type (`nottype`), with a keyword (`keyword`), or without a keyword (`notkeyword`).  Other options filtering the entries printed (e.g., `category` or `filter`) depend on definitions elsewhere in the document, so we do not interpret them
+#[derive(Clone, Debug)]
+pub struct PrintedBibliography {
+    pub refsection: usize,
+    pub refsegment: Option<usize>,
+    pub entry_type: Option<String>,
+    pub not_entry_type: Option<String>,
+    pub keyword: Option<String>,
+    pub not_keyword: Option<String>,
+
+    /// Whether the bibliography is filtered by options that we do not interpret, in which case it may print any entry cited in its refsection (and refsegment)
+    pub other_filters: bool,
+    pub location: Location,
+}
+
+impl PrintedBibliography {
+    /// Parse the options of a bibliography printed at the given location within the given refsection
+    ///
+    /// ```
+    /// use citati::source::{latex::PrintedBibliography, Location};
+    /// use std::path::Path;
+    ///
+    /// let location = Location::new(Path::new("main.tex"), 12, 1);
+    /// let printed = PrintedBibliography::parse("segment=2, keyword={primary}", 1, location);
+    /// assert_eq!(printed.refsection, 1);
+    /// assert_eq!(printed.refsegment, Some(2));
+    /// assert_eq!(printed.keyword.as_deref(), Some("primary"));
+    /// ```
+    pub fn parse(options: &str, refsection: usize, location: Location) -> Self {
+        let mut printed = Self {
+            refsection,
+            refsegment: None,
+            entry_type: None,
+            not_entry_type: None,
+            keyword: None,
+            not_keyword: None,
+            other_filters: false,
+            location,
+        };
+        for option in options.split(',') {
+            let (name, value) = option.split_once('=').unwrap_or((option, ""));
+            let value = value.trim().trim_start_matches('{').trim_end_matches('}');
+            let value = value.trim().to_owned();
+            match name.trim() {
+                "section" => printed.refsection = value.parse().unwrap_or(refsection),
+                "segment" => printed.refsegment = value.parse().ok(),
+                "type" => printed.entry_type = Some(value.to_lowercase()),
+                "nottype" => printed.not_entry_type = Some(value.to_lowercase()),
+                "keyword" => printed.keyword = Some(value),
+                "notkeyword" => printed.not_keyword = Some(value),
+                "subtype" | "notsubtype" | "category" | "notcategory" | "filter" | "check" => {
+                    printed.other_filters = true
+                }
+                _ => {}
+            }
+        }
+        printed
+    }
+}
+
+/// Position of the lexer with respect to biblatex refsections and refsegments
+///
+/// This is shared between lexers of nested inputs, so that refsections and refsegments are numbered across the whole document
+#[derive(Clone, Copy, Default)]
+struct RefContext {
+    /// Current refsection and refsegment
+    section: usize,
+    segment: usize,
+
+    /// Number of refsections and refsegments started so far
+    sections: usize,
+    segments: usize,
+}
+
+/// Commands found on a line of LaTeX source, each of which queues tokens
+#[derive(Clone, Copy)]
+enum Command {
+    Citation,
+    Refsection,
+    Bibliography,
 }
 
 /// Possible token types from LaTeX source, output by `Lexer`
 ///
-/// We only really care about the `Citation` token; everything else can be `Other`
+/// We only really care about the `Citation` token (and `Directive`s in comments, which suppress findings, the start of each `Refsection`, which may have its own bibliography, and each `Bibliography` printed, which may not print every entry cited); everything else can be `Other`
+#[derive(Clone)]
 pub enum Token {
     Citation(CitationToken),
+    Directive(Directive),
+    Refsection(Refsection),
+    Bibliography(PrintedBibliography),
     Other,
 }
 
 /// Custom Lexer for LaTeX source code that will find citations
 ///
-/// Lexer will also recurse into `\input{}`s and add them to the `stack`
+/// Lexer will also recurse into `\input{}`s and `\include{}`s and add them to the `stack`.  The source itself may be read from any [`BufRead`] (e.g., a file, standard input, or a string in memory), but `\input{}`s are always read from files
 pub struct Lexer<R: BufRead> {
     reader: R,
 
     /// Stack of lexers for handling nested inputs
-    stack: Vec<Lexer<R>>,
+    stack: Vec<Lexer<BufReader<File>>>,
 
     /// Store the base path of the file we are lexing
     base_path: PathBuf,
+
+    /// Path of the file we are lexing, as it should be reported to the user
+    path: PathBuf,
+
+    /// Number of lines read so far by this lexer
+    line: usize,
+
+    /// Tokens found on the current line that have not yet been returned
+    pending: VecDeque<Token>,
+
+    /// Refsection and refsegment that we are in
+    refs: RefContext,
+
+    /// Directory in which the document is compiled (i.e., that of the file at which lexing started), as it should be reported to the user
+    compile_dir: PathBuf,
+
+    /// Directories in which to search for inputs that are not in the compile directory, shared between lexers of nested inputs
+    search_path: Rc<SearchPath>,
 }
 
 /// Convenient implementation of construction of `Lexer`
 impl<R: BufRead> Lexer<R> {
     /// Constructor method for `Lexer`
     ///
-    /// Requires a `base_path` to be constructed so that we can handle recursion into `\input{}`s from the relative source path
-    fn new(reader: R, base_path: PathBuf) -> Self {
+    /// Requires a `base_path` to be constructed so that we can handle recursion into `\input{}`s from the relative source path, and the `path` of the source so that we can report where citations are
+    fn new(reader: R, base_path: PathBuf, path: PathBuf) -> Self {
+        let compile_dir = path.parent().unwrap_or(Path::new("")).to_path_buf();
         Lexer {
             reader,
             stack: Vec::new(),
             base_path,
+            path,
+            line: 0,
+            pending: VecDeque::new(),
+            refs: RefContext::default(),
+            compile_dir,
+            search_path: Rc::default(),
+        }
+    }
+
+    /// Search the given directories for inputs that are not in the directory in which the document is compiled (see [`search`](`super::search`))
+    pub fn with_search_path(mut self, search_path: Rc<SearchPath>) -> Self {
+        self.search_path = search_path;
+        self
+    }
+
+    /// Box the reader of the lexer, so that lexers of sources read in different ways have the same type
+    pub fn boxed<'a>(self) -> Lexer<Box<dyn BufRead + 'a>>
+    where
+        R: 'a,
+    {
+        Lexer {
+            reader: Box::new(self.reader),
+            stack: self.stack,
+            base_path: self.base_path,
+            path: self.path,
+            line: self.line,
+            pending: self.pending,
+            refs: self.refs,
+            compile_dir: self.compile_dir,
+            search_path: self.search_path,
         }
     }
 }
 
+/// Construction of `Lexer` from source in memory
+impl<'a> Lexer<&'a [u8]> {
+    /// Construct `Lexer` from LaTeX source, with a virtual `path` at which it is reported to be
+    ///
+    /// The path need not exist, but `\input{}`s are read relative to its directory.  This is useful for checking source that has not been written to a file (e.g., an unsaved buffer in an editor, or standard input)
+    ///
+    /// ```
+    /// use citati::source::latex::{Lexer, Token};
+    /// use std::path::Path;
+    ///
+    /// let mut lexer = Lexer::from_source("See \\cite{smith2020}.\n", Path::new("unsaved.tex"));
+    /// let Ok(Some(Token::Citation(citation))) = lexer.next_token() else {
+    ///     panic!("expected citation");
+    /// };
+    /// assert_eq!(citation.key, "smith2020");
+    /// assert_eq!(citation.location.file, Path::new("unsaved.tex"));
+    /// ```
+    pub fn from_source(src: &'a str, path: &Path) -> Self {
+        let base_path = path.parent().unwrap_or(Path::new("")).to_path_buf();
+        Self::new(src.as_bytes(), base_path, path.to_path_buf())
+    }
+}
+
 /// Convenient implementation of construction of `Lexer` with concrete reader type from files
 impl Lexer<BufReader<File>> {
     /// Construct `Lexer` from `Path`
-    fn from_path(latex_file: &Path) -> Self {
-        // TODO: better error handling if failed to open file or get absolute path
+    pub fn from_path(latex_file: &Path) -> Result<Self> {
         // TODO: move path logic into file.rs or path.rs (tuck the logic behind some other module)
-        let file = File::open(latex_file).unwrap();
+        let file = File::open(latex_file).map_err(Error::io(format!(
+            "Could not open LaTeX file {latex_file:?}"
+        )))?;
         let reader = BufReader::new(file);
-        let abs_path = fs::canonicalize(latex_file).unwrap();
+        let abs_path = fs::canonicalize(latex_file).map_err(Error::io(format!(
+            "Could not resolve LaTeX file {latex_file:?}"
+        )))?;
         let base_path = abs_path.parent().unwrap();
         let path_buf = base_path.to_path_buf();
-        Self::new(reader, path_buf)
+        Ok(Self::new(reader, path_buf, latex_file.to_path_buf()))
     }
 
     /// Construct `Lexer` from path `&str`
-    pub fn from_str(latex_file: &str) -> Self {
+    ///
+    /// ```
+    /// use citati::source::latex::{Lexer, Token};
+    /// # let latex_file = std::env::temp_dir().join("citati-doc-lexer.tex");
+    /// # std::fs::write(&latex_file, "See \\textcite{smith2020}.\n").unwrap();
+    /// # let latex_file = latex_file.to_str().unwrap();
+    ///
+    /// let mut lexer = Lexer::from_file(latex_file)?;
+    /// while let Some(token) = lexer.next_token()? {
+    ///     if let Token::Citation(citation) = token {
+    ///         assert_eq!(citation.key, "smith2020");
+    ///         assert_eq!(citation.cite_cmd, "textcite");
+    ///     }
+    /// }
+    /// # Ok::<(), citati::Error>(())
+    /// ```
+    pub fn from_file(latex_file: &str) -> Result<Self> {
         let path = Path::new(latex_file);
         Self::from_path(path)
     }
 }
 
+/// Queueing of tokens found on a line
+impl<R: BufRead> Lexer<R> {
+    /// Queue a token for each key cited by a citation command on the current line
+    fn queue_citations(&mut self, line: &str, caps: &Captures) {
+        let cite_cmd = caps.get(1).map(|m| m.as_str().to_owned()).unwrap();
+        let citations = caps.get(2).unwrap();
+
+        // Split citations by comma and queue each as a separate token
+        let mut offset = citations.start();
+        for citation in citations.as_str().split(',') {
+            let key = citation.trim();
+            if !key.is_empty() {
+                let start = offset + citation.find(key).unwrap();
+                let column = line[..start].chars().count() + 1;
+                let location = Location::new(&self.path, self.line, column);
+                self.pending.push_back(Token::Citation(CitationToken {
+                    key: key.to_string(),
+                    cite_cmd: cite_cmd.clone(),
+                    location,
+                    refsection: self.refs.section,
+                    refsegment: self.refs.segment,
+                }));
+            }
+            offset += citation.len() + 1;
+        }
+    }
+
+    /// Move into or out of a refsection or refsegment, queueing a token for the start of each refsection
+    ///
+    /// Content after the end of a refsection or refsegment is in refsection or refsegment 0, as in biblatex.  A new refsection also starts a new refsegment
+    fn queue_refsection(&mut self, caps: &Captures) {
+        let starts = caps.get(1).is_none_or(|m| m.as_str() == "begin");
+        let kind = caps.get(2).or(caps.get(3)).unwrap().as_str();
+        match (kind, starts) {
+            ("refsection", true) => {
+                self.refs.sections += 1;
+                self.refs.section = self.refs.sections;
+                self.refs.segment = 0;
+
+                let resources = caps
+                    .get(4)
+                    .map(|m| m.as_str())
+                    .unwrap_or_default()
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|resource| !resource.is_empty())
+                    .map(PathBuf::from)
+                    .collect();
+                self.pending.push_back(Token::Refsection(Refsection {
+                    number: self.refs.section,
+                    resources,
+                }));
+            }
+            ("refsection", false) => {
+                self.refs.section = 0;
+                self.refs.segment = 0;
+            }
+            (_, true) => {
+                self.refs.segments += 1;
+                self.refs.segment = self.refs.segments;
+            }
+            (_, false) => self.refs.segment = 0,
+        }
+    }
+
+    /// Queue a token for a bibliography printed on the current line
+    fn queue_bibliography(&mut self, line: &str, caps: &Captures) {
+        let start = caps.get(0).unwrap().start();
+        let column = line[..start].chars().count() + 1;
+        let location = Location::new(&self.path, self.line, column);
+        let options = caps.get(1).map(|m| m.as_str()).unwrap_or_default();
+        self.pending
+            .push_back(Token::Bibliography(PrintedBibliography::parse(
+                options,
+                self.refs.section,
+                location,
+            )));
+    }
+}
+
 /// Implements primary `Lexer` functionality to get next token
-///
-/// Currently only works for `R: BufReader<File>` but in future we should make this more generic (if required)
-impl Lexer<BufReader<File>> {
-    /// Get the next token from a `Lexer`
-    pub fn next_token(&mut self) -> Option<Token> {
-        // Check the stack first
-        if let Some(top_lexer) = self.stack.last_mut() {
-            if let Some(token) = top_lexer.next_token() {
-                return Some(token);
-            } else {
-                // Pop the lexer if it's exhausted
-                self.stack.pop();
+impl<R: BufRead> Lexer<R> {
+    /// Get the next token from a `Lexer`, or `None` once the source and every file it inputs have been read
+    pub fn next_token(&mut self) -> Result<Option<Token>> {
+        // Return any citations remaining from the line we most recently read
+        if let Some(token) = self.pending.pop_front() {
+            return Ok(Some(token));
+        }
+
+        // Check the stack next
+        while let Some(top_lexer) = self.stack.last_mut() {
+            if let Some(token) = top_lexer.next_token()? {
+                return Ok(Some(token));
+            }
+
+            // Pop the lexer if it's exhausted, continuing from the refsection in which it ended
+            self.refs = top_lexer.refs;
+            self.stack.pop();
+            if let Some(next_lexer) = self.stack.last_mut() {
+                next_lexer.refs = self.refs;
             }
         }
 
         // Continue reading from the main lexer
         let mut buffer = String::new();
-        if self.reader.read_line(&mut buffer).unwrap() == 0 {
-            return None; // EOF
+        let n = self
+            .reader
+            .read_line(&mut buffer)
+            .map_err(Error::io(format!(
+                "Could not read LaTeX file {:?}",
+                self.path
+            )))?;
+        if n == 0 {
+            return Ok(None); // EOF
         }
+        self.line += 1;
+
+        // Remove comments from the line, keeping hold of any suppression
+        // directives.  An escaped `\%` is a literal percent sign rather than
+        // the start of a comment
+        let comment_pos = buffer
+            .match_indices('%')
+            .map(|(i, _)| i)
+            .find(|&i| !buffer[..i].is_escaped());
+        if let Some(comment_pos) = comment_pos {
+            let column = buffer[..comment_pos].chars().count() + 1;
+            let location = Location::new(&self.path, self.line, column);
+            if let Some(directive) = Directive::parse(&buffer[comment_pos + 1..], location) {
+                self.pending.push_back(Token::Directive(directive));
+            }
 
-        // Remove comments from the line
-        if let Some(comment_pos) = buffer.find('%') {
             // Keep only the part before the comment
             buffer.truncate(comment_pos);
         }
 
-        let line = buffer.trim();
-
-        // Check for citation commands
-        if let Some(caps) = CITATION_REGEX.captures(line) {
-            let cite_cmd = caps.get(1).map(|m| m.as_str().to_owned()).unwrap();
-            let citations = caps.get(2).map_or("", |m| m.as_str());
-
-            // Split citations by comma and return each as a separate token
-            for citation in citations.split(',') {
-                let citation = citation.trim();
-                if !citation.is_empty() {
-                    let key = citation.to_string();
-                    return Some(Token::Citation(CitationToken { key, cite_cmd }));
-                }
+        // Check for citation commands, refsection and refsegment boundaries,
+        // and printed bibliographies.  There may be many of these on one line,
+        // each citation command may cite many keys, and citations (and
+        // bibliographies) belong to the refsection in which they appear, so
+        // we queue all of them in the order in which they appear
+        let mut commands: Vec<(Command, Captures)> = [
+            (Command::Citation, &*CITATION_REGEX),
+            (Command::Refsection, &*REFSECTION_REGEX),
+            (Command::Bibliography, &*PRINTBIBLIOGRAPHY_REGEX),
+        ]
+        .into_iter()
+        .flat_map(|(command, regex)| {
+            regex
+                .captures_iter(&buffer)
+                .map(move |caps| (command, caps))
+        })
+        .collect();
+        commands.sort_by_key(|(_, caps)| caps.get(0).unwrap().start());
+        for (command, caps) in commands {
+            match command {
+                Command::Citation => self.queue_citations(&buffer, &caps),
+                Command::Refsection => self.queue_refsection(&caps),
+                Command::Bibliography => self.queue_bibliography(&buffer, &caps),
             }
         }
 
-        // Recurse into \input
+        // Recurse into \input and \include, which insert the contents of other
+        // files alike as far as citations are concerned
         // TODO: recurse immediately rather than adding them to the stack, as this would be more like how the LaTeX compiler does it
-        if let Some(input_pos) = line.find("\\input") {
-            // Offset starting position to get the file name; the length of "\input{" is 7
-            let start = input_pos + 7;
-            if let Some(end) = line[start..].find('}') {
-                let filename_str = line[start..start + end].trim().to_string();
-                let mut filename = PathBuf::from(filename_str);
-
-                // Prepend the base path to the filename
-                filename = self.base_path.join(&filename);
-
-                // Check if the filename has an extension; if not, add ".tex"
-                if filename.extension().is_none() {
-                    filename.set_extension("tex");
-                }
+        let mut lexers = Vec::new();
+        for caps in INPUT_REGEX.captures_iter(&buffer) {
+            let mut filename = PathBuf::from(caps[1].trim());
 
-                // Process the input file
-                let lexer = Self::from_path(&filename);
+            // Check if the filename has an extension; if not, add ".tex"
+            if filename.extension().is_none() {
+                filename.set_extension("tex");
+            }
 
-                // Push the new lexer onto the stack
-                self.stack.push(lexer);
+            // LaTeX looks for the input file in the directory in which the document is
+            // compiled and then in the search path.  Failing that, we look relative to
+            // the file that included it, reporting its location relative to that file
+            let found = std::iter::once(self.compile_dir.join(&filename))
+                .chain(self.search_path.find(&filename))
+                .find(|path| path.is_file());
+            let mut lexer = match found {
+                Some(path) => Lexer::from_path(&path)?,
+                None => {
+                    let mut lexer = Lexer::from_path(&self.base_path.join(&filename))?;
+                    lexer.path = self.path.parent().unwrap_or(Path::new("")).join(&filename);
+                    lexer
+                }
+            };
+            lexer.compile_dir = self.compile_dir.clone();
+            lexer.search_path = Rc::clone(&self.search_path);
+            lexers.push(lexer);
+        }
 
-                // Return the next token from the newly added lexer
-                return self.stack.last_mut().unwrap().next_token();
-            }
+        // Push the new lexers onto the stack, the first on top; their tokens will be
+        // returned once we have returned any citations from the current line
+        if let Some(first) = lexers.first_mut() {
+            first.refs = self.refs;
         }
+        self.stack.extend(lexers.into_iter().rev());
 
-        // If no citation command is found, treat it as 'Other'.
-        Some(Token::Other)
+        // Return the first citation from this line; if no citation command is
+        // found, treat it as 'Other'.
+        Ok(Some(self.pending.pop_front().unwrap_or(Token::Other)))
     }
 }
@@ -0,0 +1,84 @@
+//! Positions of citations within source files
+//!
+//! Citations and bibliography entries are reported alongside the [`Location`] at which they were found, so that downstream consumers (scripts, editors, CI dashboards) can point the user at the offending line.  Lines and columns are both one-based, as is conventional for editors and compilers.  Within Jupyter notebooks, lines are counted from the start of a cell, so locations also have the (one-based) index of that cell.
+
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+/// Position of some item within a source file
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Location {
+    pub file: PathBuf,
+    pub line: usize,
+    pub column: usize,
+
+    /// Cell of a Jupyter notebook within which the line and column are counted, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cell: Option<usize>,
+}
+
+impl Location {
+    pub fn new(file: &Path, line: usize, column: usize) -> Self {
+        Self {
+            file: file.to_path_buf(),
+            line,
+            column,
+            cell: None,
+        }
+    }
+
+    /// Line and column of the location, preceded by its cell (e.g., `cell 2, 3:5`) within Jupyter notebooks
+    pub fn position(&self) -> String {
+        match self.cell {
+            Some(cell) => format!("cell {cell}, {}:{}", self.line, self.column),
+            None => format!("{}:{}", self.line, self.column),
+        }
+    }
+}
+
+/// Lookup table to convert byte offsets within a source string into line/column positions
+///
+/// Parsers such as [`biblatex`] report byte spans into the source; we compute the start of each line once so that each lookup is a binary search rather than a scan of the whole file.
+pub struct LineIndex<'a> {
+    src: &'a str,
+    line_starts: Vec<usize>,
+}
+
+impl<'a> LineIndex<'a> {
+    pub fn new(src: &'a str) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(src.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        Self { src, line_starts }
+    }
+
+    /// Get the one-based line and (character) column of the given byte offset
+    pub fn line_col(&self, offset: usize) -> (usize, usize) {
+        let line = self
+            .line_starts
+            .partition_point(|&start| start <= offset)
+            .saturating_sub(1);
+        let line_start = self.line_starts[line];
+        let column = self.src[line_start..offset.min(self.src.len())]
+            .chars()
+            .count();
+        (line + 1, column + 1)
+    }
+
+    /// Get the byte offset of the given one-based line and (character) column, if it is within the source
+    ///
+    /// This is the inverse of [`line_col`](`LineIndex::line_col`)
+    pub fn offset(&self, line: usize, column: usize) -> Option<usize> {
+        let line_start = *self.line_starts.get(line.checked_sub(1)?)?;
+        self.src[line_start..]
+            .char_indices()
+            .nth(column.checked_sub(1)?)
+            .map(|(i, _)| line_start + i)
+    }
+
+    /// Construct a [`Location`] in `file` from the given byte offset
+    pub fn location(&self, file: &Path, offset: usize) -> Location {
+        let (line, column) = self.line_col(offset);
+        Location::new(file, line, column)
+    }
+}
@@ -0,0 +1,313 @@
+//! Read citations from Pandoc Markdown
+//!
+//! Pandoc cites keys in Markdown either in brackets, as in `[see @smith2020, p. 4; -@doe2019]`, or in the text itself, as in `@smith2020 says`, where `-@key` suppresses the author and `@{key}` allows keys with any characters.  An `@` preceded by a letter or digit (e.g., in an email address) is not a citation, and nor is anything within code spans, code blocks, or HTML comments.  Keys given in the `nocite` field of the YAML metadata block at the top of the document are also cited (as with `\nocite`).  In the Markdown cells of Jupyter [`notebook`](`super::notebook`)s, keys cited with raw LaTeX citation commands (e.g., `\cite{smith2020}`) are cited too, as these are passed through when notebooks are converted to LaTeX.  Suppression directives (see [`directive`](`super::directive`)) can be given in HTML comments, as in `<!-- citati-ignore-next-line -->`.  See the [Pandoc manual](https://pandoc.org/MANUAL.html#citation-syntax) for the full syntax.
+
+use super::{
+    directive::Directive,
+    latex::{self, CitationToken, Token},
+    location::Location,
+};
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::path::Path;
+
+lazy_static! {
+    static ref CITATION_REGEX: Regex =
+        Regex::new(r"(?:^|[^\w@\\])(-?)@(\{[^}]*\}|\w[\w:.#$%&+?<>~/-]*)").unwrap();
+    static ref BRACKET_REGEX: Regex = Regex::new(r"\[[^\[\]]*-?@[^\[\]]*\]").unwrap();
+    static ref FENCE_REGEX: Regex = Regex::new(r"^ {0,3}(`{3,}|~{3,})").unwrap();
+    static ref LIST_ITEM_REGEX: Regex = Regex::new(r"^ {0,3}(?:[-*+]|\d+[.)])\s").unwrap();
+    static ref CODE_SPAN_REGEX: Regex = Regex::new(r"`+").unwrap();
+}
+
+/// Punctuation that may be within a key, but not at its end
+const INTERNAL_PUNCTUATION: &[char] = &[
+    ':', '.', '#', '$', '%', '&', '-', '+', '?', '<', '>', '~', '/',
+];
+
+/// Citation mode, in Pandoc's terms, used as the citation command of citations in Markdown
+const NORMAL: &str = "normal";
+const SUPPRESS_AUTHOR: &str = "suppress-author";
+const AUTHOR_IN_TEXT: &str = "author-in-text";
+const NOCITE: &str = "nocite";
+
+/// Parse the citations and suppression directives in Markdown source, with a (possibly virtual) path at which they are reported
+///
+/// ```
+/// use citati::source::{latex::Token, markdown};
+/// use std::path::Path;
+///
+/// let src = "As @smith2020 says [see -@doe2019, p. 4], email foo@bar.com.\n";
+/// let keys: Vec<String> = markdown::parse_markdown_from_str(src, Path::new("paper.md"))
+///     .into_iter()
+///     .filter_map(|token| match token {
+///         Token::Citation(citation) => Some(citation.key),
+///         _ => None,
+///     })
+///     .collect();
+/// assert_eq!(keys, ["smith2020", "doe2019"]);
+/// ```
+pub fn parse_markdown_from_str(src: &str, path: &Path) -> Vec<Token> {
+    parse_markdown(src, path, false)
+}
+
+/// Parse the citations and suppression directives in a Markdown cell of a Jupyter notebook, in which raw LaTeX citation commands are also citations
+pub(crate) fn parse_markdown_cell_from_str(src: &str, path: &Path) -> Vec<Token> {
+    parse_markdown(src, path, true)
+}
+
+/// Parse Markdown source, reading raw LaTeX citation commands as citations if `raw_latex`
+fn parse_markdown(src: &str, path: &Path, raw_latex: bool) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let lines: Vec<&str> = src.lines().collect();
+
+    // The YAML metadata block, if any, must be at the very start of the document
+    let mut start = 0;
+    if lines.first().is_some_and(|line| line.trim_end() == "---") {
+        if let Some(end) = lines[1..]
+            .iter()
+            .position(|line| matches!(line.trim_end(), "---" | "..."))
+        {
+            parse_nocite(&lines[1..=end], 2, path, &mut tokens);
+            start = end + 2;
+        }
+    }
+
+    let mut fence: Option<&str> = None;
+    let mut in_comment = false;
+    let mut in_list = false;
+    let mut previous_blank = true;
+    for (i, line) in lines.iter().enumerate().skip(start) {
+        let blank = line.trim().is_empty();
+
+        // Skip fenced code blocks, which end with a fence of the same character at least as long
+        if let Some(open) = fence {
+            let close = line.trim();
+            if close.len() >= open.len() && close.chars().all(|c| open.starts_with(c)) {
+                fence = None;
+            }
+            previous_blank = blank;
+            continue;
+        }
+        if !in_comment {
+            if let Some(caps) = FENCE_REGEX.captures(line) {
+                fence = Some(caps.get(1).unwrap().as_str());
+                previous_blank = false;
+                continue;
+            }
+        }
+
+        // Skip indented code blocks, which (outside of lists) follow a blank line or another indented line
+        let indented = line.starts_with("    ") || line.starts_with('\t');
+        if !in_comment && indented && !in_list && (previous_blank || blank) {
+            continue;
+        }
+        if LIST_ITEM_REGEX.is_match(line) {
+            in_list = true;
+        } else if previous_blank && !blank && !indented {
+            in_list = false;
+        }
+        previous_blank = blank;
+
+        let text = blank_code_spans(line);
+        let text = blank_comments(&text, &mut in_comment, i + 1, path, &mut tokens);
+        parse_citations(&text, i + 1, path, raw_latex, &mut tokens);
+    }
+
+    tokens
+}
+
+/// Replace the given range of the text with spaces, one for each character, so that the columns of the rest of the text are unchanged
+///
+/// Returns the end of the range in the new text
+fn blank(text: &mut String, start: usize, end: usize) -> usize {
+    let n = text[start..end].chars().count();
+    text.replace_range(start..end, &" ".repeat(n));
+    start + n
+}
+
+/// Replace code spans with spaces
+///
+/// A code span starts with a run of backticks and ends with a run of the same length
+fn blank_code_spans(line: &str) -> String {
+    let runs: Vec<(usize, usize)> = CODE_SPAN_REGEX
+        .find_iter(line)
+        .map(|m| (m.start(), m.end()))
+        .collect();
+    let mut spans = Vec::new();
+    let mut i = 0;
+    while i < runs.len() {
+        let (start, open_end) = runs[i];
+        let len = open_end - start;
+        match (i + 1..runs.len()).find(|&j| runs[j].1 - runs[j].0 == len) {
+            Some(j) => {
+                spans.push((start, runs[j].1));
+                i = j + 1;
+            }
+            None => i += 1,
+        }
+    }
+
+    // Blank from the end of the line so that earlier byte offsets are unchanged
+    let mut text = line.to_owned();
+    for (start, end) in spans.into_iter().rev() {
+        blank(&mut text, start, end);
+    }
+    text
+}
+
+/// Replace HTML comments with spaces, queueing any suppression directives within them
+///
+/// Comments may span many lines, so whether we are within one is kept between lines
+fn blank_comments(
+    line: &str,
+    in_comment: &mut bool,
+    line_number: usize,
+    path: &Path,
+    tokens: &mut Vec<Token>,
+) -> String {
+    let mut text = line.to_owned();
+    let mut pos = 0;
+    loop {
+        let (start, content_start) = if *in_comment {
+            (pos, pos)
+        } else {
+            match text[pos..].find("<!--") {
+                Some(offset) => (pos + offset, pos + offset + 4),
+                None => break,
+            }
+        };
+        let close = text[content_start..]
+            .find("-->")
+            .map(|offset| content_start + offset);
+        let content_end = close.unwrap_or(text.len());
+
+        let column = text[..content_start].chars().count() + 1;
+        let location = Location::new(path, line_number, column);
+        if let Some(directive) = Directive::parse(&text[content_start..content_end], location) {
+            tokens.push(Token::Directive(directive));
+        }
+
+        *in_comment = close.is_none();
+        let end = close.map_or(text.len(), |close| close + 3);
+        pos = blank(&mut text, start, end);
+        if *in_comment {
+            break;
+        }
+    }
+    text
+}
+
+/// Queue a token for each key cited on the line, in the order in which they appear
+fn parse_citations(
+    line: &str,
+    line_number: usize,
+    path: &Path,
+    raw_latex: bool,
+    tokens: &mut Vec<Token>,
+) {
+    let mut citations = Vec::new();
+
+    // Raw LaTeX citation commands, which are passed through to LaTeX output
+    let raw_citations = raw_latex
+        .then(|| latex::CITATION_REGEX.captures_iter(line))
+        .into_iter()
+        .flatten();
+    for caps in raw_citations {
+        let cite_cmd = caps.get(1).unwrap().as_str();
+        let keys = caps.get(2).unwrap();
+        let mut offset = keys.start();
+        for key in keys.as_str().split(',') {
+            let trimmed = key.trim();
+            if !trimmed.is_empty() {
+                let start = offset + key.find(trimmed).unwrap();
+                citations.push((start, trimmed, cite_cmd));
+            }
+            offset += key.len() + 1;
+        }
+    }
+
+    let brackets: Vec<(usize, usize)> = BRACKET_REGEX
+        .find_iter(line)
+        .map(|m| (m.start(), m.end()))
+        .collect();
+
+    for caps in CITATION_REGEX.captures_iter(line) {
+        let key = caps.get(2).unwrap();
+        let (start, key_str) = match key.as_str().strip_prefix('{') {
+            Some(braced) => (key.start() + 1, braced.trim_end_matches('}')),
+            None => (
+                key.start(),
+                key.as_str().trim_end_matches(INTERNAL_PUNCTUATION),
+            ),
+        };
+        if key_str.is_empty() {
+            continue;
+        }
+
+        let in_brackets = brackets
+            .iter()
+            .any(|(open, close)| *open < start && start < *close);
+        let cite_cmd = match (in_brackets, caps[1].is_empty()) {
+            (_, false) => SUPPRESS_AUTHOR,
+            (true, true) => NORMAL,
+            (false, true) => AUTHOR_IN_TEXT,
+        };
+        citations.push((start, key_str, cite_cmd));
+    }
+
+    citations.sort_by_key(|(start, _, _)| *start);
+    for (start, key, cite_cmd) in citations {
+        let column = line[..start].chars().count() + 1;
+        tokens.push(Token::Citation(CitationToken {
+            key: key.to_owned(),
+            cite_cmd: cite_cmd.to_owned(),
+            location: Location::new(path, line_number, column),
+            refsection: 0,
+            refsegment: 0,
+        }));
+    }
+}
+
+/// Queue a token for each key in the `nocite` field of the metadata block, which starts on line `first_line`
+///
+/// The field may be given on one line (e.g., `nocite: "@smith2020, @doe2019"`), or as a block on the lines indented below it
+fn parse_nocite(lines: &[&str], first_line: usize, path: &Path, tokens: &mut Vec<Token>) {
+    let Some(field) = lines.iter().position(|line| line.starts_with("nocite:")) else {
+        return;
+    };
+    let block = lines[field + 1..]
+        .iter()
+        .take_while(|line| line.is_empty() || line.starts_with([' ', '\t']))
+        .count();
+
+    let mut citations = Vec::new();
+    for (i, line) in lines[field..=field + block].iter().enumerate() {
+        let text = if i == 0 {
+            " ".repeat("nocite:".len()) + &line["nocite:".len()..]
+        } else {
+            line.to_string()
+        };
+        parse_citations(&text, first_line + field + i, path, false, &mut citations);
+    }
+
+    // `@*` cites every entry, as `\nocite{*}` does, but is not matched as a key
+    for (i, line) in lines[field..=field + block].iter().enumerate() {
+        if let Some(column) = line.find("@*") {
+            citations.push(Token::Citation(CitationToken {
+                key: "*".to_owned(),
+                cite_cmd: NOCITE.to_owned(),
+                location: Location::new(path, first_line + field + i, column + 2),
+                refsection: 0,
+                refsegment: 0,
+            }));
+        }
+    }
+
+    for mut token in citations {
+        if let Token::Citation(citation) = &mut token {
+            citation.cite_cmd = NOCITE.to_owned();
+        }
+        tokens.push(token);
+    }
+}
@@ -2,10 +2,25 @@
 //!
 //! The data that we work with in citati have varying sources, from LaTeX source code to bibliography files.  This module contains submodules that help to handle these required data from various sources.
 
+pub mod auxiliary;
+pub mod bcf;
 pub mod bib;
+pub mod directive;
 pub mod latex;
+mod location;
+pub mod markdown;
+pub mod notebook;
+pub mod org;
+pub mod rst;
+pub mod search;
 mod sources;
+pub mod typst;
 
 pub use bib::BibCitation;
-pub use latex::LaTeXCitation;
-pub use sources::{Abstract, Bib, CitationSource, LaTeX, Source};
+pub use directive::{Directive, Scope};
+pub use latex::{LaTeXCitation, Refsection};
+pub use location::{LineIndex, Location};
+pub use sources::{
+    Abstract, Aux, Bcf, Bib, CitationSource, Document, DocumentSource, LaTeX, Markdown, Markup,
+    Notebook, Org, Rst, Source, Typst,
+};
@@ -0,0 +1,95 @@
+//! Read citations from Jupyter notebooks
+//!
+//! A Jupyter notebook (.ipynb) is JSON containing a list of cells, of which only Markdown cells contain prose.  We read Markdown cells as Pandoc [`markdown`](`super::markdown`), in which LaTeX citation commands (e.g., `\cite{smith2020}`) are citations as well as Pandoc citations (e.g., `[@smith2020]`), and, if asked to, raw cells whose format is LaTeX with the LaTeX [`Lexer`](`super::latex::Lexer`).  Code cells and other raw cells are ignored.  As lines are only meaningful within a cell, locations within notebooks have the (one-based) index of the cell as well as the line and column within it.  See the [nbformat documentation](https://nbformat.readthedocs.io/en/latest/format_description.html) for the format of notebooks.
+
+use super::{
+    latex::{Lexer, Token},
+    markdown,
+};
+use crate::{Error, Result};
+use serde_json::Value;
+use std::path::Path;
+
+/// Formats of raw cells that are LaTeX, from the metadata of the cell
+const LATEX_FORMATS: &[&str] = &["text/latex", "latex"];
+
+/// Source of a cell, which may be given as a string or as a list of lines
+fn cell_source(cell: &Value) -> String {
+    match cell.get("source") {
+        Some(Value::String(src)) => src.clone(),
+        Some(Value::Array(lines)) => lines.iter().filter_map(Value::as_str).collect(),
+        _ => String::new(),
+    }
+}
+
+/// Whether a raw cell is LaTeX, according to its metadata
+fn is_latex(cell: &Value) -> bool {
+    let metadata = &cell["metadata"];
+    ["raw_mimetype", "format"].iter().any(|field| {
+        metadata[field]
+            .as_str()
+            .is_some_and(|format| LATEX_FORMATS.contains(&format.to_lowercase().as_str()))
+    })
+}
+
+/// Parse the citations and suppression directives in notebook source, with a (possibly virtual) path at which they are reported
+///
+/// Raw LaTeX cells are only read if `latex_cells` is set
+///
+/// ```
+/// use citati::source::{latex::Token, notebook};
+/// use std::path::Path;
+///
+/// let src = r##"{"cells": [
+///     {"cell_type": "code", "source": ["x = '[@notacitation]'"]},
+///     {"cell_type": "markdown", "source": ["# Results\n", "As in [@smith2020] and \\cite{doe2019}."]}
+/// ]}"##;
+/// let citations: Vec<_> = notebook::parse_notebook_from_str(src, Path::new("paper.ipynb"), false)?
+///     .into_iter()
+///     .filter_map(|token| match token {
+///         Token::Citation(citation) => Some(citation),
+///         _ => None,
+///     })
+///     .collect();
+/// assert_eq!(citations[0].key, "smith2020");
+/// assert_eq!(citations[1].key, "doe2019");
+/// assert_eq!(citations[0].location.cell, Some(2));
+/// assert_eq!(citations[0].location.line, 2);
+/// # Ok::<(), citati::Error>(())
+/// ```
+pub fn parse_notebook_from_str(src: &str, path: &Path, latex_cells: bool) -> Result<Vec<Token>> {
+    let notebook: Value = serde_json::from_str(src)
+        .map_err(|e| Error::Parse(format!("Could not parse notebook {path:?}: {e}")))?;
+    let Some(cells) = notebook["cells"].as_array() else {
+        return Err(Error::Parse(format!("Notebook {path:?} has no cells")));
+    };
+
+    let mut tokens = Vec::new();
+    for (i, cell) in cells.iter().enumerate() {
+        let cell_tokens = match cell["cell_type"].as_str() {
+            Some("markdown") => markdown::parse_markdown_cell_from_str(&cell_source(cell), path),
+            Some("raw") if latex_cells && is_latex(cell) => {
+                let cell_src = cell_source(cell);
+                let mut lexer = Lexer::from_source(&cell_src, path);
+                std::iter::from_fn(|| lexer.next_token().transpose()).collect::<Result<_>>()?
+            }
+            _ => continue,
+        };
+
+        // Tokens from files included with `\input{}` are not in the cell
+        tokens.extend(cell_tokens.into_iter().map(|mut token| {
+            let location = match &mut token {
+                Token::Citation(citation) => Some(&mut citation.location),
+                Token::Directive(directive) => Some(&mut directive.location),
+                Token::Bibliography(printed) => Some(&mut printed.location),
+                Token::Refsection(_) | Token::Other => None,
+            };
+            if let Some(location) = location.filter(|location| location.file == path) {
+                location.cell = Some(i + 1);
+            }
+            token
+        }));
+    }
+
+    Ok(tokens)
+}
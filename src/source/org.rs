@@ -0,0 +1,197 @@
+//! Read citations from Org documents
+//!
+//! Org cites keys either with org-cite, as in `[cite:@smith2020]` or `[cite/t:see @smith2020 p. 4; @doe2019]`, or with org-ref links, as in `cite:smith2020`, `citep:smith2020,doe2019`, or `cite:&smith2020;&doe2019`.  Nothing within comments (lines starting with `#`, or `#+begin_comment` blocks), source or example blocks, or inline code and verbatim text (`~...~` and `=...=`) is a citation.  We also record the bibliography files given with `#+bibliography:`.  Suppression directives (see [`directive`](`super::directive`)) can be given in comments, as in `# citati-ignore-next-line`.  See the [Org manual](https://orgmode.org/manual/Citations.html) for the org-cite syntax.
+
+use super::{
+    directive::Directive,
+    latex::{CitationToken, Token},
+    location::Location,
+};
+use crate::{Error, Result};
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+lazy_static! {
+    static ref ORG_CITE_REGEX: Regex = Regex::new(r"\[cite(?:/([^:\]]*))?:([^\]]*)\]").unwrap();
+    static ref ORG_CITE_KEY_REGEX: Regex = Regex::new(r"@([\w\-.:?!'/*+|<>&^$#%~]+)").unwrap();
+    static ref ORG_REF_REGEX: Regex =
+        Regex::new(r"(?:^|[^\w:])([a-zA-Z]*cite[a-zA-Z]*\*?):(&?[\w\-.:/]+(?:[,;]&?[\w\-.:/]+)*)")
+            .unwrap();
+    static ref ORG_REF_KEY_REGEX: Regex = Regex::new(r"&?([\w\-.:/]+)").unwrap();
+    static ref INLINE_CODE_REGEX: Regex =
+        Regex::new(r"(?:^|[\s(])([~=])[^\s~=](?:[^~=]*[^\s~=])?([~=])").unwrap();
+    static ref BLOCK_REGEX: Regex = Regex::new(r"(?i)^\s*#\+begin_(\w+)").unwrap();
+    static ref BIBLIOGRAPHY_REGEX: Regex = Regex::new(r"(?i)^\s*#\+bibliography:\s*(.+)$").unwrap();
+}
+
+/// Punctuation that may be within a key, but not at its end
+const TRAILING_PUNCTUATION: &[char] = &['.', ':', '?', '!', ',', ';'];
+
+/// Blocks whose contents are not prose, and so cannot contain citations
+const VERBATIM_BLOCKS: &[&str] = &["comment", "src", "example", "export"];
+
+/// Citations, directives, and bibliographies found in an Org document
+pub struct OrgDocument {
+    /// Citations and suppression directives, in the order in which they are found
+    pub tokens: Vec<Token>,
+
+    /// Paths of the bibliography files given with `#+bibliography:`, relative to the document
+    pub bibliographies: Vec<PathBuf>,
+}
+
+/// Parse the citations, suppression directives, and bibliographies in the Org file
+pub fn parse_org_from_file(org_file: &str) -> Result<OrgDocument> {
+    let src = fs::read_to_string(org_file)
+        .map_err(Error::io(format!("Could not read Org file {org_file:?}")))?;
+    Ok(parse_org_from_str(&src, Path::new(org_file)))
+}
+
+/// Parse the citations, suppression directives, and bibliographies in Org source, with a (possibly virtual) path at which they are reported
+///
+/// ```
+/// use citati::source::{latex::Token, org};
+/// use std::path::Path;
+///
+/// let src = "See [cite/t:@smith2020; @doe2019] and citep:jones2018, not ~cite:code~.\n";
+/// let document = org::parse_org_from_str(src, Path::new("notes.org"));
+/// let keys: Vec<String> = document
+///     .tokens
+///     .into_iter()
+///     .filter_map(|token| match token {
+///         Token::Citation(citation) => Some(citation.key),
+///         _ => None,
+///     })
+///     .collect();
+/// assert_eq!(keys, ["smith2020", "doe2019", "jones2018"]);
+/// ```
+pub fn parse_org_from_str(src: &str, path: &Path) -> OrgDocument {
+    let mut tokens = Vec::new();
+    let mut bibliographies = Vec::new();
+    let base_path = path.parent().unwrap_or(Path::new(""));
+
+    // Name of the verbatim block that we are within, if any
+    let mut block: Option<String> = None;
+    for (i, line) in src.lines().enumerate() {
+        let trimmed = line.trim_start();
+
+        if let Some(name) = &block {
+            if trimmed.to_lowercase().starts_with(&format!("#+end_{name}")) {
+                block = None;
+            }
+            continue;
+        }
+        if let Some(caps) = BLOCK_REGEX.captures(line) {
+            let name = caps[1].to_lowercase();
+            if VERBATIM_BLOCKS.contains(&name.as_str()) {
+                block = Some(name);
+            }
+            continue;
+        }
+
+        if let Some(caps) = BIBLIOGRAPHY_REGEX.captures(line) {
+            bibliographies.extend(
+                caps[1]
+                    .split([',', ' '])
+                    .filter(|bib_file| !bib_file.is_empty())
+                    .map(|bib_file| base_path.join(bib_file)),
+            );
+            continue;
+        }
+
+        // Comments are lines starting with `#` followed by a space (or nothing)
+        if trimmed == "#" || trimmed.starts_with("# ") {
+            let column = line.len() - trimmed.len() + 2;
+            let location = Location::new(path, i + 1, column);
+            if let Some(directive) = Directive::parse(&trimmed[1..], location) {
+                tokens.push(Token::Directive(directive));
+            }
+            continue;
+        }
+
+        parse_citations(&blank_inline_code(line), i + 1, path, &mut tokens);
+    }
+
+    OrgDocument {
+        tokens,
+        bibliographies,
+    }
+}
+
+/// Replace inline code and verbatim text with spaces, so that the columns of the rest of the line are unchanged
+fn blank_inline_code(line: &str) -> String {
+    let mut text = line.to_owned();
+    let spans: Vec<(usize, usize)> = INLINE_CODE_REGEX
+        .captures_iter(line)
+        .filter(|caps| caps[1] == caps[2])
+        .map(|caps| (caps.get(1).unwrap().start(), caps.get(2).unwrap().end()))
+        .collect();
+    for (start, end) in spans.into_iter().rev() {
+        let n = text[start..end].chars().count();
+        text.replace_range(start..end, &" ".repeat(n));
+    }
+    text
+}
+
+/// Queue a token for each key cited on the line, in the order in which they appear
+fn parse_citations(line: &str, line_number: usize, path: &Path, tokens: &mut Vec<Token>) {
+    let mut citations = Vec::new();
+
+    // org-cite citations, which we blank once read so that their contents are
+    // not mistaken for org-ref links (e.g., in `[cite:see cite:@key]`)
+    let mut text = line.to_owned();
+    for caps in ORG_CITE_REGEX.captures_iter(line) {
+        let style = caps.get(1).map_or("", |m| m.as_str());
+        let cite_cmd = if style.is_empty() {
+            "cite".to_owned()
+        } else {
+            format!("cite/{style}")
+        };
+        let contents = caps.get(2).unwrap();
+        for key_caps in ORG_CITE_KEY_REGEX.captures_iter(contents.as_str()) {
+            let key = key_caps.get(1).unwrap();
+            let key_str = key.as_str().trim_end_matches(TRAILING_PUNCTUATION);
+            citations.push((
+                contents.start() + key.start(),
+                key_str.to_owned(),
+                cite_cmd.clone(),
+            ));
+        }
+
+        let whole = caps.get(0).unwrap();
+        text.replace_range(whole.range(), &" ".repeat(whole.len()));
+    }
+
+    // org-ref links
+    for caps in ORG_REF_REGEX.captures_iter(&text) {
+        let cite_cmd = caps[1].to_owned();
+        let keys = caps.get(2).unwrap();
+        for key_caps in ORG_REF_KEY_REGEX.captures_iter(keys.as_str()) {
+            let key = key_caps.get(1).unwrap();
+            let key_str = key.as_str().trim_end_matches(TRAILING_PUNCTUATION);
+            citations.push((
+                keys.start() + key.start(),
+                key_str.to_owned(),
+                cite_cmd.clone(),
+            ));
+        }
+    }
+
+    citations.sort_by_key(|(start, _, _)| *start);
+    for (start, key, cite_cmd) in citations {
+        if key.is_empty() {
+            continue;
+        }
+        let column = line[..start].chars().count() + 1;
+        tokens.push(Token::Citation(CitationToken {
+            key,
+            cite_cmd,
+            location: Location::new(path, line_number, column),
+            refsection: 0,
+            refsegment: 0,
+        }));
+    }
+}
@@ -0,0 +1,164 @@
+//! Read citations from reStructuredText documents
+//!
+//! Sphinx (with the sphinxcontrib-bibtex extension) cites keys in reStructuredText with roles, as in ``:cite:`smith2020` ``, ``:cite:p:`smith2020,doe2019` ``, or ``:footcite:t:`smith2020` ``, where pre- and post-notes may be given in braces (e.g., ``:cite:p:`{see}smith2020{p. 4}` ``).  A `.. bibliography::` directive with the `:all:` option cites every entry, as `\nocite{*}` does.  Nothing within comments, literal blocks (following `::`, or in `code-block` and similar directives), or inline literals (` ``...`` `) is a citation.  Suppression directives (see [`directive`](`super::directive`)) can be given in comments, as in `.. citati-ignore-next-line`.  See the [sphinxcontrib-bibtex documentation](https://sphinxcontrib-bibtex.readthedocs.io/en/latest/usage.html#roles-and-directives) for the full syntax.
+
+use super::{
+    directive::Directive,
+    latex::{CitationToken, Token},
+    location::Location,
+};
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::path::Path;
+
+lazy_static! {
+    static ref ROLE_REGEX: Regex = Regex::new(r":((?:foot)?cite(?::[a-z]+)?):`([^`]*)`").unwrap();
+    static ref NOTE_REGEX: Regex = Regex::new(r"\{[^}]*\}").unwrap();
+    static ref INLINE_LITERAL_REGEX: Regex = Regex::new(r"``.*?``").unwrap();
+    static ref EXPLICIT_MARKUP_REGEX: Regex = Regex::new(r"^(\s*)\.\.(?:\s+(.*))?$").unwrap();
+    static ref DIRECTIVE_REGEX: Regex = Regex::new(r"^([\w\-:+.]+)::(?:\s|$)").unwrap();
+}
+
+/// Directives whose contents are code, and so cannot contain citations
+const LITERAL_DIRECTIVES: &[&str] = &["code", "code-block", "sourcecode", "literalinclude", "math"];
+
+/// What the indented block that we are within, if any, contains
+enum Block {
+    /// A comment, which ends at the first line that is less indented than the given indentation
+    Comment(usize),
+
+    /// A literal block or code, which ends at the first line that is less indented than the given indentation
+    Literal(usize),
+
+    /// A `bibliography` directive, whose options we read
+    Bibliography(usize),
+}
+
+/// Indentation of a line, in characters
+fn indentation(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+/// Parse the citations and suppression directives in reStructuredText source, with a (possibly virtual) path at which they are reported
+///
+/// ```
+/// use citati::source::{latex::Token, rst};
+/// use std::path::Path;
+///
+/// let src = "See :cite:p:`{see}smith2020{p. 4},doe2019` but not ``:cite:`code` ``.\n";
+/// let keys: Vec<String> = rst::parse_rst_from_str(src, Path::new("index.rst"))
+///     .into_iter()
+///     .filter_map(|token| match token {
+///         Token::Citation(citation) => Some(citation.key),
+///         _ => None,
+///     })
+///     .collect();
+/// assert_eq!(keys, ["smith2020", "doe2019"]);
+/// ```
+pub fn parse_rst_from_str(src: &str, path: &Path) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut block: Option<Block> = None;
+
+    // Whether the previous paragraph ended with `::`, so that the next indented block is literal
+    let mut literal_next = false;
+    let mut paragraph_indent = 0;
+
+    for (i, line) in src.lines().enumerate() {
+        let blank = line.trim().is_empty();
+        let indent = indentation(line);
+
+        // Indented blocks continue until a non-blank line that is no more indented than their parent
+        if let Some(Block::Comment(parent) | Block::Literal(parent) | Block::Bibliography(parent)) =
+            &block
+        {
+            if blank || indent > *parent {
+                if let Some(Block::Bibliography(_)) = &block {
+                    if line.trim() == ":all:" {
+                        tokens.push(Token::Citation(CitationToken {
+                            key: "*".to_owned(),
+                            cite_cmd: "bibliography".to_owned(),
+                            location: Location::new(path, i + 1, indent + 1),
+                            refsection: 0,
+                            refsegment: 0,
+                        }));
+                    }
+                }
+                continue;
+            }
+            block = None;
+        }
+
+        if blank {
+            continue;
+        }
+        if literal_next && indent > paragraph_indent {
+            literal_next = false;
+            block = Some(Block::Literal(paragraph_indent));
+            continue;
+        }
+
+        // Explicit markup: comments, directives, targets, and footnotes
+        if let Some(caps) = EXPLICIT_MARKUP_REGEX.captures(line) {
+            let text = caps.get(2).map_or("", |m| m.as_str());
+            if let Some(directive_caps) = DIRECTIVE_REGEX.captures(text) {
+                let name = &directive_caps[1];
+                if LITERAL_DIRECTIVES.contains(&name) {
+                    block = Some(Block::Literal(indent));
+                    continue;
+                }
+                if name == "bibliography" {
+                    block = Some(Block::Bibliography(indent));
+                    continue;
+                }
+            } else if !text.starts_with(['_', '[', '|']) {
+                let column = caps.get(2).map_or(line.len(), |m| m.start()) + 1;
+                let location = Location::new(path, i + 1, column);
+                if let Some(directive) = Directive::parse(text, location) {
+                    tokens.push(Token::Directive(directive));
+                }
+                block = Some(Block::Comment(indent));
+                continue;
+            }
+        }
+
+        paragraph_indent = indent;
+        literal_next = line.trim_end().ends_with("::");
+        parse_citations(line, i + 1, path, &mut tokens);
+    }
+
+    tokens
+}
+
+/// Queue a token for each key cited on the line, in the order in which they appear
+fn parse_citations(line: &str, line_number: usize, path: &Path, tokens: &mut Vec<Token>) {
+    // Blank inline literals, keeping byte offsets the same
+    let mut text = line.to_owned();
+    for m in INLINE_LITERAL_REGEX.find_iter(line) {
+        text.replace_range(m.range(), &" ".repeat(m.len()));
+    }
+
+    for caps in ROLE_REGEX.captures_iter(&text) {
+        let cite_cmd = &caps[1];
+        let keys = caps.get(2).unwrap();
+
+        // Replace pre- and post-notes with commas, so that the keys around them are split
+        let keys_text =
+            NOTE_REGEX.replace_all(keys.as_str(), |m: &regex::Captures| ",".repeat(m[0].len()));
+        let mut offset = keys.start();
+        for key in keys_text.split(',') {
+            let trimmed = key.trim();
+            if !trimmed.is_empty() {
+                let start = offset + key.find(trimmed).unwrap();
+                let column = line[..start].chars().count() + 1;
+                tokens.push(Token::Citation(CitationToken {
+                    key: trimmed.to_owned(),
+                    cite_cmd: cite_cmd.to_owned(),
+                    location: Location::new(path, line_number, column),
+                    refsection: 0,
+                    refsegment: 0,
+                }));
+            }
+            offset += key.len() + 1;
+        }
+    }
+}
@@ -0,0 +1,130 @@
+//! Search paths for files included in LaTeX source, and for bibliography files
+//!
+//! LaTeX and BibTeX find files using kpathsea, which looks in the directory in which the document is compiled and then in the directories of a search path, given by the TEXINPUTS environment variable for files included with `\input{}` and by BIBINPUTS for bibliography files.  We mirror its syntax without depending on an installed TeX distribution: a search path is a list of directories separated by `:` (or `;` on Windows), where a directory ending in `//` also includes all of its subdirectories, and `dir//sub` includes every subdirectory named `sub` below `dir`.  Empty entries, which kpathsea expands to the default path of the distribution, are ignored, as is the `!!` prefix, which restricts kpathsea to its ls-R database, as we do not read the distribution's configuration.  Relative directories are relative to the directory in which the document is compiled (i.e., that of its root file), and hidden directories (e.g., `.git`) and `node_modules` are not searched below a recursive directory.  The library only searches the paths it is given (see [`CitationSource::with_texinputs`](`super::CitationSource::with_texinputs`)); it is `citati check` that reads them from the environment, unless they are given with its `--texinputs` and `--bibinputs` options.
+
+use super::latex::normalise;
+use std::{
+    cell::OnceCell,
+    ffi::OsStr,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Environment variable giving the search path for files included in LaTeX source
+pub const TEXINPUTS: &str = "TEXINPUTS";
+
+/// Environment variable giving the search path for bibliography files
+pub const BIBINPUTS: &str = "BIBINPUTS";
+
+/// Separator of directories in a search path, as in kpathsea
+const SEPARATOR: char = if cfg!(windows) { ';' } else { ':' };
+
+/// Directories in which to search for files, in order
+///
+/// Recursive directories are only expanded into their subdirectories when a search first reaches them, and are expanded once, so that a search path can be built without walking directories that are never searched
+#[derive(Clone, Debug, Default)]
+pub struct SearchPath {
+    entries: Vec<Entry>,
+}
+
+/// Directory of a search path, which may include its subdirectories
+#[derive(Clone, Debug)]
+enum Entry {
+    Dir(PathBuf),
+    Recursive {
+        root: PathBuf,
+
+        /// Trailing components that subdirectories must have to be searched (e.g., `sub` in `dir//sub`), which are empty if every subdirectory is
+        suffix: PathBuf,
+
+        /// The root and its matching subdirectories, once they have been walked
+        dirs: OnceCell<Vec<PathBuf>>,
+    },
+}
+
+impl SearchPath {
+    /// Parse a search path, resolving relative directories against `base`
+    ///
+    /// ```
+    /// use citati::source::search::SearchPath;
+    /// use std::path::Path;
+    /// # let base = std::env::temp_dir().join("citati-doc-search");
+    /// # std::fs::create_dir_all(base.join("styles/journal")).unwrap();
+    /// # std::fs::write(base.join("styles/journal/macros.tex"), "").unwrap();
+    ///
+    /// let search_path = SearchPath::parse("styles//:", &base);
+    /// let found = search_path.find(Path::new("macros.tex")).unwrap();
+    /// assert!(found.ends_with("styles/journal/macros.tex"));
+    /// ```
+    pub fn parse(spec: &str, base: &Path) -> Self {
+        let entries = spec
+            .split(SEPARATOR)
+            .map(|entry| entry.trim_start_matches("!!"))
+            .filter(|entry| !entry.is_empty())
+            .map(|entry| match entry.split_once("//") {
+                Some((prefix, suffix)) => Entry::Recursive {
+                    root: normalise(&base.join(prefix)),
+                    suffix: PathBuf::from(suffix.trim_matches('/')),
+                    dirs: OnceCell::new(),
+                },
+                None => Entry::Dir(normalise(&base.join(entry))),
+            })
+            .collect();
+        Self { entries }
+    }
+
+    /// Directories of the search path, in the order in which they are searched
+    ///
+    /// Recursive directories are walked as the iterator reaches them
+    pub fn dirs(&self) -> impl Iterator<Item = &Path> {
+        self.entries.iter().flat_map(|entry| {
+            let dirs = match entry {
+                Entry::Dir(dir) => std::slice::from_ref(dir),
+                Entry::Recursive { root, suffix, dirs } => dirs.get_or_init(|| {
+                    let mut subdirs = vec![root.clone()];
+                    subdirectories(root, &mut subdirs);
+                    subdirs.retain(|dir| dir.ends_with(suffix));
+                    subdirs
+                }),
+            };
+            dirs.iter().map(PathBuf::as_path)
+        })
+    }
+
+    /// First file of the given (relative) name within the directories of the search path, if any
+    pub fn find(&self, name: &Path) -> Option<PathBuf> {
+        self.dirs()
+            .map(|dir| dir.join(name))
+            .find(|path| path.is_file())
+    }
+}
+
+/// Whether a subdirectory is skipped when walking a recursive directory: hidden directories (e.g., `.git`), and `node_modules`, which never hold files for TeX but may hold very many others
+fn skipped(name: &OsStr) -> bool {
+    name.to_str()
+        .is_some_and(|name| name.starts_with('.') || name == "node_modules")
+}
+
+/// Add the subdirectories of the directory that are not [`skipped`], recursively and in order of their names, to `dirs`
+fn subdirectories(dir: &Path, dirs: &mut Vec<PathBuf>) {
+    // The current directory is the empty path once normalised
+    let read_dir = if dir.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        dir
+    };
+    let Ok(entries) = fs::read_dir(read_dir) else {
+        return;
+    };
+    let mut subdirs: Vec<PathBuf> = entries
+        .filter_map(Result::ok)
+        .filter(|entry| !skipped(&entry.file_name()))
+        .map(|entry| dir.join(entry.file_name()))
+        .filter(|path| path.is_dir())
+        .collect();
+    subdirs.sort();
+    for subdir in subdirs {
+        dirs.push(subdir.clone());
+        subdirectories(&subdir, dirs);
+    }
+}
@@ -2,8 +2,12 @@
 //!
 //! Implement singleton types (i.e., [`LaTeX`] and [`Bib`]) that pertain to different data sources.  We also define the `Abstract` type, which is useful downstream (see [`GatherCitations`](`crate::citations::GatherCitations`))
 
-use super::bib::BibCitation;
-use super::latex::LaTeXCitation;
+use super::bib::{self, BibCitation};
+use super::latex::{CitationToken, LaTeXCitation, Lexer, PrintedBibliography, Refsection, Token};
+use super::search::SearchPath;
+use super::{auxiliary, bcf, markdown, notebook, org, rst, typst};
+use crate::{Error, Result};
+use std::{borrow::Cow, cell::OnceCell, fs, io::BufRead, path::Path, rc::Rc};
 
 /*
   Singleton types to denote the source of some citations
@@ -15,6 +19,23 @@ use super::latex::LaTeXCitation;
 pub struct LaTeX;
 /// Singleton bib source type
 pub struct Bib;
+/// Singleton auxiliary file source type
+pub struct Aux;
+/// Singleton biblatex control file source type
+pub struct Bcf;
+/// Singleton Markdown source type
+pub struct Markdown;
+/// Singleton Typst source type
+pub struct Typst;
+/// Singleton Org source type
+pub struct Org;
+/// Singleton reStructuredText source type
+pub struct Rst;
+
+/// Singleton Jupyter notebook source type
+pub struct Notebook;
+/// Singleton source type of the citations of a document, from whichever file they are read
+pub struct Document;
 /// Singleton abstract source type
 pub struct Abstract;
 
@@ -43,6 +64,60 @@ impl Source for Bib {
     type CitationType = BibCitation;
 }
 
+/// The source is an auxiliary file written by LaTeX (.aux)
+///
+/// Citations in auxiliary files have the same information as those in LaTeX source, except that their locations are in the auxiliary file
+impl Source for Aux {
+    type CitationType = LaTeXCitation;
+}
+
+/// The source is a control file written by biblatex for biber (.bcf)
+///
+/// Citations in control files are given the command `nocite` if they were only cited with `\nocite`, and `cite` otherwise
+impl Source for Bcf {
+    type CitationType = LaTeXCitation;
+}
+
+/// The source is Pandoc Markdown (.md)
+///
+/// Citations in Markdown have the same information as those in LaTeX source, where the citation command is Pandoc's citation mode (e.g., `author-in-text`)
+impl Source for Markdown {
+    type CitationType = LaTeXCitation;
+}
+
+/// The source is a Typst document (.typ)
+///
+/// Citations in Typst have the same information as those in LaTeX source, where the citation command is `@` for references in markup, and `cite` for the `cite` function
+impl Source for Typst {
+    type CitationType = LaTeXCitation;
+}
+
+/// The source is an Org document (.org)
+///
+/// Citations in Org have the same information as those in LaTeX source, where the citation command is, e.g., `cite/t` for org-cite, or `citep` for org-ref
+impl Source for Org {
+    type CitationType = LaTeXCitation;
+}
+
+/// The source is a reStructuredText document (.rst)
+///
+/// Citations in reStructuredText have the same information as those in LaTeX source, where the citation command is the role (e.g., `cite:p`)
+impl Source for Rst {
+    type CitationType = LaTeXCitation;
+}
+
+/// The source is a Jupyter notebook (.ipynb)
+///
+/// Citations in notebooks have the same information as those in LaTeX source or Markdown, depending on the cell in which they are found
+impl Source for Notebook {
+    type CitationType = LaTeXCitation;
+}
+
+/// The source is whichever file the citations of a document are read from: its control file or auxiliary file (in that order of preference) where given, and otherwise the document itself, in its markup language
+impl Source for Document {
+    type CitationType = LaTeXCitation;
+}
+
 /// Abstract source, where the source is technically unspecified or indeterminate
 impl Source for Abstract {
     type CitationType = ();
@@ -54,38 +129,439 @@ impl Source for Abstract {
 //     type CitationType = Citation;
 // }
 
+/// Trait to denote a source from which citations are read as the tokens of a document
+///
+/// Citations of any such source can be gathered (see [`GatherCitations`](`crate::citations::GatherCitations`)), so that supporting another markup language only requires its source type to implement this trait, and [`Markup::tokens`] to dispatch to it
+pub trait DocumentSource: Source<CitationType = LaTeXCitation> {
+    /// Tokens read from the source, in the order in which they are found
+    fn tokens(src: &CitationSource) -> Result<Vec<Token>>;
+}
+
+/// Citation tokens, as read from files that only list citations
+fn citation_tokens(citations: Vec<CitationToken>) -> Vec<Token> {
+    citations.into_iter().map(Token::Citation).collect()
+}
+
+impl DocumentSource for LaTeX {
+    fn tokens(src: &CitationSource) -> Result<Vec<Token>> {
+        let mut lexer = src.latex_lexer()?;
+        std::iter::from_fn(|| lexer.next_token().transpose()).collect()
+    }
+}
+
+impl DocumentSource for Aux {
+    fn tokens(src: &CitationSource) -> Result<Vec<Token>> {
+        let citations = auxiliary::parse_aux_citations_from_file(src.aux_path()?)?;
+        Ok(citation_tokens(citations))
+    }
+}
+
+impl DocumentSource for Bcf {
+    fn tokens(src: &CitationSource) -> Result<Vec<Token>> {
+        Ok(citation_tokens(src.control_file()?.citations()))
+    }
+}
+
+impl DocumentSource for Markdown {
+    fn tokens(src: &CitationSource) -> Result<Vec<Token>> {
+        let tokens =
+            markdown::parse_markdown_from_str(&src.latex_contents()?, Path::new(src.latex_path()?));
+        Ok(tokens)
+    }
+}
+
+impl DocumentSource for Typst {
+    fn tokens(src: &CitationSource) -> Result<Vec<Token>> {
+        let document =
+            typst::parse_typst_from_str(&src.latex_contents()?, Path::new(src.latex_path()?))?;
+        Ok(document.tokens)
+    }
+}
+
+impl DocumentSource for Org {
+    fn tokens(src: &CitationSource) -> Result<Vec<Token>> {
+        let document =
+            org::parse_org_from_str(&src.latex_contents()?, Path::new(src.latex_path()?));
+        Ok(document.tokens)
+    }
+}
+
+impl DocumentSource for Rst {
+    fn tokens(src: &CitationSource) -> Result<Vec<Token>> {
+        let tokens = rst::parse_rst_from_str(&src.latex_contents()?, Path::new(src.latex_path()?));
+        Ok(tokens)
+    }
+}
+
+impl DocumentSource for Notebook {
+    fn tokens(src: &CitationSource) -> Result<Vec<Token>> {
+        notebook::parse_notebook_from_str(
+            &src.latex_contents()?,
+            Path::new(src.latex_path()?),
+            src.latex_cells,
+        )
+    }
+}
+
+impl DocumentSource for Document {
+    fn tokens(src: &CitationSource) -> Result<Vec<Token>> {
+        Ok(src.document_tokens()?.to_vec())
+    }
+}
+
+/// Markup language in which a document is written
+///
+/// Documents are recognised by their extension (see [`Markup::from_path`]), and their citations are read as the same tokens as those of LaTeX source (see [`Markup::tokens`]), so that every check applies to them alike
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Markup {
+    LaTeX,
+    Markdown,
+    Typst,
+    Org,
+    Rst,
+    Notebook,
+}
+
+impl Markup {
+    /// Determine the markup language of a document from the extension of its path, which is LaTeX unless otherwise recognised
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("md" | "markdown") => Markup::Markdown,
+            Some("typ") => Markup::Typst,
+            Some("org") => Markup::Org,
+            Some("rst") => Markup::Rst,
+            Some("ipynb") => Markup::Notebook,
+            _ => Markup::LaTeX,
+        }
+    }
+
+    /// Tokens of the document given as the LaTeX file of the source, read as written in this markup language
+    pub fn tokens(self, src: &CitationSource) -> Result<Vec<Token>> {
+        match self {
+            Markup::LaTeX => LaTeX::tokens(src),
+            Markup::Markdown => Markdown::tokens(src),
+            Markup::Typst => Typst::tokens(src),
+            Markup::Org => Org::tokens(src),
+            Markup::Rst => Rst::tokens(src),
+            Markup::Notebook => Notebook::tokens(src),
+        }
+    }
+}
+
 /// Source of citations being used or defined
 ///
-/// Either from bibliography file (.bib) or LaTeX file(s) (.tex)
+/// Either from bibliography file (.bib) or LaTeX file(s) (.tex), which may instead be in another markup language (see [`Markup`]).  The contents of either can instead be given in memory (e.g., from standard input, or an unsaved buffer in an editor), in which case the file path is virtual: it is only used to report locations and, for LaTeX source, to find files included with `\input{}`
 pub struct CitationSource<'a> {
     pub latex_file: Option<&'a str>,
     pub bib_file: Option<&'a str>,
+
+    /// Contents of the LaTeX file, if it should not be read from `latex_file`
+    pub latex_src: Option<&'a str>,
+
+    /// Contents of the bib file, if it should not be read from `bib_file`
+    pub bib_src: Option<&'a str>,
+
+    /// Auxiliary file written by LaTeX, from which citations are read instead of the LaTeX source, if given
+    pub aux_file: Option<&'a str>,
+
+    /// Control file written by biblatex, from which citations are read instead of the LaTeX source or auxiliary file, if given
+    pub bcf_file: Option<&'a str>,
+
+    /// Search path for files included in the LaTeX source, if any (see [`search`](`super::search`))
+    pub texinputs: Option<&'a str>,
+
+    /// Search path for bibliography files, if any
+    pub bibinputs: Option<&'a str>,
+
+    /// Whether raw LaTeX cells of a Jupyter notebook are read, as well as its Markdown cells
+    pub latex_cells: bool,
+
+    /// Control file, once it has been parsed
+    control_file: OnceCell<bcf::ControlFile>,
+
+    /// Tokens of the document, once they have been read
+    document_tokens: OnceCell<Vec<Token>>,
+
+    /// Search paths, once they have been parsed
+    tex_search_path: OnceCell<Rc<SearchPath>>,
+    bib_search_path: OnceCell<Rc<SearchPath>>,
 }
 
 /// Convenient implementations for construction of `CitationSource`
 impl<'a> CitationSource<'a> {
     /// Create a new `CitationSource` object from both source files
     pub fn new(latex_file: &'a str, bib_file: &'a str) -> Self {
-        Self {
-            latex_file: Some(latex_file),
-            bib_file: Some(bib_file),
-        }
+        Self::from_files(Some(latex_file), Some(bib_file))
     }
 
-    // NOTE: the following can be uncommented if/when needed
-    // /// Create a new `CitationSource` object from just LaTeX file
-    // pub fn from_latex(latex_file: &'a str) -> Self {
-    //     Self {
-    //         latex_file: Some(latex_file),
-    //         bib_file: None,
-    //     }
-    // }
+    /// Create a new `CitationSource` object from just LaTeX file
+    pub fn from_latex(latex_file: &'a str) -> Self {
+        Self::from_files(Some(latex_file), None)
+    }
 
     /// Create a new `CitationSource` object from just bib file
     pub fn from_bib(bib_file: &'a str) -> Self {
+        Self::from_files(None, Some(bib_file))
+    }
+
+    /// Create a new `CitationSource` object from whichever source files are given, with every other option unset
+    fn from_files(latex_file: Option<&'a str>, bib_file: Option<&'a str>) -> Self {
         Self {
-            latex_file: None,
-            bib_file: Some(bib_file),
+            latex_file,
+            bib_file,
+            latex_src: None,
+            bib_src: None,
+            aux_file: None,
+            bcf_file: None,
+            texinputs: None,
+            bibinputs: None,
+            latex_cells: false,
+            control_file: OnceCell::new(),
+            document_tokens: OnceCell::new(),
+            tex_search_path: OnceCell::new(),
+            bib_search_path: OnceCell::new(),
+        }
+    }
+
+    /// Use the given contents of the LaTeX file rather than reading it
+    ///
+    /// ```
+    /// use citati::{missing, source::CitationSource};
+    ///
+    /// let src = CitationSource::new("document.tex", "references.bib")
+    ///     .with_latex_src("See \\cite{smith2020}.\n")
+    ///     .with_bib_src("@book{doe2019, title = {Book}}\n");
+    /// let findings = missing::missing_citations(&src)?;
+    /// assert_eq!(findings[0].key, "smith2020");
+    /// # Ok::<(), citati::Error>(())
+    /// ```
+    pub fn with_latex_src(mut self, latex_src: &'a str) -> Self {
+        self.latex_src = Some(latex_src);
+        self.document_tokens = OnceCell::new();
+        self
+    }
+
+    /// Use the given contents of the bib file rather than reading it
+    pub fn with_bib_src(mut self, bib_src: &'a str) -> Self {
+        self.bib_src = Some(bib_src);
+        self
+    }
+
+    /// Read citations from the given auxiliary file rather than lexing the LaTeX source
+    pub fn with_aux_file(mut self, aux_file: &'a str) -> Self {
+        self.aux_file = Some(aux_file);
+        self.document_tokens = OnceCell::new();
+        self
+    }
+
+    /// Read citations from the given biblatex control file rather than lexing the LaTeX source
+    pub fn with_bcf_file(mut self, bcf_file: &'a str) -> Self {
+        self.bcf_file = Some(bcf_file);
+        self.control_file = OnceCell::new();
+        self.document_tokens = OnceCell::new();
+        self
+    }
+
+    /// Search for files included in the LaTeX source in the given search path (e.g., that of the TEXINPUTS environment variable)
+    pub fn with_texinputs(mut self, texinputs: &'a str) -> Self {
+        self.texinputs = Some(texinputs);
+        self.tex_search_path = OnceCell::new();
+        self.document_tokens = OnceCell::new();
+        self
+    }
+
+    /// Search for bibliography files in the given search path (e.g., that of the BIBINPUTS environment variable)
+    pub fn with_bibinputs(mut self, bibinputs: &'a str) -> Self {
+        self.bibinputs = Some(bibinputs);
+        self.bib_search_path = OnceCell::new();
+        self
+    }
+
+    /// Read raw LaTeX cells of a Jupyter notebook, as well as its Markdown cells
+    pub fn with_latex_cells(mut self) -> Self {
+        self.latex_cells = true;
+        self.document_tokens = OnceCell::new();
+        self
+    }
+}
+
+/// Access to the contents of the source, whether from files or memory
+impl<'a> CitationSource<'a> {
+    /// Path of the LaTeX file, which must have been given
+    pub fn latex_path(&self) -> Result<&'a str> {
+        self.latex_file
+            .ok_or_else(|| Error::Invalid("No LaTeX file was given".to_owned()))
+    }
+
+    /// Path of the auxiliary file, which must have been given
+    pub fn aux_path(&self) -> Result<&'a str> {
+        self.aux_file
+            .ok_or_else(|| Error::Invalid("No auxiliary file was given".to_owned()))
+    }
+
+    /// Path of the control file, which must have been given
+    pub fn bcf_path(&self) -> Result<&'a str> {
+        self.bcf_file
+            .ok_or_else(|| Error::Invalid("No control file was given".to_owned()))
+    }
+
+    /// Contents of the control file, which must have been given
+    ///
+    /// The control file is parsed the first time this is called, and the same contents are returned thereafter
+    pub fn control_file(&self) -> Result<&bcf::ControlFile> {
+        if let Some(control_file) = self.control_file.get() {
+            return Ok(control_file);
+        }
+        let control_file = bcf::parse_bcf_from_file(self.bcf_path()?)?;
+        Ok(self.control_file.get_or_init(|| control_file))
+    }
+
+    /// Tokens of the document, read from whichever file its citations are read (see [`Document`])
+    ///
+    /// The document is read the first time this is called, so that its citations and [`refsections`](`Self::refsections`) are found in the same pass, and the same tokens are returned thereafter
+    pub fn document_tokens(&self) -> Result<&[Token]> {
+        if let Some(tokens) = self.document_tokens.get() {
+            return Ok(tokens);
+        }
+        let tokens = match (self.bcf_file, self.aux_file) {
+            (Some(_), _) => Bcf::tokens(self)?,
+            (None, Some(_)) => Aux::tokens(self)?,
+            (None, None) => self.markup().tokens(self)?,
+        };
+        Ok(self.document_tokens.get_or_init(|| tokens))
+    }
+
+    /// Path of the bib file, which must have been given
+    pub fn bib_path(&self) -> Result<&'a str> {
+        self.bib_file
+            .ok_or_else(|| Error::Invalid("No bibliography file was given".to_owned()))
+    }
+
+    /// Markup language of the document given as the LaTeX file, which may instead be, e.g., Markdown
+    pub fn markup(&self) -> Markup {
+        self.latex_file.map_or(Markup::LaTeX, |latex_file| {
+            Markup::from_path(Path::new(latex_file))
+        })
+    }
+
+    /// Contents of the LaTeX file
+    pub fn latex_contents(&self) -> Result<Cow<'a, str>> {
+        match self.latex_src {
+            Some(src) => Ok(Cow::Borrowed(src)),
+            None => {
+                let latex_file = self.latex_path()?;
+                let src = fs::read_to_string(latex_file)
+                    .map_err(Error::io(format!("Could not read file {latex_file:?}")))?;
+                Ok(Cow::Owned(src))
+            }
+        }
+    }
+
+    /// Directory in which the document is compiled, against which relative paths in search paths are resolved
+    fn compile_dir(&self) -> &'a Path {
+        self.latex_file
+            .and_then(|latex_file| Path::new(latex_file).parent())
+            .unwrap_or(Path::new(""))
+    }
+
+    /// Search path for files included in the LaTeX source, which is empty unless one was given
+    ///
+    /// The search path is parsed once, and shared by every lexer of the source, so that its recursive directories are walked at most once
+    pub fn tex_search_path(&self) -> Rc<SearchPath> {
+        let search_path = self.tex_search_path.get_or_init(|| {
+            Rc::new(SearchPath::parse(
+                self.texinputs.unwrap_or_default(),
+                self.compile_dir(),
+            ))
+        });
+        Rc::clone(search_path)
+    }
+
+    /// Search path for bibliography files, which is empty unless one was given
+    pub fn bib_search_path(&self) -> Rc<SearchPath> {
+        let search_path = self.bib_search_path.get_or_init(|| {
+            Rc::new(SearchPath::parse(
+                self.bibinputs.unwrap_or_default(),
+                self.compile_dir(),
+            ))
+        });
+        Rc::clone(search_path)
+    }
+
+    /// Lexer over the LaTeX source
+    pub fn latex_lexer(&self) -> Result<Lexer<Box<dyn BufRead + 'a>>> {
+        let lexer = match self.latex_src {
+            Some(src) => Lexer::from_source(src, Path::new(self.latex_path()?)).boxed(),
+            None => Lexer::from_file(self.latex_path()?)?.boxed(),
+        };
+        Ok(lexer.with_search_path(self.tex_search_path()))
+    }
+
+    /// Contents of the bib file
+    pub fn bib_contents(&self) -> Result<Cow<'a, str>> {
+        match self.bib_src {
+            Some(src) => Ok(Cow::Borrowed(src)),
+            None => Ok(Cow::Owned(bib::read_bib_file(self.bib_path()?)?)),
+        }
+    }
+
+    /// Bibliographies printed by the document with biblatex's `\printbibliography`
+    ///
+    /// Neither auxiliary nor control files record these, so they are read from the LaTeX source even where citations are read from one of those, as long as the source exists.  Documents in other markup languages do not print bibliographies in this way, so none are found from them
+    pub fn printed_bibliographies(&self) -> Result<Vec<PrintedBibliography>> {
+        let tokens = if self.aux_file.is_none() && self.bcf_file.is_none() {
+            Cow::Borrowed(self.document_tokens()?)
+        } else if self.markup() == Markup::LaTeX
+            && (self.latex_src.is_some()
+                || self.latex_file.is_some_and(|file| Path::new(file).exists()))
+        {
+            Cow::Owned(self.markup().tokens(self)?)
+        } else {
+            return Ok(Vec::new());
+        };
+        Ok(tokens
+            .iter()
+            .filter_map(|token| match token {
+                Token::Bibliography(printed) => Some(printed.clone()),
+                _ => None,
+            })
+            .collect())
+    }
+
+    /// Refsections of the document, and the bibliography resources local to each
+    ///
+    /// These are read from the control file or the LaTeX source, where given, and resources are resolved relative to whichever of these is read, as biber would resolve them relative to the directory in which the document is compiled.  Resources of the LaTeX source that are not found there are looked for in the [`bib_search_path`](`Self::bib_search_path`).  Auxiliary files and documents in other markup languages do not have refsections, so none are found from them
+    pub fn refsections(&self) -> Result<Vec<Refsection>> {
+        match (self.bcf_file, self.aux_file) {
+            (Some(_), _) => Ok(self.control_file()?.refsections()),
+            (None, Some(_)) => Ok(Vec::new()),
+            (None, None) => {
+                let base_path = self.compile_dir();
+                let search_path = self.bib_search_path();
+                Ok(self
+                    .document_tokens()?
+                    .iter()
+                    .filter_map(|token| match token {
+                        Token::Refsection(refsection) => Some(refsection),
+                        _ => None,
+                    })
+                    .map(|refsection| Refsection {
+                        number: refsection.number,
+                        resources: refsection
+                            .resources
+                            .iter()
+                            .map(|resource| {
+                                let path = base_path.join(resource);
+                                if path.is_file() {
+                                    path
+                                } else {
+                                    search_path.find(resource).unwrap_or(path)
+                                }
+                            })
+                            .collect(),
+                    })
+                    .collect())
+            }
         }
     }
 }
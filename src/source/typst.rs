@@ -0,0 +1,403 @@
+//! Read citations from Typst documents
+//!
+//! Typst cites keys in markup as references, as in `@smith2020` or `@smith2020[p. 4]`, or with the `cite` function, as in `#cite(<smith2020>)` or `#cite(label("smith2020"))`.  References may also be to labels within the document (e.g., `<fig:setup>`), so references to labels that are defined in the document are not citations.  Nothing within comments or raw text (`` `...` `` and ```` ```...``` ````) is a citation, nor is an `@` preceded by a letter or digit (e.g., in an email address).  Similar to `\input{}` in the LaTeX [`Lexer`](`super::latex::Lexer`), we follow `#include "file.typ"` to read other source files, and we record the bibliography files given to `#bibliography(...)`.  Suppression directives (see [`directive`](`super::directive`)) can be given in comments, as in `// citati-ignore-next-line`.  This is not a full parser for Typst, but it understands enough of the structure of markup and code to tell these apart.
+
+use super::{
+    directive::Directive,
+    latex::{CitationToken, Token},
+    location::{LineIndex, Location},
+};
+use crate::{Error, Result};
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// The citation command of references in markup, as in `@key`
+const REFERENCE: &str = "@";
+
+/// The citation command of citations with the `cite` function, as in `#cite(<key>)`
+const CITE: &str = "cite";
+
+/// Citations, directives, and bibliographies found in a Typst document
+pub struct TypstDocument {
+    /// Citations and suppression directives, in the order in which they are found
+    pub tokens: Vec<Token>,
+
+    /// Paths of the bibliography files given to `#bibliography(...)`, relative to the file in which they are given
+    pub bibliographies: Vec<PathBuf>,
+}
+
+/// Function or keyword whose arguments we are within, for those whose arguments we care about
+#[derive(Clone, Copy, PartialEq)]
+enum Call {
+    Cite,
+    CiteLabel,
+    Bibliography,
+    Include,
+    Other,
+}
+
+impl Call {
+    fn from_name(name: &str) -> Self {
+        match name {
+            "cite" => Call::Cite,
+            "bibliography" => Call::Bibliography,
+            "include" => Call::Include,
+            _ => Call::Other,
+        }
+    }
+}
+
+/// Nested structure of Typst source, where text is either markup or code
+enum Frame {
+    /// Content block (`[...]`), in which text is markup
+    Content,
+
+    /// Arguments (`(...)`) or a code block (`{...}`), in which text is code, ending with the given delimiter
+    Code(Call, char),
+
+    /// Code following a keyword (e.g., `#let`), which ends at the end of the line
+    Line(Call),
+}
+
+/// State of parsing a Typst document and the files it includes
+struct Parser {
+    tokens: Vec<Token>,
+    bibliographies: Vec<PathBuf>,
+
+    /// Labels defined in the document, references to which are not citations
+    labels: HashSet<String>,
+
+    /// Files currently being parsed, so that we do not follow cyclic includes
+    visiting: Vec<PathBuf>,
+}
+
+/// Parse the citations, suppression directives, and bibliographies in the Typst file and the files it includes
+pub fn parse_typst_from_file(typst_file: &str) -> Result<TypstDocument> {
+    let src = read_typst_file(Path::new(typst_file))?;
+    parse_typst_from_str(&src, Path::new(typst_file))
+}
+
+/// Parse the citations, suppression directives, and bibliographies in Typst source, with a (possibly virtual) path at which they are reported
+///
+/// Files included with `#include` are read relative to the directory of the path
+///
+/// ```
+/// use citati::source::{latex::Token, typst};
+/// use std::path::Path;
+///
+/// let src = "See @smith2020 and #cite(<doe2019>), but not @fig.\n#figure[] <fig>\n#bibliography(\"refs.bib\")\n";
+/// let document = typst::parse_typst_from_str(src, Path::new("paper.typ"))?;
+/// let keys: Vec<String> = document
+///     .tokens
+///     .into_iter()
+///     .filter_map(|token| match token {
+///         Token::Citation(citation) => Some(citation.key),
+///         _ => None,
+///     })
+///     .collect();
+/// assert_eq!(keys, ["smith2020", "doe2019"]);
+/// assert_eq!(document.bibliographies, [Path::new("refs.bib")]);
+/// # Ok::<(), citati::Error>(())
+/// ```
+pub fn parse_typst_from_str(src: &str, path: &Path) -> Result<TypstDocument> {
+    let mut parser = Parser {
+        tokens: Vec::new(),
+        bibliographies: Vec::new(),
+        labels: HashSet::new(),
+        visiting: vec![path.to_path_buf()],
+    };
+    parser.parse(src, path)?;
+
+    let labels = parser.labels;
+    let tokens = parser
+        .tokens
+        .into_iter()
+        .filter(|token| match token {
+            Token::Citation(citation) => {
+                citation.cite_cmd != REFERENCE || !labels.contains(&citation.key)
+            }
+            _ => true,
+        })
+        .collect();
+
+    Ok(TypstDocument {
+        tokens,
+        bibliographies: parser.bibliographies,
+    })
+}
+
+fn read_typst_file(typst_file: &Path) -> Result<String> {
+    fs::read_to_string(typst_file).map_err(Error::io(format!(
+        "Could not read Typst file {typst_file:?}"
+    )))
+}
+
+/// Whether the character may be part of a label or reference
+fn is_label_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '-' | '.' | ':')
+}
+
+/// Whether the character may be part of an identifier in code
+fn is_ident_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '-')
+}
+
+/// Length of the longest prefix of `s` whose characters satisfy the predicate
+fn prefix_len(s: &str, predicate: fn(char) -> bool) -> usize {
+    s.find(|c| !predicate(c)).unwrap_or(s.len())
+}
+
+/// Length of the string literal at the start of `s` (including quotes), and its contents
+fn string_literal(s: &str) -> (usize, String) {
+    let mut contents = String::new();
+    let mut chars = s.char_indices().skip(1);
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => return (i + 1, contents),
+            '\\' => {
+                if let Some((_, escaped)) = chars.next() {
+                    contents.push(escaped);
+                }
+            }
+            _ => contents.push(c),
+        }
+    }
+    (s.len(), contents)
+}
+
+/// Length of the (possibly nested) block comment at the start of `s`
+fn block_comment(s: &str) -> usize {
+    let mut depth = 0;
+    let mut i = 0;
+    while i < s.len() {
+        if s[i..].starts_with("/*") {
+            depth += 1;
+            i += 2;
+        } else if s[i..].starts_with("*/") {
+            depth -= 1;
+            i += 2;
+            if depth == 0 {
+                return i;
+            }
+        } else {
+            i += s[i..].chars().next().unwrap().len_utf8();
+        }
+    }
+    s.len()
+}
+
+impl Parser {
+    /// Parse the source of one file, following any files that it includes
+    fn parse(&mut self, src: &str, path: &Path) -> Result<()> {
+        let index = LineIndex::new(src);
+        let base_path = path.parent().unwrap_or(Path::new(""));
+        let mut stack: Vec<Frame> = Vec::new();
+        let mut i = 0;
+
+        while i < src.len() {
+            let rest = &src[i..];
+            let c = rest.chars().next().unwrap();
+            let markup = matches!(stack.last(), None | Some(Frame::Content));
+            let call = match stack.last() {
+                Some(Frame::Code(call, _) | Frame::Line(call)) => *call,
+                _ => Call::Other,
+            };
+
+            // Comments, in both markup and code.  In markup, `//` following a
+            // colon is part of a URL (e.g., `https://`)
+            let url = markup && src[..i].ends_with(':');
+            if rest.starts_with("//") && !url {
+                let end = rest.find('\n').unwrap_or(rest.len());
+                let location = index.location(path, i + 2);
+                if let Some(directive) = Directive::parse(&rest[2..end], location) {
+                    self.tokens.push(Token::Directive(directive));
+                }
+                i += end;
+                continue;
+            }
+            if rest.starts_with("/*") {
+                let end = block_comment(rest);
+                // An unterminated comment runs to the end of the source
+                let content = rest[2..end].strip_suffix("*/").unwrap_or(&rest[2..end]);
+                let location = index.location(path, i + 2);
+                if let Some(directive) = Directive::parse(content, location) {
+                    self.tokens.push(Token::Directive(directive));
+                }
+                i += end;
+                continue;
+            }
+
+            if markup {
+                match c {
+                    // Escaped characters are text
+                    '\\' => {
+                        i += 1 + rest[1..].chars().next().map_or(0, char::len_utf8);
+                        continue;
+                    }
+                    '`' => {
+                        i += raw(rest);
+                        continue;
+                    }
+                    ']' if matches!(stack.last(), Some(Frame::Content)) => {
+                        stack.pop();
+                    }
+                    '#' => {
+                        i += 1 + self.code_expression(&rest[1..], &mut stack);
+                        continue;
+                    }
+                    '@' if !src[..i].ends_with(char::is_alphanumeric) => {
+                        let len = prefix_len(&rest[1..], is_label_char);
+                        let key = rest[1..1 + len].trim_end_matches(['.', ':']);
+                        if !key.is_empty() {
+                            self.push_citation(key, REFERENCE, index.location(path, i + 1));
+                        }
+                        i += 1 + key.len();
+                        continue;
+                    }
+                    '<' => {
+                        let len = prefix_len(&rest[1..], is_label_char);
+                        if len > 0 && rest[1 + len..].starts_with('>') {
+                            self.labels.insert(rest[1..1 + len].to_owned());
+                            i += len + 2;
+                            continue;
+                        }
+                    }
+                    _ => {}
+                }
+            } else {
+                match c {
+                    '"' => {
+                        let (len, contents) = string_literal(rest);
+                        match call {
+                            Call::CiteLabel => {
+                                self.push_citation(&contents, CITE, index.location(path, i + 1))
+                            }
+                            Call::Bibliography if contents.ends_with(".bib") => {
+                                self.bibliographies.push(base_path.join(contents));
+                            }
+                            Call::Include => self.include(&contents, base_path)?,
+                            _ => {}
+                        }
+                        i += len;
+                        continue;
+                    }
+                    '(' | '{' => {
+                        let close = if c == '(' { ')' } else { '}' };
+                        stack.push(Frame::Code(call, close));
+                    }
+                    ')' | '}' => {
+                        if matches!(stack.last(), Some(Frame::Code(_, close)) if *close == c) {
+                            stack.pop();
+                        }
+                    }
+                    '[' => stack.push(Frame::Content),
+                    '\n' if matches!(stack.last(), Some(Frame::Line(_))) => {
+                        stack.pop();
+                    }
+                    '<' if call == Call::Cite => {
+                        let len = prefix_len(&rest[1..], is_label_char);
+                        if len > 0 && rest[1 + len..].starts_with('>') {
+                            let key = &rest[1..1 + len];
+                            self.push_citation(key, CITE, index.location(path, i + 1));
+                            i += len + 2;
+                            continue;
+                        }
+                    }
+                    _ if call == Call::Cite && rest.starts_with("label(") => {
+                        stack.push(Frame::Code(Call::CiteLabel, ')'));
+                        i += "label(".len();
+                        continue;
+                    }
+                    _ => {}
+                }
+            }
+
+            i += c.len_utf8();
+        }
+
+        Ok(())
+    }
+
+    /// Parse the start of an embedded code expression (i.e., following `#`), returning the length parsed
+    ///
+    /// Function calls and keywords whose arguments are code push a frame onto the stack, the contents of which are parsed as we continue
+    fn code_expression(&mut self, s: &str, stack: &mut Vec<Frame>) -> usize {
+        let name_len = prefix_len(s, is_ident_char);
+        let name = &s[..name_len];
+        match name {
+            "" => {
+                match s.chars().next() {
+                    Some('(') => stack.push(Frame::Code(Call::Other, ')')),
+                    Some('{') => stack.push(Frame::Code(Call::Other, '}')),
+                    Some('[') => stack.push(Frame::Content),
+                    _ => return 0,
+                }
+                1
+            }
+            "let" | "set" | "show" | "import" | "include" => {
+                stack.push(Frame::Line(Call::from_name(name)));
+                name_len
+            }
+            _ => {
+                // Skip any field accesses, as in `#sym.arrow`, to find the arguments
+                let mut len = name_len;
+                while s[len..].starts_with('.') {
+                    let field_len = prefix_len(&s[len + 1..], is_ident_char);
+                    if field_len == 0 {
+                        break;
+                    }
+                    len += 1 + field_len;
+                }
+                match s[len..].chars().next() {
+                    Some('(') => {
+                        stack.push(Frame::Code(Call::from_name(name), ')'));
+                        len + 1
+                    }
+                    Some('[') => {
+                        stack.push(Frame::Content);
+                        len + 1
+                    }
+                    _ => len,
+                }
+            }
+        }
+    }
+
+    fn push_citation(&mut self, key: &str, cite_cmd: &str, location: Location) {
+        self.tokens.push(Token::Citation(CitationToken {
+            key: key.to_owned(),
+            cite_cmd: cite_cmd.to_owned(),
+            location,
+            refsection: 0,
+            refsegment: 0,
+        }));
+    }
+
+    /// Parse a file included with `#include`, relative to the directory of the file that included it
+    fn include(&mut self, included: &str, base_path: &Path) -> Result<()> {
+        let path = base_path.join(included);
+        if self.visiting.contains(&path) {
+            return Ok(());
+        }
+        let src = read_typst_file(&path)?;
+        self.visiting.push(path.clone());
+        self.parse(&src, &path)?;
+        self.visiting.pop();
+        Ok(())
+    }
+}
+
+/// Length of the raw text at the start of `s`
+///
+/// Raw text starts with one backtick, or with three or more for a raw block, and ends with the same number of backticks.  Two backticks are empty raw text
+fn raw(s: &str) -> usize {
+    let n = prefix_len(s, |c| c == '`');
+    if n == 2 {
+        return n;
+    }
+    let fence = &s[..n];
+    s[n..].find(fence).map_or(s.len(), |end| n + end + n)
+}
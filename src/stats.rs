@@ -0,0 +1,260 @@
+//! Statistics of the citations in a document
+//!
+//! Summarise how a document cites its bibliography: how many times each key is cited and in which files, how many distinct works are cited, and how the cited works are distributed by entry type, year, decade, and journal, along with the most-cited works.  Citations are counted from their occurrences in the LaTeX source (or other markup; see [`Markup`](`crate::source::Markup`)), and works are described by their entries in the bibliography, where they are defined.  This module contains the logic for this functionality, which can be accessed using the `citati stats` subcommand.
+
+use super::{
+    citations::{gather_citations, Citations},
+    error::Result,
+    source::{Bib, BibCitation, CitationSource, Document, LaTeXCitation, Source},
+};
+use biblatex::EntryType;
+use clap::ValueEnum;
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::Serialize;
+use std::{collections::BTreeMap, fmt};
+
+lazy_static! {
+    static ref YEAR_REGEX: Regex = Regex::new(r"[0-9]{4}").unwrap();
+}
+
+/// Number of the most-cited works that are listed by default
+pub const DEFAULT_TOP: usize = 10;
+
+/// Formats in which statistics can be written
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// Tables in a short human-readable form
+    Plain,
+
+    /// The same as plain, for consistency with the formats of `citati check`
+    Pretty,
+
+    /// A single JSON document
+    Json,
+}
+
+/// How a single key is cited
+#[derive(Clone, Debug, Serialize)]
+pub struct KeyStats {
+    pub key: String,
+
+    /// Number of times the key is cited
+    pub count: usize,
+
+    /// Files in which the key is cited, in the order in which they are first cited
+    pub files: Vec<String>,
+
+    /// Whether the key is defined in the bibliography
+    pub defined: bool,
+}
+
+/// Statistics of the citations in a document
+///
+/// Counts by entry type, year, decade, and journal are of distinct works cited (rather than of citations), and only of those defined in the bibliography
+#[derive(Clone, Debug, Serialize)]
+pub struct Stats {
+    /// Total number of citations, counting each key in each citation command
+    pub citations: usize,
+
+    /// Number of distinct keys cited
+    pub distinct: usize,
+
+    /// Number of distinct keys cited that are not defined in the bibliography
+    pub undefined: usize,
+
+    /// Each key cited, from the most to the least cited (and alphabetically where cited equally often)
+    pub keys: Vec<KeyStats>,
+
+    /// Number of works cited of each entry type (e.g., `article`)
+    pub by_type: BTreeMap<String, usize>,
+
+    /// Number of works cited from each year, from the `year` field (or, failing that, the `date` field)
+    pub by_year: BTreeMap<String, usize>,
+
+    /// Number of works cited from each decade (e.g., `1990s`)
+    pub by_decade: BTreeMap<String, usize>,
+
+    /// Number of works cited from each journal, from the `journal` (or `journaltitle`) field
+    pub by_journal: BTreeMap<String, usize>,
+
+    /// Number of works cited that have no year
+    pub undated: usize,
+
+    /// Number of the most-cited works to list when displayed
+    #[serde(skip)]
+    pub top: usize,
+}
+
+/// Name of the entry type, as given in the bibliography
+fn type_name(entry_type: &EntryType) -> String {
+    match entry_type {
+        EntryType::Unknown(name) => name.to_owned(),
+        entry_type => entry_type.to_string(),
+    }
+}
+
+/// Year of the work, from the first four digits of its `year` or `date` field
+fn year(entry: &BibCitation) -> Option<String> {
+    let date = entry.get("year").or_else(|| entry.get("date"))?;
+    YEAR_REGEX.find(&date).map(|m| m.as_str().to_owned())
+}
+
+/// Compute statistics of the citations, describing cited works by their entries in the bibliography
+fn count<S>(citations: Citations<S>, bib: &Citations<Bib>, top: usize) -> Stats
+where
+    S: Source<CitationType = LaTeXCitation>,
+{
+    let mut keys = Vec::new();
+    let mut by_type = BTreeMap::new();
+    let mut by_year = BTreeMap::new();
+    let mut by_decade = BTreeMap::new();
+    let mut by_journal = BTreeMap::new();
+    let mut undated = 0;
+
+    // `\nocite{*}` is not a citation of an entry with key `*`
+    for citation in citations.iter().filter(|citation| citation.key != "*") {
+        let mut files = Vec::new();
+        for location in citation.locations.iter() {
+            let file = location.file.display().to_string();
+            if !files.contains(&file) {
+                files.push(file);
+            }
+        }
+
+        let entry = bib.get(&citation.key);
+        keys.push(KeyStats {
+            key: citation.key.clone(),
+            count: citation.locations.len(),
+            files,
+            defined: entry.is_some(),
+        });
+
+        let Some(entry) = entry else {
+            continue;
+        };
+        *by_type.entry(type_name(&entry.entry_type())).or_default() += 1;
+        match year(entry) {
+            Some(year) => {
+                let decade = format!("{}0s", &year[..3]);
+                *by_year.entry(year).or_default() += 1;
+                *by_decade.entry(decade).or_default() += 1;
+            }
+            None => undated += 1,
+        }
+        if let Some(journal) = entry.get("journal").or_else(|| entry.get("journaltitle")) {
+            *by_journal.entry(journal).or_default() += 1;
+        }
+    }
+
+    keys.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.key.cmp(&b.key)));
+    Stats {
+        citations: keys.iter().map(|key| key.count).sum(),
+        distinct: keys.len(),
+        undefined: keys.iter().filter(|key| !key.defined).count(),
+        keys,
+        by_type,
+        by_year,
+        by_decade,
+        by_journal,
+        undated,
+        top,
+    }
+}
+
+/// Compute statistics of the citations in the document, listing the `top` most-cited works when displayed
+///
+/// ```
+/// use citati::{source::CitationSource, stats};
+///
+/// let src = CitationSource::new("document.tex", "references.bib")
+///     .with_latex_src("\\cite{smith2020} and \\cite{smith2020, doe2019}.\n")
+///     .with_bib_src("@article{smith2020, title = {A}, year = {2020}}\n");
+/// let stats = stats::citation_stats(&src, stats::DEFAULT_TOP)?;
+/// assert_eq!((stats.citations, stats.distinct, stats.undefined), (3, 2, 1));
+/// assert_eq!(stats.keys[0].key, "smith2020");
+/// assert_eq!(stats.by_decade["2020s"], 1);
+/// # Ok::<(), citati::Error>(())
+/// ```
+pub fn citation_stats(src: &CitationSource, top: usize) -> Result<Stats> {
+    let bib = gather_citations::<Citations<Bib>>(src)?;
+    Ok(count(
+        gather_citations::<Citations<Document>>(src)?,
+        &bib,
+        top,
+    ))
+}
+
+/// Write a section of counts, aligning the counts
+fn write_counts(
+    f: &mut fmt::Formatter<'_>,
+    title: &str,
+    counts: &BTreeMap<String, usize>,
+) -> fmt::Result {
+    if counts.is_empty() {
+        return Ok(());
+    }
+    writeln!(f, "\n{title}")?;
+    let width = counts
+        .keys()
+        .map(|name| name.chars().count())
+        .max()
+        .unwrap_or(0);
+    for (name, count) in counts.iter() {
+        writeln!(f, "  {name:<width$}  {count:>4}")?;
+    }
+    Ok(())
+}
+
+/// Human-readable report of the statistics
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} citations of {} distinct works ({} not in the bibliography)",
+            self.citations, self.distinct, self.undefined
+        )?;
+
+        if self.top > 0 && !self.keys.is_empty() {
+            writeln!(f, "\nMost cited")?;
+            for key in self.keys.iter().take(self.top) {
+                writeln!(f, "  {:>4}  {}", key.count, key.key)?;
+            }
+        }
+
+        if !self.keys.is_empty() {
+            writeln!(f, "\nCitations by key")?;
+            let mut keys: Vec<&KeyStats> = self.keys.iter().collect();
+            keys.sort_by(|a, b| a.key.cmp(&b.key));
+            let width = keys
+                .iter()
+                .map(|key| key.key.chars().count())
+                .max()
+                .unwrap_or(0);
+            for key in keys {
+                let undefined = if key.defined { "" } else { " (undefined)" };
+                writeln!(
+                    f,
+                    "  {:<width$}  {:>4}  {}{undefined}",
+                    key.key,
+                    key.count,
+                    key.files.join(", ")
+                )?;
+            }
+        }
+
+        write_counts(f, "Works by type", &self.by_type)?;
+        write_counts(f, "Works by year", &self.by_year)?;
+        write_counts(f, "Works by decade", &self.by_decade)?;
+        write_counts(f, "Works by journal", &self.by_journal)?;
+        if self.undated > 0 {
+            let works = if self.undated == 1 {
+                "work has"
+            } else {
+                "works have"
+            };
+            writeln!(f, "\n{} {works} no year", self.undated)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,187 @@
+//! Apply inline suppression directives to findings
+//!
+//! Findings can be silenced using comments in the LaTeX or bibliography source; see [`directive`](`crate::source::directive`) for their syntax.  This module removes findings that are suppressed, keeping track of which directives were used so that we can report those which no longer match anything.  The latter can be accessed using the `--unused-suppressions` option of `citati check`.
+
+use super::{
+    cite_commands,
+    diagnostics::{Check, Finding},
+    error::Result,
+    fields, missing, pages,
+    source::{bib, latex, CitationSource, Directive, Scope},
+    unused,
+};
+use std::path::Path;
+
+/// Collection of suppression directives from some source, and whether each has matched a finding
+pub struct Suppressions {
+    directives: Vec<Directive>,
+    used: Vec<bool>,
+}
+
+impl Suppressions {
+    /// Gather directives from the LaTeX (or other markup) and bibliography files of the source, where given
+    pub fn gather(src: &CitationSource) -> Result<Self> {
+        Ok(Self::from_directives(directives_of(src)?))
+    }
+
+    /// Gather directives from the documents of many sources, which share a bibliography file
+    ///
+    /// Directives in files that are read for more than one source (e.g., the bibliography, or a file included by many documents) are only gathered once
+    pub fn gather_all(srcs: &[CitationSource]) -> Result<Self> {
+        let mut directives: Vec<Directive> = Vec::new();
+        for src in srcs.iter() {
+            for directive in directives_of(src)? {
+                if !directives
+                    .iter()
+                    .any(|existing| existing.location == directive.location)
+                {
+                    directives.push(directive);
+                }
+            }
+        }
+        Ok(Self::from_directives(directives))
+    }
+
+    /// Collect the directives, warning of any unknown checks that they name
+    fn from_directives(directives: Vec<Directive>) -> Self {
+        for directive in directives.iter() {
+            for name in directive.unknown_checks.iter() {
+                eprintln!(
+                    "[WARN] Unknown check {name:?} in suppression at {}:{}",
+                    directive.location.file.display(),
+                    directive.location.line
+                );
+            }
+        }
+
+        let used = vec![false; directives.len()];
+        Self { directives, used }
+    }
+
+    /// Remove any findings that are suppressed by a directive
+    pub fn apply(&mut self, findings: Vec<Finding>) -> Vec<Finding> {
+        findings
+            .into_iter()
+            .filter_map(|finding| self.apply_one(finding))
+            .collect()
+    }
+
+    /// Apply directives to a single finding, returning it unless it is entirely suppressed
+    ///
+    /// Directives that apply to a line only remove the finding's locations on that line, so that, e.g., a key that is missing from the bibliography is still reported if it is cited elsewhere
+    fn apply_one(&mut self, mut finding: Finding) -> Option<Finding> {
+        for (directive, used) in self.directives.iter().zip(self.used.iter_mut()) {
+            if !directive.applies_to(finding.check) {
+                continue;
+            }
+
+            match &directive.scope {
+                Scope::All => {
+                    *used = true;
+                    return None;
+                }
+                Scope::Entry(key) if *key == finding.key => {
+                    *used = true;
+                    return None;
+                }
+                Scope::NextLine => {
+                    let n = finding.locations.len();
+                    finding.locations.retain(|location| {
+                        location.file != directive.location.file
+                            || location.cell != directive.location.cell
+                            || location.line != directive.location.line + 1
+                    });
+                    if finding.locations.len() < n {
+                        *used = true;
+                        if finding.locations.is_empty() {
+                            return None;
+                        }
+                    }
+                }
+                Scope::Entry(_) | Scope::NextEntry => {}
+            }
+        }
+
+        Some(finding)
+    }
+
+    /// Findings for each directive that has not matched any finding
+    pub fn unused(&self) -> Vec<Finding> {
+        self.directives
+            .iter()
+            .zip(self.used.iter())
+            .filter(|(_, used)| !**used)
+            .map(|(directive, _)| report_unused_suppression(directive))
+            .collect()
+    }
+}
+
+/// Directives in the LaTeX (or other markup) and bibliography files of the source, where given
+fn directives_of(src: &CitationSource) -> Result<Vec<Directive>> {
+    let mut directives = Vec::new();
+
+    // The LaTeX source is not required by checks of the bib file alone, or
+    // if citations are read from an auxiliary or control file, but we still
+    // respect its directives where it exists
+    let has_latex = match src.latex_file {
+        Some(latex_file) => src.latex_src.is_some() || Path::new(latex_file).exists(),
+        None => false,
+    };
+    if has_latex {
+        directives.extend(
+            src.markup()
+                .tokens(src)?
+                .into_iter()
+                .filter_map(|token| match token {
+                    latex::Token::Directive(directive) => Some(directive),
+                    _ => None,
+                }),
+        );
+    }
+
+    if let Some(bib_file) = src.bib_file {
+        directives.extend(bib::parse_bib_directives_from_str(
+            &src.bib_contents()?,
+            bib_file,
+        )?);
+    }
+
+    Ok(directives)
+}
+
+/// Defines the finding reported for directives that do not match any finding
+fn report_unused_suppression(directive: &Directive) -> Finding {
+    let key = match &directive.scope {
+        Scope::Entry(key) => key.as_str(),
+        _ => "",
+    };
+    let mut finding = Finding::new(
+        Check::UnusedSuppression,
+        key,
+        format!(
+            "Suppression {:?} does not match any finding",
+            directive.text
+        ),
+    );
+    finding
+        .values
+        .insert("directive".to_owned(), directive.text.clone());
+    finding.locations.push(directive.location.clone());
+    finding
+}
+
+/// List any suppression directives in LaTeX and bib sources that do not match any finding
+///
+/// To determine this, we run every other check
+pub fn unused_suppressions(src: &CitationSource) -> Result<Vec<Finding>> {
+    let mut findings = Vec::new();
+    findings.extend(unused::unused_citations(src)?);
+    findings.extend(missing::missing_citations(src)?);
+    findings.extend(cite_commands::check_cite_commands(src)?);
+    findings.extend(pages::check_bib_pages(src)?);
+    findings.extend(fields::article::check_article_fields(src)?);
+
+    let mut suppressions = Suppressions::gather(src)?;
+    suppressions.apply(findings);
+    Ok(suppressions.unused())
+}
@@ -0,0 +1,570 @@
+//! Format bibliography source consistently
+//!
+//! Similar to [bibtex-tidy](https://github.com/FlamingTempura/bibtex-tidy), this module rewrites each entry of the bibliography with consistent indentation, aligned field values, lower case entry types and field names, and a consistent choice of value delimiters.  Optionally, fields can be reordered (per entry type), entries can be sorted (by key, year, or author), and empty fields are removed.  This functionality can be accessed using the `citati fmt` subcommand, whose `--check` option fails if the file is not already formatted.  Formatting is built on the lossless [`cst`](`crate::source::bib::cst`) layer, so `%` comments (including suppression directives) are kept alongside the entries and fields to which they belong, and `@string`, `@preamble`, and `@comment` blocks are kept as-is.
+
+use super::{
+    error::{Error, Result},
+    source::{
+        bib::{
+            self,
+            cst::{Document, EntryNode, FieldNode, SyntaxError},
+        },
+        CitationSource,
+    },
+};
+use clap::ValueEnum;
+use similar::TextDiff;
+use std::cmp::Ordering;
+
+/// Fields in the order in which they are placed by the `--sort-fields` option of `citati fmt`, as in bibtex-tidy
+const DEFAULT_FIELD_ORDER: [&str; 26] = [
+    "title",
+    "shorttitle",
+    "author",
+    "year",
+    "month",
+    "day",
+    "journal",
+    "booktitle",
+    "location",
+    "on",
+    "publisher",
+    "address",
+    "series",
+    "volume",
+    "number",
+    "pages",
+    "doi",
+    "isbn",
+    "issn",
+    "url",
+    "urldate",
+    "copyright",
+    "category",
+    "note",
+    "metadata",
+    "abstract",
+];
+
+/// How field values are delimited
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum Delimiters {
+    /// `{...}`
+    Braces,
+
+    /// `"..."`, where the value does not itself contain quotes
+    Quotes,
+}
+
+/// What entries may be sorted by
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum SortKey {
+    /// Citation key
+    Key,
+
+    /// The `year` field (or the year of the `date` field)
+    Year,
+
+    /// Family name of the first author (or editor)
+    Author,
+}
+
+/// Order in which to place the fields of entries, optionally only for entries of a given type
+#[derive(Clone, Debug)]
+pub struct FieldOrder {
+    pub entry_type: Option<String>,
+    pub fields: Vec<String>,
+}
+
+/// Parse a field order from the command line, in the form `[TYPE=]FIELD,FIELD,...`
+pub fn parse_field_order(s: &str) -> Result<FieldOrder, String> {
+    let (entry_type, fields) = match s.split_once('=') {
+        Some((entry_type, fields)) => (Some(entry_type.trim().to_lowercase()), fields),
+        None => (None, s),
+    };
+    let fields: Vec<String> = fields
+        .split(',')
+        .map(|field| field.trim().to_lowercase())
+        .filter(|field| !field.is_empty())
+        .collect();
+
+    if fields.is_empty() {
+        return Err("expected a comma-separated list of fields".to_owned());
+    }
+    if entry_type.as_ref().is_some_and(String::is_empty) {
+        return Err("expected an entry type before '='".to_owned());
+    }
+
+    Ok(FieldOrder { entry_type, fields })
+}
+
+/// Options controlling how the bibliography is formatted
+pub struct Options {
+    /// Number of spaces by which fields are indented
+    pub indent: usize,
+
+    /// Whether to align the values of fields within each entry
+    pub align: bool,
+    pub delimiters: Delimiters,
+
+    /// Keys by which to sort entries, in order of precedence; entries are not sorted if empty
+    pub sort: Vec<SortKey>,
+
+    /// Orders in which to place fields; fields not listed are placed after those that are, in their original order
+    pub field_orders: Vec<FieldOrder>,
+    pub remove_empty_fields: bool,
+}
+
+impl Options {
+    /// The field order that applies to entries of the given type, preferring orders specific to that type
+    fn field_order(&self, entry_type: &str) -> Option<&[String]> {
+        self.field_orders
+            .iter()
+            .rev()
+            .find(|order| order.entry_type.as_deref() == Some(entry_type))
+            .or_else(|| {
+                self.field_orders
+                    .iter()
+                    .rev()
+                    .find(|order| order.entry_type.is_none())
+            })
+            .map(|order| order.fields.as_slice())
+    }
+}
+
+/// Default field order, for use with [`Options::field_orders`]
+pub fn default_field_order() -> FieldOrder {
+    FieldOrder {
+        entry_type: None,
+        fields: DEFAULT_FIELD_ORDER.iter().map(|f| f.to_string()).collect(),
+    }
+}
+
+/// Split whitespace and `%` comments between two syntactic items into a comment on the same line as the first item, and comments on their own lines before the second item
+fn split_comments(gap: &str) -> (Option<&str>, Vec<&str>) {
+    let mut lines = gap.split('\n').map(str::trim);
+    let trailing = lines.next().filter(|line| !line.is_empty());
+    (trailing, lines.filter(|line| !line.is_empty()).collect())
+}
+
+/// A field of an entry, along with its comments
+struct Field<'a> {
+    name: String,
+    value: String,
+
+    /// Whether the value is an empty string
+    empty: bool,
+    trailing: Option<&'a str>,
+    leading: Vec<&'a str>,
+}
+
+/// An entry, along with the comments before, within, and after it
+struct Entry<'a> {
+    node: &'a EntryNode,
+    entry_type: String,
+    key: &'a str,
+
+    /// Comment lines immediately preceding the entry
+    leading: Vec<&'a str>,
+
+    /// Comment on the same line as the opening of the entry
+    header: Option<&'a str>,
+    fields: Vec<Field<'a>>,
+
+    /// Comment lines after the last field
+    closing: Vec<&'a str>,
+
+    /// Comment on the same line as the closing delimiter of the entry
+    trailing: Option<&'a str>,
+}
+
+/// Top-level item of the bibliography: either an entry, or anything else (comments and special blocks) between entries
+enum Item<'a> {
+    Entry(Entry<'a>),
+    Other(String),
+}
+
+impl<'a> Entry<'a> {
+    fn new(document: &'a Document, node: &'a EntryNode, options: &Options) -> Self {
+        let src = document.src();
+        let mut previous = node.key_comma.map_or(node.key.end, |comma| comma + 1);
+        let mut header = None;
+        let mut fields: Vec<Field> = Vec::new();
+
+        for field in node.fields.iter() {
+            let (trailing, leading) = split_comments(&src[previous..field.span.start]);
+            match fields.last_mut() {
+                Some(last) => last.trailing = trailing,
+                None => header = trailing,
+            }
+            fields.push(Field {
+                name: document.text(&field.name).to_lowercase(),
+                value: format_value(document, field, options.delimiters),
+                empty: document
+                    .content(field)
+                    .is_some_and(|content| document.text(&content).trim().is_empty()),
+                trailing: None,
+                leading,
+            });
+            previous = field.comma.map_or(field.span.end, |comma| comma + 1);
+        }
+
+        let (trailing, closing) = split_comments(&src[previous..node.close]);
+        match fields.last_mut() {
+            Some(last) => last.trailing = trailing,
+            None => header = header.or(trailing),
+        }
+
+        // Removing fields would lose their comments, so we only remove those without any
+        if options.remove_empty_fields {
+            fields.retain(|field| {
+                !(field.empty && field.leading.is_empty() && field.trailing.is_none())
+            });
+        }
+
+        let entry_type = document.text(&node.kind).to_lowercase();
+        if let Some(order) = options.field_order(&entry_type) {
+            let position = |field: &Field| {
+                order
+                    .iter()
+                    .position(|name| *name == field.name)
+                    .unwrap_or(order.len())
+            };
+            fields.sort_by_key(position);
+        }
+
+        Self {
+            node,
+            entry_type,
+            key: document.key(node),
+            leading: Vec::new(),
+            header,
+            fields,
+            closing,
+            trailing: None,
+        }
+    }
+
+    /// Content of the given field, if present and not empty
+    fn get(&self, document: &Document, name: &str) -> Option<String> {
+        let field = document.field(self.node, name)?;
+        let content = document.text(&document.content(field)?).trim();
+        (!content.is_empty()).then(|| content.to_owned())
+    }
+
+    /// Value of the entry by which to sort it, where entries without a value are sorted last
+    fn sort_value(&self, document: &Document, sort_key: SortKey) -> Option<SortValue> {
+        match sort_key {
+            SortKey::Key => Some(SortValue::Text(self.key.to_lowercase())),
+            SortKey::Year => {
+                let year = self
+                    .get(document, "year")
+                    .or_else(|| self.get(document, "date"))?;
+                let digits: String = year.chars().take_while(char::is_ascii_digit).collect();
+                digits.parse().ok().map(SortValue::Number)
+            }
+            SortKey::Author => {
+                let names = self
+                    .get(document, "author")
+                    .or_else(|| self.get(document, "editor"))?;
+                let first = names.split(" and ").next()?.trim();
+
+                // Names are either written as "Family, Given" or "Given Family"
+                let family = match first.split_once(',') {
+                    Some((family, _)) => family,
+                    None => first.rsplit(' ').next()?,
+                };
+                Some(SortValue::Text(
+                    family.trim_matches(['{', '}', ' ']).to_lowercase(),
+                ))
+            }
+        }
+    }
+
+    fn write(&self, out: &mut String, options: &Options) {
+        let indent = " ".repeat(options.indent);
+        let width = if options.align {
+            self.fields.iter().map(|f| f.name.len()).max().unwrap_or(0)
+        } else {
+            0
+        };
+
+        for comment in self.leading.iter() {
+            out.push_str(&format!("{comment}\n"));
+        }
+
+        // Entries without any fields (or comments) are written on one line
+        out.push_str(&format!("@{}{{{}", self.entry_type, self.key));
+        if !self.fields.is_empty() || self.header.is_some() || !self.closing.is_empty() {
+            out.push(',');
+            if let Some(comment) = self.header {
+                out.push_str(&format!(" {comment}"));
+            }
+            out.push('\n');
+        }
+
+        for field in self.fields.iter() {
+            for comment in field.leading.iter() {
+                out.push_str(&format!("{indent}{comment}\n"));
+            }
+            out.push_str(&format!(
+                "{indent}{:width$} = {},",
+                field.name,
+                field.value,
+                width = width
+            ));
+            if let Some(comment) = field.trailing {
+                out.push_str(&format!(" {comment}"));
+            }
+            out.push('\n');
+        }
+        for comment in self.closing.iter() {
+            out.push_str(&format!("{indent}{comment}\n"));
+        }
+
+        out.push('}');
+        if let Some(comment) = self.trailing {
+            out.push_str(&format!(" {comment}"));
+        }
+        out.push('\n');
+    }
+}
+
+/// Value of an entry by which it is sorted
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+enum SortValue {
+    Number(i64),
+    Text(String),
+}
+
+/// Format the value of a field with the given delimiters, where possible
+///
+/// Values consisting of several concatenated parts, and bare numbers and abbreviations, are left as-is
+fn format_value(document: &Document, field: &FieldNode, delimiters: Delimiters) -> String {
+    let value = document.text(&field.value).replace("\r\n", "\n");
+    let delimited = value.starts_with('{') || value.starts_with('"');
+    let Some(content) = document.content(field).filter(|_| delimited) else {
+        return value;
+    };
+
+    let content = document.text(&content).replace("\r\n", "\n");
+    match delimiters {
+        Delimiters::Braces => format!("{{{content}}}"),
+        Delimiters::Quotes if !content.contains('"') => format!("\"{content}\""),
+        Delimiters::Quotes => format!("{{{content}}}"),
+    }
+}
+
+/// Split the document into top-level items, attaching comments to the entries they precede or follow
+fn items<'a>(document: &'a Document, options: &Options) -> Vec<Item<'a>> {
+    let src = document.src();
+    let mut items = Vec::new();
+    let mut previous = 0;
+
+    for node in document.entries().iter() {
+        let gap = &src[previous..node.span.start];
+        let mut entry = Entry::new(document, node, options);
+
+        // Anything on the same line as the end of the previous entry belongs to it
+        let gap = match items.last_mut() {
+            Some(Item::Entry(last)) => {
+                let (first_line, rest) = gap.split_once('\n').unwrap_or((gap, ""));
+                let first_line = first_line.trim();
+                last.trailing = (!first_line.is_empty()).then_some(first_line);
+                rest
+            }
+            _ => gap,
+        };
+
+        // Comment lines immediately before the entry (e.g., suppression
+        // directives) belong to it, and are moved with it if entries are sorted
+        let mut lines: Vec<&str> = gap.split('\n').collect();
+        let mut attached = 0;
+        if lines.last().is_some_and(|line| line.trim().is_empty()) {
+            lines.pop();
+        }
+        while lines.len() > attached
+            && lines[lines.len() - attached - 1]
+                .trim_start()
+                .starts_with('%')
+        {
+            attached += 1;
+        }
+        entry.leading = lines[lines.len() - attached..]
+            .iter()
+            .map(|line| line.trim())
+            .collect();
+
+        let other = lines[..lines.len() - attached].join("\n");
+        let other = other.trim_matches(['\n', '\r']).trim_end();
+        if !other.trim().is_empty() {
+            items.push(Item::Other(other.to_owned()));
+        }
+
+        items.push(Item::Entry(entry));
+        previous = node.span.end;
+    }
+
+    let gap = &src[previous..];
+    let gap = match items.last_mut() {
+        Some(Item::Entry(last)) => {
+            let (first_line, rest) = gap.split_once('\n').unwrap_or((gap, ""));
+            let first_line = first_line.trim();
+            last.trailing = (!first_line.is_empty()).then_some(first_line);
+            rest
+        }
+        _ => gap,
+    };
+    let other = gap.trim_matches(['\n', '\r']).trim_end();
+    if !other.trim().is_empty() {
+        items.push(Item::Other(other.to_owned()));
+    }
+
+    items
+}
+
+/// Format the bibliography document, returning the formatted document
+///
+/// The formatted source is parsed again to check that it has the same entries as the original, so that a formatting bug cannot silently corrupt the bibliography; if it does not, an error is returned instead
+///
+/// ```
+/// use citati::{
+///     source::bib::cst::Document,
+///     tidy::{format_document, Delimiters, Options},
+/// };
+///
+/// let document = Document::parse("@Article{smith2020, Title=\"A\", year={2020}}".to_owned()).unwrap();
+/// let options = Options {
+///     indent: 2,
+///     align: true,
+///     delimiters: Delimiters::Braces,
+///     sort: Vec::new(),
+///     field_orders: Vec::new(),
+///     remove_empty_fields: true,
+/// };
+/// assert_eq!(
+///     format_document(&document, &options).unwrap().src(),
+///     "@article{smith2020,\n  title = {A},\n  year  = {2020},\n}\n"
+/// );
+/// ```
+pub fn format_document(document: &Document, options: &Options) -> Result<Document, SyntaxError> {
+    let mut items = items(document, options);
+
+    // When sorting entries, other text between entries (e.g., `@string`
+    // definitions) is kept before all entries, as entries may depend on it.
+    // Text after the last entry (e.g., JabRef metadata) is kept at the end
+    if !options.sort.is_empty() {
+        let last_entry = items
+            .iter()
+            .rposition(|item| matches!(item, Item::Entry(_)))
+            .map_or(0, |i| i + 1);
+        let tail = items.split_off(last_entry);
+        let (mut others, mut entries): (Vec<Item>, Vec<Item>) = items
+            .into_iter()
+            .partition(|item| matches!(item, Item::Other(_)));
+
+        let compare = |a: &Item, b: &Item| {
+            let (Item::Entry(a), Item::Entry(b)) = (a, b) else {
+                unreachable!("only entries are sorted");
+            };
+            options
+                .sort
+                .iter()
+                .map(|&sort_key| {
+                    match (
+                        a.sort_value(document, sort_key),
+                        b.sort_value(document, sort_key),
+                    ) {
+                        (Some(a), Some(b)) => a.cmp(&b),
+                        (Some(_), None) => Ordering::Less,
+                        (None, Some(_)) => Ordering::Greater,
+                        (None, None) => Ordering::Equal,
+                    }
+                })
+                .find(|ordering| ordering.is_ne())
+                .unwrap_or(Ordering::Equal)
+        };
+        entries.sort_by(compare);
+
+        others.extend(entries);
+        others.extend(tail);
+        items = others;
+    }
+
+    let mut out = String::new();
+    for (i, item) in items.iter().enumerate() {
+        if i > 0 {
+            out.push('\n');
+        }
+        match item {
+            Item::Entry(entry) => entry.write(&mut out, options),
+            Item::Other(text) => {
+                out.push_str(&text.replace("\r\n", "\n"));
+                out.push('\n');
+            }
+        }
+    }
+
+    if document.newline() == "\r\n" {
+        out = out.replace('\n', "\r\n");
+    }
+
+    let formatted = Document::parse(out).map_err(|e| SyntaxError {
+        offset: e.offset,
+        message: format!(
+            "Formatting would not preserve the syntax of the bibliography: {}",
+            e.message
+        ),
+    })?;
+    let keys = |document: &Document| {
+        let mut keys: Vec<String> = document
+            .entries()
+            .iter()
+            .map(|entry| document.key(entry).to_owned())
+            .collect();
+        keys.sort();
+        keys
+    };
+    if keys(&formatted) != keys(document) {
+        return Err(SyntaxError {
+            offset: 0,
+            message: "Formatting would not preserve the entries of the bibliography".to_owned(),
+        });
+    }
+    Ok(formatted)
+}
+
+/// Format the bibliography file of the source in place
+///
+/// If `check` is set, the file is not written; instead, a unified diff of the changes that would be made is printed.  If the bibliography was given in memory rather than read from a file, it is formatted to standard output.  Returns whether the file was (or would be) changed
+pub fn format_bib_file(src: &CitationSource, options: &Options, check: bool) -> Result<bool> {
+    let bib_file = src.bib_path()?;
+    let document = bib::parse_bib_document_from_str(src.bib_contents()?.into_owned(), bib_file)?;
+    let formatted = format_document(&document, options).map_err(|e| {
+        Error::Invalid(format!(
+            "Could not format bibliography file {bib_file:?}: {e}"
+        ))
+    })?;
+    let changed = formatted.src() != document.src();
+    if src.bib_src.is_some() && !check {
+        print!("{formatted}");
+        return Ok(changed);
+    }
+    if !changed {
+        return Ok(false);
+    }
+
+    if check {
+        let diff = TextDiff::from_lines(document.src(), formatted.src());
+        print!(
+            "{}",
+            diff.unified_diff()
+                .header(&format!("a/{bib_file}"), &format!("b/{bib_file}"))
+        );
+    } else {
+        bib::write_bib_document_to_file(bib_file, &formatted)?;
+        eprintln!("[INFO] Formatted bibliography file {bib_file:?}");
+    }
+
+    Ok(true)
+}
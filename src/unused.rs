@@ -1,20 +1,124 @@
 //! Check LaTeX source for any unused citations defined in bibliography
 //!
-//! Check for any bibliography entries that are defined but not used in the LaTeX source.  This module contains the logic for this functionality, which can be accessed using the [`--unused`](`crate::Group::unused`) option.
+//! Check for any bibliography entries that are defined but not used in the LaTeX source.  This module contains the logic for this functionality, which can be accessed using the `--unused` option of `citati check`.  Citations can be read from the auxiliary file written by LaTeX (see [`auxiliary`](`crate::source::auxiliary`)) instead of the LaTeX source, using its `--aux` option, or from the control file written by biblatex (see [`bcf`](`crate::source::bcf`)), using its `--bcf` option.  Documents in other markup languages are read alike (see [`Document`](`crate::source::Document`)).  Entries of bibliographies local to biblatex refsections are only used if they are cited within those refsections (see [`Bibliographies`](`crate::citations::Bibliographies`)).
 
 use super::{
-    citations::{gather_citations, HollowCitations},
-    source::{Bib, CitationSource, LaTeX},
+    citations::{gather_citations, Bibliographies, Citations, HollowCitations, LocalBibliography},
+    diagnostics::{Check, Finding},
+    error::Result,
+    source::{Abstract, Bib, BibCitation, CitationSource, Document, LaTeXCitation, Source},
 };
 
+/// Defines the finding reported for citations from bib file that are not cited
+fn report_unused(citation: &BibCitation) -> Finding {
+    let mut finding = Finding::new(
+        Check::Unused,
+        &citation.key,
+        format!("{} is not cited in the LaTeX source", citation.key),
+    );
+    finding.locations.push(citation.location.clone());
+    finding
+}
+
+/// Defines the finding reported for citations from a bibliography local to some refsections that are not cited within them
+fn report_unused_in_refsections(citation: &BibCitation, refsections: &[usize]) -> Finding {
+    let numbers: Vec<String> = refsections.iter().map(|n| n.to_string()).collect();
+    let mut finding = Finding::new(
+        Check::Unused,
+        &citation.key,
+        format!(
+            "{} is not cited in refsection{} {}",
+            citation.key,
+            if numbers.len() == 1 { "" } else { "s" },
+            numbers.join(", ")
+        ),
+    );
+    finding.locations.push(citation.location.clone());
+    finding
+}
+
+/// Keys of bib entries that are not cited
+fn uncited<S: Source>(
+    bib_entries: &HollowCitations<Bib>,
+    citations: HollowCitations<S>,
+) -> HollowCitations<Abstract> {
+    // `\nocite{*}` cites every entry in the bibliography
+    if citations.contains("*") {
+        return HollowCitations::new();
+    }
+
+    bib_entries.difference(citations)
+}
+
+/// Findings for entries of a local bibliography that are not cited within its refsections
+fn uncited_locally<S>(bibliography: &LocalBibliography, citations: &Citations<S>) -> Vec<Finding>
+where
+    S: Source<CitationType = LaTeXCitation>,
+{
+    let cited = |key: &str| {
+        citations
+            .get(key)
+            .is_some_and(|citation| bibliography.cited_by(citation))
+    };
+    if cited("*") {
+        return Vec::new();
+    }
+
+    bibliography
+        .entries
+        .list_sorted()
+        .into_iter()
+        .filter(|entry| !cited(&entry.key))
+        .map(|entry| report_unused_in_refsections(entry, &bibliography.refsections))
+        .collect()
+}
+
+/// Findings for entries of each local bibliography that are not cited within its refsections
+fn unused_locally<S>(bibliographies: &Bibliographies, citations: Citations<S>) -> Vec<Finding>
+where
+    S: Source<CitationType = LaTeXCitation>,
+{
+    bibliographies
+        .local
+        .iter()
+        .flat_map(|bibliography| uncited_locally(bibliography, &citations))
+        .collect()
+}
+
 /// List (in alphabetical order) any unused citations from LaTeX and bib sources
-pub fn unused_citations(latex_file: &str, bib_file: &str) {
-    let src = CitationSource::new(latex_file, bib_file);
-    let citations = gather_citations::<HollowCitations<LaTeX>>(&src);
-    let bib_entries = gather_citations::<HollowCitations<Bib>>(&src);
-    let unused = bib_entries.difference(citations);
-
-    for citation in unused.list_sorted() {
-        println!("{citation}");
+///
+/// Citations are read from the control file, auxiliary file, or document of the source (see [`Document`]).  Unused entries of bibliographies local to refsections follow those of the global bibliography
+pub fn unused_citations(src: &CitationSource) -> Result<Vec<Finding>> {
+    let citations = gather_citations::<HollowCitations<Document>>(src)?;
+    let mut findings = unused_globally(src, citations)?;
+    findings.extend(unused_local_citations(src)?);
+    Ok(findings)
+}
+
+/// Findings (in alphabetical order) for entries of the global bibliography of the source that are not among the given citations
+pub(crate) fn unused_globally<S: Source>(
+    src: &CitationSource,
+    citations: HollowCitations<S>,
+) -> Result<Vec<Finding>> {
+    // Keep the full bib entries so that we can report where unused citations are defined
+    let bib_citations = gather_citations::<Citations<Bib>>(src)?;
+    let bib_entries = HollowCitations::<Bib>::from(bib_citations.keys().cloned());
+    Ok(uncited(&bib_entries, citations)
+        .list_sorted()
+        .iter()
+        .filter_map(|key| bib_citations.get(key))
+        .map(report_unused)
+        .collect())
+}
+
+/// Findings for entries of bibliographies local to refsections of the source that are not cited within them
+pub(crate) fn unused_local_citations(src: &CitationSource) -> Result<Vec<Finding>> {
+    let bibliographies = gather_citations::<Bibliographies>(src)?;
+    if bibliographies.local.is_empty() {
+        return Ok(Vec::new());
     }
+    Ok(unused_locally(
+        &bibliographies,
+        gather_citations::<Citations<Document>>(src)?,
+    ))
 }
@@ -0,0 +1,97 @@
+//! Citations read from auxiliary files written by LaTeX
+//!
+//! Every key of `\citation{}` and biblatex's `\abx@aux@cite{}` is read, with the refsection that biblatex records, from the `.aux` file and those it includes with `\@input{}`; `--aux` checks these citations in place of the LaTeX source.
+
+mod common;
+
+use citati::source::auxiliary::parse_aux_citations_from_file;
+use common::{status, stdout, Project};
+
+/// Keys, refsections, and positions of the citations in the auxiliary file of the project
+fn citations(project: &Project) -> Vec<(String, usize, String)> {
+    let aux_file = project.path("document.aux");
+    parse_aux_citations_from_file(aux_file.to_str().unwrap())
+        .unwrap()
+        .into_iter()
+        .map(|citation| {
+            let file = citation.location.file.file_name().unwrap();
+            let position = format!(
+                "{}:{}:{}",
+                file.to_string_lossy(),
+                citation.location.line,
+                citation.location.column
+            );
+            (citation.key, citation.refsection, position)
+        })
+        .collect()
+}
+
+#[test]
+fn citations_are_read_with_their_locations() {
+    let project = Project::new("aux-citations").file(
+        "document.aux",
+        "\\relax\n\\citation{smith2020, doe2019}\n\\abx@aux@cite{0}{lee2018}\n\\abx@aux@cite{2}{kim2021}\n\\abx@aux@cite{old2000}\n",
+    );
+    assert_eq!(
+        citations(&project),
+        [
+            ("smith2020".to_owned(), 0, "document.aux:2:11".to_owned()),
+            ("doe2019".to_owned(), 0, "document.aux:2:22".to_owned()),
+            ("lee2018".to_owned(), 0, "document.aux:3:18".to_owned()),
+            ("kim2021".to_owned(), 2, "document.aux:4:18".to_owned()),
+            ("old2000".to_owned(), 0, "document.aux:5:15".to_owned()),
+        ]
+    );
+}
+
+#[test]
+fn included_aux_files_are_read_in_order() {
+    let project = Project::new("aux-include")
+        .file(
+            "document.aux",
+            "\\citation{first}\n\\@input{chapters/intro.aux}\n\\@input{unbuilt.aux}\n\\citation{last}\n",
+        )
+        .file("chapters/intro.aux", "\\relax\n\\citation{smith2020}\n");
+    let found: Vec<(String, String)> = citations(&project)
+        .into_iter()
+        .map(|(key, _, position)| (key, position))
+        .collect();
+    assert_eq!(
+        found,
+        [
+            ("first".to_owned(), "document.aux:1:11".to_owned()),
+            ("smith2020".to_owned(), "intro.aux:2:11".to_owned()),
+            ("last".to_owned(), "document.aux:4:11".to_owned()),
+        ]
+    );
+}
+
+#[test]
+fn checks_read_citations_from_the_aux_file() {
+    // The LaTeX source is not needed, and would not be read anyway
+    let project = Project::new("aux-check")
+        .file(
+            "references.bib",
+            "@book{doe2019,\n  title = {A},\n}\n@book{lee2018,\n  title = {B},\n}\n",
+        )
+        .file("document.aux", "\\citation{doe2019}\n\\citation{nokey}\n");
+    let output = project.run(&[
+        "check",
+        "--unused",
+        "--missing",
+        "--aux",
+        "document.aux",
+        "--format",
+        "plain",
+    ]);
+    assert_eq!(status(&output), 1);
+    let out = stdout(&output);
+    assert!(out.contains("lee2018"), "{out}");
+    assert!(out.contains("nokey"), "{out}");
+    assert!(!out.contains("doe2019"), "{out}");
+
+    // `\nocite{*}` is recorded as a citation of every entry
+    let project = project.file("document.aux", "\\citation{*}\n");
+    let output = project.run(&["check", "--unused", "--aux", "document.aux"]);
+    assert_eq!(status(&output), 0, "{}", stdout(&output));
+}
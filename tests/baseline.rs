@@ -0,0 +1,75 @@
+//! Baselines of known findings
+//!
+//! Findings recorded with `--write-baseline` are not reported by runs given the baseline with `--baseline`, even when unrelated edits move them around the bib file, whereas new findings (including a changed offending value) still are.
+
+mod common;
+
+use common::{status, stderr, stdout, Project};
+
+const BIB: &str = "@book{doe2019,\n  title = {A Book},\n  pages = {12-34},\n}\n";
+
+#[test]
+fn baseline_records_findings() {
+    let project = Project::new("baseline-write").file("references.bib", BIB);
+    let output = project.run(&["check", "-p", "--write-baseline", "baseline.json"]);
+    assert_eq!(status(&output), 0, "{}", stderr(&output));
+
+    let baseline: serde_json::Value = serde_json::from_str(&project.read("baseline.json")).unwrap();
+    assert_eq!(baseline["version"], 1);
+    let findings = baseline["findings"].as_array().unwrap();
+    assert_eq!(findings.len(), 1);
+    assert_eq!(findings[0]["check"], "pages");
+    assert_eq!(findings[0]["key"], "doe2019");
+    assert!(findings[0]["fingerprint"].is_string());
+}
+
+#[test]
+fn baseline_findings_are_not_reported() {
+    let project = Project::new("baseline-filter").file("references.bib", BIB);
+    project.run(&["check", "-p", "--write-baseline", "baseline.json"]);
+    let output = project.run(&["check", "-p", "--baseline", "baseline.json"]);
+    assert_eq!(status(&output), 0, "{}", stdout(&output));
+
+    // Moving the entry does not make its finding new
+    let project = project.file(
+        "references.bib",
+        &format!("@book{{lee2018,\n  title = {{B}},\n}}\n\n{BIB}"),
+    );
+    let output = project.run(&["check", "-p", "--baseline", "baseline.json"]);
+    assert_eq!(status(&output), 0, "{}", stdout(&output));
+}
+
+#[test]
+fn new_findings_are_reported() {
+    let project = Project::new("baseline-new").file("references.bib", BIB);
+    project.run(&["check", "-p", "--write-baseline", "baseline.json"]);
+
+    let project = project.file(
+        "references.bib",
+        &format!("{BIB}@book{{lee2018,\n  title = {{B}},\n  pages = {{1-2}},\n}}\n"),
+    );
+    let output = project.run(&["check", "-p", "--baseline", "baseline.json"]);
+    assert_eq!(status(&output), 1);
+    let out = stdout(&output);
+    assert!(out.contains("lee2018"), "{out}");
+    assert!(!out.contains("doe2019"), "{out}");
+
+    // A different offending value is a new finding
+    let project = project.file("references.bib", &BIB.replace("12-34", "56-78"));
+    let output = project.run(&["check", "-p", "--baseline", "baseline.json"]);
+    assert_eq!(status(&output), 1);
+    assert!(stdout(&output).contains("doe2019"));
+}
+
+#[test]
+fn unreadable_baselines_are_errors() {
+    let project = Project::new("baseline-invalid")
+        .file("references.bib", BIB)
+        .file("baseline.json", "{\"version\": 99, \"findings\": []}\n");
+    let output = project.run(&["check", "-p", "--baseline", "baseline.json"]);
+    assert_eq!(status(&output), 2);
+    assert!(stderr(&output).contains("version"), "{}", stderr(&output));
+
+    let output = project.run(&["check", "-p", "--baseline", "missing.json"]);
+    assert_eq!(status(&output), 2);
+}
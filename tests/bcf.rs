@@ -0,0 +1,137 @@
+//! Citations and datasources read from biblatex control files
+//!
+//! The keys cited in each refsection (noting those only cited with `\nocite`) and the datasources of each refsection are read from the `.bcf` file, relative to which datasources are found; `--bcf` checks these citations in place of the LaTeX source.
+
+mod common;
+
+use citati::source::bcf::parse_bcf_from_str;
+use common::{status, stderr, stdout, Project};
+use std::path::PathBuf;
+
+const BCF: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<bcf:controlfile version="3.10" bltxversion="3.19" xmlns:bcf="https://sourceforge.net/projects/biblatex">
+  <bcf:bibdata section="0">
+    <bcf:datasource type="file" datatype="bibtex" glob="false">references.bib</bcf:datasource>
+  </bcf:bibdata>
+  <bcf:bibdata section="1">
+    <bcf:datasource type="file" datatype="bibtex" glob="false">chapter.bib</bcf:datasource>
+  </bcf:bibdata>
+  <bcf:section number="0">
+    <bcf:citekey order="1" intorder="1">doe2019</bcf:citekey>
+    <bcf:citekey order="2" intorder="1" nocite="1">nokey</bcf:citekey>
+  </bcf:section>
+  <bcf:section number="1">
+    <bcf:citekey order="1" intorder="1">lee2018</bcf:citekey>
+  </bcf:section>
+</bcf:controlfile>
+"#;
+
+#[test]
+fn citekeys_are_read_per_section() {
+    let control_file = parse_bcf_from_str(BCF, "build/document.bcf").unwrap();
+    let citations: Vec<(String, String, usize, String)> = control_file
+        .citations()
+        .into_iter()
+        .map(|citation| {
+            let position = format!("{}:{}", citation.location.line, citation.location.column);
+            (
+                citation.key,
+                citation.cite_cmd,
+                citation.refsection,
+                position,
+            )
+        })
+        .collect();
+    assert_eq!(
+        citations,
+        [
+            (
+                "doe2019".to_owned(),
+                "cite".to_owned(),
+                0,
+                "10:41".to_owned()
+            ),
+            (
+                "nokey".to_owned(),
+                "nocite".to_owned(),
+                0,
+                "11:52".to_owned()
+            ),
+            (
+                "lee2018".to_owned(),
+                "cite".to_owned(),
+                1,
+                "14:41".to_owned()
+            ),
+        ]
+    );
+}
+
+#[test]
+fn datasources_are_relative_to_the_control_file() {
+    let control_file = parse_bcf_from_str(BCF, "build/document.bcf").unwrap();
+    let refsections: Vec<(usize, Vec<PathBuf>)> = control_file
+        .refsections()
+        .into_iter()
+        .map(|refsection| (refsection.number, refsection.resources))
+        .collect();
+    assert_eq!(
+        refsections,
+        [
+            (0, vec![PathBuf::from("build/references.bib")]),
+            (1, vec![PathBuf::from("build/chapter.bib")]),
+        ]
+    );
+}
+
+#[test]
+fn invalid_control_files_are_errors() {
+    assert!(parse_bcf_from_str("<bcf:controlfile>", "document.bcf").is_err());
+}
+
+#[test]
+fn checks_read_citations_from_the_control_file() {
+    let project = Project::new("bcf-check")
+        .file("document.bcf", BCF)
+        .file(
+            "references.bib",
+            "@book{doe2019,\n  title = {A},\n}\n@book{kim2021,\n  title = {B},\n}\n",
+        )
+        .file("chapter.bib", "@book{lee2018,\n  title = {C},\n}\n");
+    let args = [
+        "check",
+        "--unused",
+        "--missing",
+        "--bcf",
+        "document.bcf",
+        "--format",
+        "plain",
+    ];
+    let output = project.run(&args);
+    assert_eq!(status(&output), 1);
+    let out = stdout(&output);
+    assert!(out.contains("kim2021"), "{out}");
+    assert!(out.contains("nokey"), "{out}");
+    assert!(!out.contains("doe2019"), "{out}");
+
+    // Entries of the bibliography local to refsection 1 are cited there
+    assert!(!out.contains("lee2018"), "{out}");
+}
+
+#[test]
+fn unlisted_bib_files_are_warned_of() {
+    let project = Project::new("bcf-unlisted")
+        .file("document.bcf", BCF)
+        .file("other.bib", "@book{doe2019,\n  title = {A},\n}\n");
+    let output = project.run(&[
+        "check",
+        "--unused",
+        "--bcf",
+        "document.bcf",
+        "-b",
+        "other.bib",
+    ]);
+    let err = stderr(&output);
+    assert!(err.contains("is not a datasource of control file"), "{err}");
+    assert!(err.contains("references.bib"), "{err}");
+}
@@ -0,0 +1,159 @@
+//! Citation commands within parentheses
+//!
+//! Commands that print parentheses of their own are reported within parentheses, with the variant without them of the package that the document loads, and commands outside parentheses (counted within the paragraph, ignoring comments, escaped parentheses, and math) are not.
+
+mod common;
+
+use citati::{cite_commands::Package, source::CitationSource};
+use common::{status, stdout, Project};
+use serde_json::Value;
+
+/// Commands and their suggested variants reported in the LaTeX source, in order of key
+fn suggestions(latex: &str) -> Vec<(String, String, String)> {
+    let src = CitationSource::new("document.tex", "references.bib").with_latex_src(latex);
+    citati::cite_commands::check_cite_commands(&src)
+        .unwrap()
+        .into_iter()
+        .map(|finding| {
+            (
+                finding.key,
+                finding.values["command"].clone(),
+                finding.values["suggestion"].clone(),
+            )
+        })
+        .collect()
+}
+
+/// Keys of the citations reported in the LaTeX source
+fn keys(latex: &str) -> Vec<String> {
+    suggestions(latex)
+        .into_iter()
+        .map(|(key, _, _)| key)
+        .collect()
+}
+
+#[test]
+fn packages_are_detected_from_the_preamble() {
+    assert_eq!(
+        Package::detect("\\usepackage[round]{natbib}\n"),
+        Some(Package::Natbib)
+    );
+    assert_eq!(
+        Package::detect("\\usepackage{amsmath, apacite}\n"),
+        Some(Package::Apacite)
+    );
+    assert_eq!(
+        Package::detect("\\usepackage[backend=biber, style = apa]{biblatex}\n"),
+        Some(Package::BiblatexApa)
+    );
+    assert_eq!(
+        Package::detect("\\usepackage{biblatex}\n\\usepackage{natbib}\n"),
+        Some(Package::Biblatex)
+    );
+    assert_eq!(Package::detect("\\usepackage{cite}\n"), None);
+}
+
+#[test]
+fn each_package_suggests_its_own_variant() {
+    let found = suggestions("\\usepackage{natbib}\n(\\citet{a}; \\Citep*{b})\n");
+    let found: Vec<(&str, &str, &str)> = found
+        .iter()
+        .map(|(key, cmd, variant)| (key.as_str(), cmd.as_str(), variant.as_str()))
+        .collect();
+    assert_eq!(
+        found,
+        [("a", "citet", "citealt"), ("b", "Citep*", "Citealp*")]
+    );
+
+    let found = suggestions(
+        "\\usepackage{biblatex}\n(\\textcite{a}; \\parencite[p.~3]{b}; \\autocite{c})\n",
+    );
+    let variants: Vec<&str> = found
+        .iter()
+        .map(|(_, _, variant)| variant.as_str())
+        .collect();
+    assert_eq!(variants, ["cite", "cite", "cite"]);
+
+    let found = suggestions("\\usepackage[style=apa]{biblatex}\n(\\textcite{a}; \\Textcite{b})\n");
+    let variants: Vec<&str> = found
+        .iter()
+        .map(|(_, _, variant)| variant.as_str())
+        .collect();
+    assert_eq!(variants, ["nptextcite", "nptextcite"]);
+
+    let found = suggestions(
+        "\\usepackage{apacite}\n(\\textcite{a}; \\citeA{b}; \\cite{c}; \\citeyear{d})\n",
+    );
+    let variants: Vec<&str> = found
+        .iter()
+        .map(|(_, _, variant)| variant.as_str())
+        .collect();
+    assert_eq!(variants, ["nptextcite", "citeANP", "citeNP", "citeyearNP"]);
+}
+
+#[test]
+fn variants_without_parentheses_are_not_reported() {
+    assert!(
+        keys("\\usepackage{natbib}\n(\\citealt{a}; \\citealp{b}; \\citeauthor{c})\n").is_empty()
+    );
+    assert!(keys("\\usepackage{biblatex}\n(\\cite{a})\n").is_empty());
+    assert!(keys("\\usepackage{apacite}\n(\\citeANP{a}; \\nptextcite{b})\n").is_empty());
+}
+
+#[test]
+fn commands_outside_parentheses_are_not_reported() {
+    let latex = "\\usepackage{natbib}\nAs \\citet{a} shows (in passing), \\citep{b}.\n";
+    assert!(keys(latex).is_empty());
+
+    // Parentheses are counted from the start of the paragraph
+    let latex = "\\usepackage{natbib}\nAs shown (see\n\\citet{a}).\n\n(Unclosed\n\n\\citep{b}\n";
+    assert_eq!(keys(latex), ["a"]);
+}
+
+#[test]
+fn comments_escapes_and_math_are_not_parentheses() {
+    let latex = "\\usepackage{natbib}\n% (\n\\citet{a}\n\\( x \\) \\citet{b} $f(x$ \\citet{c}\n";
+    assert!(keys(latex).is_empty());
+}
+
+#[test]
+fn documents_without_a_package_are_not_checked() {
+    assert!(keys("(\\citet{a}; \\textcite{b})\n").is_empty());
+    let src = CitationSource::new("paper.md", "references.bib")
+        .with_latex_src("\\usepackage{natbib}\n(\\citet{a})\n");
+    assert!(citati::cite_commands::check_cite_commands(&src)
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn commands_in_included_files_are_reported_once_per_command() {
+    let project = Project::new("cite-commands-included")
+        .file(
+            "document.tex",
+            "\\usepackage{natbib}\n(\\citet{smith2020})\n\\input{chapter}\n",
+        )
+        .file(
+            "chapter.tex",
+            "As \\citet{smith2020} shows (cf. \\citet{smith2020}; \\citep{smith2020}).\n",
+        )
+        .file("references.bib", "@book{smith2020,\n  title = {A},\n}\n");
+    let output = project.run(&["check", "--cite-commands", "--format", "json"]);
+    assert_eq!(status(&output), 1);
+    let document: Value = serde_json::from_str(&stdout(&output)).unwrap();
+    let findings = document["findings"].as_array().unwrap();
+    assert_eq!(findings.len(), 2);
+
+    assert_eq!(
+        findings[0]["message"],
+        "\\citet is within parentheses; use \\citealt instead (natbib)"
+    );
+    let locations: Vec<(&str, u64)> = findings[0]["locations"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|l| (l["file"].as_str().unwrap(), l["line"].as_u64().unwrap()))
+        .collect();
+    assert_eq!(locations, [("document.tex", 2), ("chapter.tex", 1)]);
+    assert_eq!(findings[1]["values"]["command"], "citep");
+}
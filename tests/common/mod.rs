@@ -0,0 +1,76 @@
+//! Projects written to temporary directories, on which the `citati` binary is run
+//!
+//! Each test names its own project, so that tests running in parallel do not share files.  Search paths and colour are taken from the options given rather than from the environment in which the tests are run.
+
+#![allow(dead_code)]
+
+use std::{
+    fs,
+    path::PathBuf,
+    process::{Command, Output},
+};
+
+/// Files of a project in a fresh temporary directory
+pub struct Project {
+    pub dir: PathBuf,
+}
+
+impl Project {
+    /// Create an empty project, removing any left by a previous run of the test
+    pub fn new(name: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("citati-test-{name}"));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        Self { dir }
+    }
+
+    /// Write a file of the project, creating its directory if needed
+    pub fn file(self, path: &str, src: &str) -> Self {
+        let path = self.dir.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, src).unwrap();
+        self
+    }
+
+    /// Path of a file of the project
+    pub fn path(&self, path: &str) -> PathBuf {
+        self.dir.join(path)
+    }
+
+    /// Contents of a file of the project
+    pub fn read(&self, path: &str) -> String {
+        fs::read_to_string(self.dir.join(path)).unwrap()
+    }
+
+    /// Run `citati` with the given arguments in the directory of the project
+    pub fn run(&self, args: &[&str]) -> Output {
+        self.command(args).output().unwrap()
+    }
+
+    /// Command running `citati` with the given arguments in the directory of the project, to which input can be given
+    pub fn command(&self, args: &[&str]) -> Command {
+        let mut command = Command::new(env!("CARGO_BIN_EXE_citati"));
+        command
+            .args(args)
+            .current_dir(&self.dir)
+            .env_remove("TEXINPUTS")
+            .env_remove("BIBINPUTS")
+            .env("NO_COLOR", "1");
+        command
+    }
+}
+
+/// Standard output of a run, which must have been valid UTF-8
+pub fn stdout(output: &Output) -> String {
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+/// Standard error of a run, which must have been valid UTF-8
+pub fn stderr(output: &Output) -> String {
+    String::from_utf8(output.stderr.clone()).unwrap()
+}
+
+/// Exit status of a run, which must have exited rather than been killed by a signal
+pub fn status(output: &Output) -> i32 {
+    output.status.code().unwrap()
+}
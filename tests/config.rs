@@ -0,0 +1,115 @@
+//! Project configuration file
+//!
+//! Defaults for the options of `check` are read from `citati.toml` (or `.citati.toml`) in the directory in which it is run, and options given on the command line take precedence over them.
+
+mod common;
+
+use common::{status, stderr, stdout, Project};
+
+/// Project whose document is not at the default path, with an unused entry, a malformatted page range, and a missing key
+fn project(name: &str) -> Project {
+    Project::new(name)
+        .file("paper/main.tex", "\\cite{smith2020, nokey}\n")
+        .file(
+            "bib/refs.bib",
+            "@book{smith2020,\n  title = {A},\n  pages = {12-34},\n}\n@book{doe2019,\n  title = {B},\n}\n",
+        )
+}
+
+/// Keys reported by `check` with the given arguments, sorted, with the status of the run
+fn keys(project: &Project, args: &[&str]) -> (i32, Vec<String>) {
+    let mut all = vec!["check"];
+    all.extend(args);
+    let output = project.run(&all);
+    let mut keys: Vec<String> = stdout(&output).lines().map(str::to_owned).collect();
+    keys.sort();
+    (status(&output), keys)
+}
+
+#[test]
+fn defaults_are_read_from_the_configuration_file() {
+    let project = project("config-defaults").file(
+        "citati.toml",
+        "files = [\"paper/main.tex\"]\nbibliography = \"bib/refs.bib\"\nchecks = [\"unused\", \"missing\"]\nformat = \"plain\"\n",
+    );
+    let (status, keys) = keys(&project, &[]);
+    assert_eq!(status, 1);
+    assert_eq!(keys, ["doe2019", "nokey"]);
+}
+
+#[test]
+fn options_take_precedence_over_the_configuration_file() {
+    let project = project("config-precedence")
+        .file(
+            ".citati.toml",
+            "files = [\"paper/main.tex\"]\nbibliography = \"bib/refs.bib\"\nchecks = [\"unused\"]\nformat = \"json\"\n",
+        )
+        .file("other.tex", "\\cite{doe2019}\n");
+    let (_, keys) = keys(
+        &project,
+        &["-f", "other.tex", "--pages", "--format", "plain"],
+    );
+    assert_eq!(keys, ["smith2020 (\"12-34\")"]);
+
+    // Checks not selected on the command line are those of the file
+    let output = project.run(&["check", "-f", "other.tex"]);
+    let document: serde_json::Value = serde_json::from_str(&stdout(&output)).unwrap();
+    let findings = document["findings"].as_array().unwrap();
+    assert_eq!(findings.len(), 1);
+    assert_eq!(findings[0]["check"], "unused");
+    assert_eq!(findings[0]["key"], "smith2020");
+}
+
+#[test]
+fn search_paths_are_read_from_the_configuration_file() {
+    let project = Project::new("config-search")
+        .file("document.tex", "\\input{intro}\n")
+        .file("chapters/intro/intro.tex", "\\cite{nokey}\n")
+        .file(
+            "bib/nested/references.bib",
+            "@book{smith2020,\n  title = {A},\n}\n",
+        )
+        .file(
+            "citati.toml",
+            "texinputs = \".:chapters//\"\nbibinputs = \"bib//\"\n",
+        );
+    let (status, keys) = keys(&project, &["--unused", "--missing", "--format", "plain"]);
+    assert_eq!(status, 1);
+    assert_eq!(keys, ["nokey", "smith2020"]);
+
+    // Search paths given as options take precedence
+    let output = project.run(&[
+        "check",
+        "--missing",
+        "--texinputs",
+        ".",
+        "--format",
+        "plain",
+    ]);
+    assert_eq!(stdout(&output), "");
+}
+
+#[test]
+fn invalid_configuration_files_are_errors() {
+    let project = project("config-invalid").file("citati.toml", "checks = [\"spelling\"]\n");
+    let output = project.run(&["check"]);
+    assert_eq!(status(&output), 2);
+    assert!(stderr(&output).contains("Could not parse configuration file"));
+
+    let project = project.file("citati.toml", "file = \"paper/main.tex\"\n");
+    let output = project.run(&["check"]);
+    assert_eq!(status(&output), 2);
+    assert!(
+        stderr(&output).contains("unknown field `file`"),
+        "{}",
+        stderr(&output)
+    );
+
+    let project = project.file(
+        "citati.toml",
+        "checks = [\"unused\", \"unused-suppression\"]\n",
+    );
+    let output = project.run(&["check"]);
+    assert_eq!(status(&output), 2);
+    assert!(stderr(&output).contains("cannot be selected with other checks"));
+}
@@ -0,0 +1,210 @@
+//! Round-tripping and targeted edits of the lossless bibliography layer
+//!
+//! Edits must only change the region that they target: the rest of the document, including whitespace, comments, and special blocks, must be byte-for-byte identical to the source.
+
+use citati::source::bib::cst::Document;
+
+/// Bibliography exercising the syntax that the parser must preserve as-is
+const SRC: &str = "% A leading comment
+@string{jfoo = \"Journal of Foo\"}
+
+@Article{smith2020,
+  author    = {Smith, Jane and {Doe \\& Sons}},
+  title     = \"On {Nested} Braces\",
+  journal   = jfoo # { Letters},
+  year      = 2020,
+  pages     = {12-34},
+}
+
+Free text between entries is kept.
+@book(doe2019,
+\ttitle = {A Book},  % trailing comment
+\tyear = {2019}
+)
+@comment{@article{ignored, title = {x}}}
+@misc{empty}
+";
+
+fn parse(src: &str) -> Document {
+    Document::parse(src.to_owned()).unwrap()
+}
+
+fn keys(document: &Document) -> Vec<&str> {
+    document
+        .entries()
+        .iter()
+        .map(|entry| document.key(entry))
+        .collect()
+}
+
+#[test]
+fn round_trip_is_byte_for_byte() {
+    let document = parse(SRC);
+    assert_eq!(document.src(), SRC);
+    assert_eq!(document.to_string(), SRC);
+    assert_eq!(keys(&document), ["smith2020", "doe2019", "empty"]);
+}
+
+#[test]
+fn round_trip_preserves_crlf() {
+    let src = SRC.replace('\n', "\r\n");
+    let document = parse(&src);
+    assert_eq!(document.src(), src);
+    assert_eq!(document.newline(), "\r\n");
+}
+
+#[test]
+fn set_existing_field_keeps_delimiters() {
+    let mut document = parse(SRC);
+    assert!(document.set_field("smith2020", "pages", "12--34").unwrap());
+    assert!(document.set_field("smith2020", "title", "New").unwrap());
+    assert!(document.set_field("smith2020", "year", "2021").unwrap());
+    assert_eq!(
+        document.src(),
+        SRC.replace("{12-34}", "{12--34}")
+            .replace("\"On {Nested} Braces\"", "\"New\"")
+            .replace("= 2020", "= 2021")
+    );
+}
+
+#[test]
+fn set_bare_field_to_text_adds_braces() {
+    let mut document = parse(SRC);
+    assert!(document.set_field("smith2020", "year", "in press").unwrap());
+    assert_eq!(document.src(), SRC.replace("= 2020", "= {in press}"));
+}
+
+#[test]
+fn set_new_field_after_last_field() {
+    let mut document = parse(SRC);
+    assert!(document.set_field("smith2020", "doi", "10.1/x").unwrap());
+    assert!(document.set_field("doe2019", "publisher", "P").unwrap());
+    assert_eq!(
+        document.src(),
+        SRC.replace(
+            "pages     = {12-34},\n",
+            "pages     = {12-34},\n  doi = {10.1/x},\n"
+        )
+        .replace("\tyear = {2019}\n", "\tyear = {2019},\n\tpublisher = {P}\n")
+    );
+}
+
+#[test]
+fn set_field_of_entry_without_fields() {
+    let mut document = parse(SRC);
+    assert!(document.set_field("empty", "title", "T").unwrap());
+    assert_eq!(
+        document.src(),
+        SRC.replace("@misc{empty}", "@misc{empty,\n  title = {T}\n}")
+    );
+}
+
+#[test]
+fn set_field_of_missing_entry() {
+    let mut document = parse(SRC);
+    assert!(!document.set_field("nobody", "title", "T").unwrap());
+    assert_eq!(document.src(), SRC);
+}
+
+#[test]
+fn remove_field() {
+    let mut document = parse(SRC);
+    assert!(document.remove_field("smith2020", "journal").unwrap());
+    assert!(!document.remove_field("smith2020", "journal").unwrap());
+    assert_eq!(
+        document.src(),
+        SRC.replace("  journal   = jfoo # { Letters},\n", "")
+    );
+}
+
+#[test]
+fn remove_last_field_without_comma() {
+    let mut document = parse(SRC);
+    assert!(document.remove_field("doe2019", "year").unwrap());
+    assert_eq!(
+        document.src(),
+        SRC.replace(",  % trailing comment\n\tyear = {2019}", "")
+    );
+    assert_eq!(keys(&document), ["smith2020", "doe2019", "empty"]);
+}
+
+#[test]
+fn rename_field() {
+    let mut document = parse(SRC);
+    assert!(document
+        .rename_field("smith2020", "JOURNAL", "journaltitle")
+        .unwrap());
+    assert_eq!(
+        document.src(),
+        SRC.replace("journal   =", "journaltitle   =")
+    );
+}
+
+#[test]
+fn rename_key() {
+    let mut document = parse(SRC);
+    assert!(document.rename_key("doe2019", "doe2019a").unwrap());
+    assert!(!document.rename_key("doe2019", "doe2019b").unwrap());
+    assert_eq!(document.src(), SRC.replace("doe2019,", "doe2019a,"));
+    assert_eq!(keys(&document), ["smith2020", "doe2019a", "empty"]);
+}
+
+#[test]
+fn delete_entry_removes_its_lines() {
+    let mut document = parse(SRC);
+    assert!(document.delete_entry("doe2019"));
+    assert!(!document.delete_entry("doe2019"));
+    assert_eq!(
+        document.src(),
+        SRC.replace(
+            "@book(doe2019,\n\ttitle = {A Book},  % trailing comment\n\tyear = {2019}\n)\n",
+            ""
+        )
+    );
+    assert_eq!(keys(&document), ["smith2020", "empty"]);
+}
+
+#[test]
+fn edits_shift_following_entries() {
+    let mut document = parse(SRC);
+    assert!(document.rename_key("smith2020", "s").unwrap());
+    assert!(document
+        .set_field("doe2019", "title", "Another Book")
+        .unwrap());
+    assert!(document.set_field("smith2020", "pages", "1--2").is_ok());
+    assert!(document.rename_key("empty", "full").unwrap());
+    assert_eq!(
+        document.src(),
+        SRC.replace("smith2020,", "s,")
+            .replace("{A Book}", "{Another Book}")
+            .replace("{empty}", "{full}")
+    );
+
+    // The edited document parses to the same entries
+    let reparsed = parse(document.src());
+    assert_eq!(keys(&reparsed), keys(&document));
+}
+
+#[test]
+fn invalid_edits_are_rejected_and_undone() {
+    let mut document = parse(SRC);
+    for key in ["a b", "a,b", ""] {
+        assert!(document.rename_key("doe2019", key).is_err(), "{key:?}");
+        assert!(document.rename_key("empty", key).is_err(), "{key:?}");
+    }
+
+    // Which closing delimiter ends the key depends on that of the entry
+    assert!(document.rename_key("doe2019", "a)b").is_err());
+    assert!(document.rename_key("empty", "a}b").is_err());
+    assert!(document.rename_field("smith2020", "title", "a b").is_err());
+    assert!(document
+        .set_field("smith2020", "title", "Unbalanced }")
+        .is_err());
+    assert!(document.set_field("doe2019", "note", "{").is_err());
+    assert_eq!(document.src(), SRC);
+    assert_eq!(keys(&document), ["smith2020", "doe2019", "empty"]);
+
+    // The document can still be edited afterwards
+    assert!(document.rename_key("doe2019", "doe").unwrap());
+    assert_eq!(document.src(), SRC.replace("doe2019,", "doe,"));
+}
@@ -0,0 +1,66 @@
+//! Exit statuses of `citati check`
+//!
+//! The status is 0 when nothing is found at or above the `--fail-on` severity (or `--warn-only` is given), 1 when something is, and 2 when the checks cannot be carried out at all.
+
+mod common;
+
+use common::{status, stderr, Project};
+
+/// Project with an unused entry (a warning) and a missing citation (an error)
+fn project(name: &str) -> Project {
+    Project::new(name)
+        .file("document.tex", "See \\cite{nokey}.\n")
+        .file("references.bib", "@book{doe2019,\n  title = {A Book},\n}\n")
+}
+
+#[test]
+fn clean_checks_succeed() {
+    let project = Project::new("exit-clean")
+        .file("document.tex", "See \\cite{doe2019}.\n")
+        .file("references.bib", "@book{doe2019,\n  title = {A Book},\n}\n");
+    assert_eq!(status(&project.run(&["check", "--unused", "--missing"])), 0);
+}
+
+#[test]
+fn findings_fail() {
+    let project = project("exit-findings");
+    assert_eq!(status(&project.run(&["check", "--unused"])), 1);
+    assert_eq!(status(&project.run(&["check", "--missing"])), 1);
+}
+
+#[test]
+fn only_findings_at_or_above_the_severity_fail() {
+    let project = project("exit-fail-on");
+    let unused = ["check", "--unused", "--fail-on", "error"];
+    assert_eq!(status(&project.run(&unused)), 0);
+    let missing = ["check", "--missing", "--fail-on", "error"];
+    assert_eq!(status(&project.run(&missing)), 1);
+    let warning = ["check", "--unused", "--fail-on", "warning"];
+    assert_eq!(status(&project.run(&warning)), 1);
+}
+
+#[test]
+fn warn_only_always_succeeds() {
+    let project = project("exit-warn-only");
+    let output = project.run(&["check", "--unused", "--missing", "--warn-only"]);
+    assert_eq!(status(&output), 0);
+}
+
+#[test]
+fn errors_are_reported() {
+    let project = Project::new("exit-error").file("document.tex", "See \\cite{nokey}.\n");
+    let output = project.run(&["check", "--missing", "-b", "nonexistent.bib"]);
+    assert_eq!(status(&output), 2);
+    assert!(stderr(&output).starts_with("[ERROR] "));
+
+    let output = project.run(&["check", "--warn-only", "-b", "nonexistent.bib"]);
+    assert_eq!(status(&output), 2);
+}
+
+#[test]
+fn usage_errors_are_reported() {
+    let project = project("exit-usage");
+    assert_eq!(status(&project.run(&["check", "--no-such-option"])), 2);
+    let both = ["check", "--fail-on", "error", "--warn-only"];
+    assert_eq!(status(&project.run(&both)), 2);
+}
@@ -0,0 +1,105 @@
+//! JSON and newline-delimited JSON output of findings
+//!
+//! Both follow the versioned schema documented in `output::json`: every finding has its check, severity, key, message, fields, values, and locations (which have a cell only within Jupyter notebooks), and the schema version is given once per document or once per line.
+
+mod common;
+
+use common::{status, stdout, Project};
+use serde_json::{json, Value};
+
+/// Project with an unused entry and a malformed page range
+fn project(name: &str) -> Project {
+    Project::new(name)
+        .file("document.tex", "See \\cite{smith2020}.\n")
+        .file(
+            "references.bib",
+            "@article{smith2020,\n  author = {Smith, Jane},\n  title = {On Things},\n  journal = {Journal of Foo},\n  year = {2020},\n  volume = {1},\n  pages = {12-34},\n}\n\n@book{doe2019,\n  title = {A Book},\n}\n",
+        )
+}
+
+#[test]
+fn json_is_a_single_versioned_document() {
+    let project = project("json-document");
+    let output = project.run(&["check", "--unused", "--pages", "--format", "json"]);
+    assert_eq!(status(&output), 1);
+
+    let document: Value = serde_json::from_str(&stdout(&output)).unwrap();
+    assert_eq!(document["version"], 1);
+    assert_eq!(document["tool"]["name"], "citati");
+    let findings = document["findings"].as_array().unwrap();
+    assert_eq!(findings.len(), 2);
+
+    assert_eq!(
+        findings[0],
+        json!({
+            "check": "unused",
+            "severity": "warning",
+            "key": "doe2019",
+            "message": "doe2019 is not cited in the LaTeX source",
+            "fields": [],
+            "values": {},
+            "locations": [{ "file": "references.bib", "line": 10, "column": 1 }],
+        })
+    );
+    assert_eq!(findings[1]["check"], "pages");
+    assert_eq!(findings[1]["key"], "smith2020");
+    assert_eq!(findings[1]["fields"], json!(["pages"]));
+    assert_eq!(findings[1]["values"], json!({ "pages": "12-34" }));
+}
+
+#[test]
+fn ndjson_is_one_versioned_finding_per_line() {
+    let project = project("json-ndjson");
+    let output = project.run(&["check", "--unused", "--pages", "--format", "ndjson"]);
+    assert_eq!(status(&output), 1);
+
+    let lines: Vec<Value> = stdout(&output)
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(lines.len(), 2);
+    for line in lines.iter() {
+        assert_eq!(line["version"], 1);
+    }
+    assert_eq!(lines[0]["key"], "doe2019");
+    assert_eq!(lines[1]["key"], "smith2020");
+}
+
+#[test]
+fn no_findings_is_an_empty_list() {
+    let project = Project::new("json-empty")
+        .file("document.tex", "See \\cite{doe2019}.\n")
+        .file("references.bib", "@book{doe2019,\n  title = {A Book},\n}\n");
+    let output = project.run(&["check", "--unused", "--format", "json"]);
+    assert_eq!(status(&output), 0);
+    let document: Value = serde_json::from_str(&stdout(&output)).unwrap();
+    assert_eq!(document["findings"], json!([]));
+
+    let output = project.run(&["check", "--unused", "--format", "ndjson"]);
+    assert_eq!(stdout(&output), "");
+}
+
+#[test]
+fn locations_in_notebooks_have_their_cell() {
+    let project = Project::new("json-notebook")
+        .file(
+            "paper.ipynb",
+            r##"{"cells": [
+                {"cell_type": "code", "source": ["print(1)"]},
+                {"cell_type": "markdown", "source": ["# Results\n", "As shown [@nokey]."]}
+            ]}"##,
+        )
+        .file("references.bib", "");
+    let args = ["check", "-f", "paper.ipynb", "--missing", "--format"];
+    let output = project.run(&[&args[..], &["json"]].concat());
+    assert_eq!(status(&output), 1);
+    let document: Value = serde_json::from_str(&stdout(&output)).unwrap();
+    assert_eq!(
+        document["findings"][0]["locations"],
+        json!([{ "file": "paper.ipynb", "line": 2, "column": 12, "cell": 2 }])
+    );
+
+    let output = project.run(&[&args[..], &["ndjson"]].concat());
+    let line: Value = serde_json::from_str(stdout(&output).trim_end()).unwrap();
+    assert_eq!(line["locations"][0]["cell"], 2);
+}
@@ -0,0 +1,129 @@
+//! Files included by LaTeX source
+//!
+//! Citations in files included with `\input{}` or `\include{}` are read in the order in which LaTeX would read them (whatever the case of the command citing them), and are attributed to the file in which they are found.  A file can name the root document including it with a magic comment at its top, but not elsewhere.
+
+use citati::source::latex::{self, Lexer, Token};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Write the files of a document to a fresh directory, returning the path of its root `main.tex`
+fn document(name: &str, files: &[(&str, &str)]) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("citati-test-{name}"));
+    let _ = fs::remove_dir_all(&dir);
+    for (file, src) in files {
+        let path = dir.join(file);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, src).unwrap();
+    }
+    dir.join("main.tex")
+}
+
+/// Keys cited by the document, in order, with the names of the files in which they are cited
+fn citations(main: &Path) -> Vec<(String, String)> {
+    let mut lexer = Lexer::from_path(main).unwrap();
+    std::iter::from_fn(|| lexer.next_token().transpose())
+        .filter_map(|token| match token.unwrap() {
+            Token::Citation(citation) => {
+                let file = citation.location.file.file_name().unwrap();
+                Some((citation.key, file.to_string_lossy().into_owned()))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn included_chapters_are_read() {
+    let main = document(
+        "include",
+        &[
+            (
+                "main.tex",
+                "\\cite{first}\n\\include{chapters/intro}\n\\cite{last}\n",
+            ),
+            ("chapters/intro.tex", "See \\cite{smith2020}.\n"),
+        ],
+    );
+    let found = citations(&main);
+    let found: Vec<(&str, &str)> = found
+        .iter()
+        .map(|(k, f)| (k.as_str(), f.as_str()))
+        .collect();
+    assert_eq!(
+        found,
+        [
+            ("first", "main.tex"),
+            ("smith2020", "intro.tex"),
+            ("last", "main.tex")
+        ]
+    );
+}
+
+#[test]
+fn files_included_on_one_line_are_read_in_order() {
+    let main = document(
+        "include-order",
+        &[
+            ("main.tex", "\\input{a}\\include{b}\n\\cite{last}\n"),
+            ("a.tex", "\\cite{a}\n"),
+            ("b.tex", "\\cite{b}\n"),
+        ],
+    );
+    let keys: Vec<String> = citations(&main).into_iter().map(|(key, _)| key).collect();
+    assert_eq!(keys, ["a", "b", "last"]);
+}
+
+#[test]
+fn other_commands_are_not_followed() {
+    let main = document(
+        "include-other",
+        &[(
+            "main.tex",
+            "\\usepackage[utf8]{inputenc}\\inputencoding{utf8}\n\\includegraphics{figure}\\cite{smith2020}\n",
+        )],
+    );
+    let keys: Vec<String> = citations(&main).into_iter().map(|(key, _)| key).collect();
+    assert_eq!(keys, ["smith2020"]);
+}
+
+#[test]
+fn root_documents_are_named_at_the_top_of_files() {
+    let main = document(
+        "magic-root",
+        &[
+            (
+                "main.tex",
+                "\\documentclass{report}\n\\input{chapters/intro}\n",
+            ),
+            (
+                "chapters/intro.tex",
+                "% Introduction\n\n  %!TeX root = ../main.tex\n\\chapter{Introduction}\n",
+            ),
+            (
+                "chapters/building.tex",
+                "\\chapter{Building}\nEach chapter starts with\n% !TeX root = ../main.tex\n",
+            ),
+        ],
+    );
+    let dir = main.parent().unwrap();
+    assert_eq!(latex::root_document(&dir.join("chapters/intro.tex")), main);
+
+    // A magic comment after the first line of code is not one
+    let building = dir.join("chapters/building.tex");
+    assert_eq!(latex::root_document(&building), building);
+}
+
+#[test]
+fn capitalised_commands_are_read() {
+    let main = document(
+        "capitalised-commands",
+        &[(
+            "main.tex",
+            "\\Citet{a} and \\Citep*{b}, \\Cite{c}, \\CiteA{d}, \\Textcite{e}, \\Parencite[p.~3]{f}\n",
+        )],
+    );
+    let keys: Vec<String> = citations(&main).into_iter().map(|(key, _)| key).collect();
+    assert_eq!(keys, ["a", "b", "c", "d", "e", "f"]);
+}
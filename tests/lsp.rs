@@ -0,0 +1,158 @@
+//! Language server
+//!
+//! `citati lsp` publishes the findings in documents open in the editor, using their text there rather than on disk, as diagnostics of the files in which they are located, and clears them once they are fixed.
+
+mod common;
+
+use common::{status, Project};
+use serde_json::{json, Value};
+use std::{io::Write, process::Stdio};
+
+/// Frame a message as it is sent over standard input
+fn frame(message: &Value) -> String {
+    let body = message.to_string();
+    format!("Content-Length: {}\r\n\r\n{body}", body.len())
+}
+
+/// Messages written by the server to standard output
+fn messages(out: &[u8]) -> Vec<Value> {
+    let out = String::from_utf8(out.to_vec()).unwrap();
+    let mut messages = Vec::new();
+    let mut rest = out.as_str();
+    while let Some((headers, body)) = rest.split_once("\r\n\r\n") {
+        let length: usize = headers
+            .strip_prefix("Content-Length: ")
+            .unwrap()
+            .parse()
+            .unwrap();
+        messages.push(serde_json::from_str(&body[..length]).unwrap());
+        rest = &body[length..];
+    }
+    messages
+}
+
+/// Run the server in the directory of the project, sending the given messages between initialising it and shutting it down
+fn serve(project: &Project, messages_sent: &[Value]) -> (i32, Vec<Value>) {
+    let mut input = frame(&json!({
+        "jsonrpc": "2.0", "id": 1, "method": "initialize",
+        "params": { "capabilities": {} },
+    }));
+    input += &frame(&json!({ "jsonrpc": "2.0", "method": "initialized", "params": {} }));
+    for message in messages_sent {
+        input += &frame(message);
+    }
+    input += &frame(&json!({ "jsonrpc": "2.0", "id": 2, "method": "shutdown" }));
+    input += &frame(&json!({ "jsonrpc": "2.0", "method": "exit" }));
+
+    let mut child = project
+        .command(&["lsp"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    (status(&output), messages(&output.stdout))
+}
+
+fn uri(project: &Project, path: &str) -> String {
+    format!("file://{}", project.path(path).display())
+}
+
+fn did_open(uri: &str, text: &str) -> Value {
+    json!({
+        "jsonrpc": "2.0", "method": "textDocument/didOpen",
+        "params": { "textDocument": { "uri": uri, "languageId": "latex", "version": 1, "text": text } },
+    })
+}
+
+/// Diagnostics published last for each URI
+fn diagnostics(messages: &[Value]) -> std::collections::BTreeMap<String, Vec<Value>> {
+    messages
+        .iter()
+        .filter(|m| m["method"] == "textDocument/publishDiagnostics")
+        .map(|m| {
+            let params = &m["params"];
+            let uri = params["uri"].as_str().unwrap().to_owned();
+            (uri, params["diagnostics"].as_array().unwrap().clone())
+        })
+        .collect()
+}
+
+#[test]
+fn server_initialises_and_shuts_down() {
+    let project = Project::new("lsp-lifecycle");
+    let (status, messages) = serve(&project, &[]);
+    assert_eq!(status, 0);
+    assert_eq!(messages[0]["id"], 1);
+    let capabilities = &messages[0]["result"]["capabilities"];
+    assert_eq!(capabilities["textDocumentSync"]["change"], 1);
+    assert_eq!(capabilities["positionEncoding"], "utf-16");
+    assert_eq!(messages[1]["id"], 2);
+    assert_eq!(messages[1]["result"], Value::Null);
+}
+
+#[test]
+fn findings_are_published_where_they_are_located() {
+    let project = Project::new("lsp-diagnostics").file(
+        "references.bib",
+        "@book{doe2019,\n  title = {A Book},\n  pages = {12-34},\n}\n",
+    );
+    let document = uri(&project, "document.tex");
+
+    // The document need not have been saved to disk
+    let (_, messages) = serve(&project, &[did_open(&document, "😀 \\cite{nokey}.\n")]);
+    let diagnostics = diagnostics(&messages);
+
+    let missing = &diagnostics[&document];
+    assert_eq!(missing.len(), 1);
+    assert_eq!(missing[0]["code"], "missing");
+    assert_eq!(missing[0]["severity"], 1);
+    assert_eq!(missing[0]["source"], "citati");
+    assert_eq!(missing[0]["range"]["start"]["line"], 0);
+
+    // Positions are counted in UTF-16 code units, of which the emoji has two
+    assert_eq!(missing[0]["range"]["start"]["character"], 9);
+
+    let bib = &diagnostics[&uri(&project, "references.bib")];
+    let mut codes: Vec<&str> = bib.iter().map(|d| d["code"].as_str().unwrap()).collect();
+    codes.sort();
+    assert_eq!(codes, ["pages", "unused"]);
+    assert!(bib.iter().all(|d| d["severity"] == 2));
+}
+
+#[test]
+fn fixed_findings_are_cleared() {
+    let project = Project::new("lsp-clear")
+        .file("references.bib", "@book{doe2019,\n  title = {A Book},\n}\n");
+    let document = uri(&project, "document.tex");
+    let change = json!({
+        "jsonrpc": "2.0", "method": "textDocument/didChange",
+        "params": {
+            "textDocument": { "uri": document, "version": 2 },
+            "contentChanges": [{ "text": "See \\cite{doe2019}.\n" }],
+        },
+    });
+    let (_, messages) = serve(
+        &project,
+        &[did_open(&document, "See \\cite{nokey}.\n"), change],
+    );
+    let diagnostics = diagnostics(&messages);
+    assert!(diagnostics[&document].is_empty());
+    assert!(diagnostics[&uri(&project, "references.bib")].is_empty());
+}
+
+#[test]
+fn unsupported_requests_are_errors() {
+    let project = Project::new("lsp-unsupported");
+    let hover = json!({ "jsonrpc": "2.0", "id": 7, "method": "textDocument/hover", "params": {} });
+    let (status, messages) = serve(&project, &[hover]);
+    assert_eq!(status, 0);
+    let response = messages.iter().find(|m| m["id"] == 7).unwrap();
+    assert_eq!(response["error"]["code"], -32601);
+}
@@ -0,0 +1,130 @@
+//! Citations read from Pandoc Markdown, and what is not a citation
+//!
+//! Keys are cited in bracket groups (with or without the author suppressed) or in the text itself, but not in email addresses, code spans, code blocks, or HTML comments, and Markdown documents are checked against the bibliography as LaTeX documents are.
+
+mod common;
+
+use citati::source::{latex::Token, markdown};
+use common::{status, stdout, Project};
+use std::path::Path;
+
+/// Keys cited in the source, in order, with the citation command (the Pandoc citation mode) of each
+fn citations(src: &str) -> Vec<(String, String)> {
+    markdown::parse_markdown_from_str(src, Path::new("paper.md"))
+        .into_iter()
+        .filter_map(|token| match token {
+            Token::Citation(citation) => Some((citation.key, citation.cite_cmd)),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Keys cited in the source, in order
+fn keys(src: &str) -> Vec<String> {
+    citations(src).into_iter().map(|(key, _)| key).collect()
+}
+
+#[test]
+fn bracket_groups_cite_each_key() {
+    assert_eq!(
+        keys("As shown [see @smith2020, p. 4; @doe2019, ch. 2].\n"),
+        ["smith2020", "doe2019"]
+    );
+    assert_eq!(keys("Braced keys [@{lee:2018/a}].\n"), ["lee:2018/a"]);
+}
+
+#[test]
+fn citation_modes_are_recorded() {
+    let found = citations("@smith2020 says so [-@doe2019; @lee2018].\n");
+    let found: Vec<(&str, &str)> = found
+        .iter()
+        .map(|(key, mode)| (key.as_str(), mode.as_str()))
+        .collect();
+    assert_eq!(
+        found,
+        [
+            ("smith2020", "author-in-text"),
+            ("doe2019", "suppress-author"),
+            ("lee2018", "normal"),
+        ]
+    );
+}
+
+#[test]
+fn narrative_keys_end_before_trailing_punctuation() {
+    assert_eq!(
+        keys("As in @smith2020: see @doe2019.\n"),
+        ["smith2020", "doe2019"]
+    );
+}
+
+#[test]
+fn email_addresses_are_not_cited() {
+    assert!(keys("Write to jane@example.com or j.doe2019@uni.edu\n").is_empty());
+    assert_eq!(
+        keys("Write to jane@example.com [@smith2020]\n"),
+        ["smith2020"]
+    );
+}
+
+#[test]
+fn code_is_not_cited() {
+    assert_eq!(keys("`@doe2019` and @smith2020\n"), ["smith2020"]);
+    assert_eq!(keys("``a ` @doe2019`` and @smith2020\n"), ["smith2020"]);
+    assert_eq!(
+        keys("```python\nprint(\"@doe2019\")\n```\n@smith2020\n"),
+        ["smith2020"]
+    );
+    assert_eq!(
+        keys("~~~~\n@doe2019\n~~~\n~~~~\n@smith2020\n"),
+        ["smith2020"]
+    );
+    assert_eq!(keys("Text\n\n    @doe2019\n\n@smith2020\n"), ["smith2020"]);
+}
+
+#[test]
+fn indented_list_items_are_cited() {
+    assert_eq!(
+        keys("- First point\n\n    continued [@smith2020]\n"),
+        ["smith2020"]
+    );
+}
+
+#[test]
+fn html_comments_are_not_cited() {
+    assert_eq!(keys("<!-- @doe2019 --> @smith2020\n"), ["smith2020"]);
+    assert_eq!(keys("<!--\n[@doe2019]\n-->\n[@smith2020]\n"), ["smith2020"]);
+}
+
+#[test]
+fn nocite_metadata_is_cited() {
+    let src = "---\ntitle: Paper\nnocite: |\n  @lee2018, @kim2021\n---\n\n[@smith2020]\n";
+    assert_eq!(keys(src), ["lee2018", "kim2021", "smith2020"]);
+}
+
+#[test]
+fn markdown_documents_are_checked() {
+    let project = Project::new("markdown-check")
+        .file(
+            "paper.md",
+            "As @smith2020 says [see -@doe2019, p. 4; @nokey].\n\n`@lee2018` is code.\n",
+        )
+        .file(
+            "references.bib",
+            "@book{smith2020,\n  title = {A},\n}\n@book{doe2019,\n  title = {B},\n}\n@book{lee2018,\n  title = {C},\n}\n",
+        );
+    let output = project.run(&[
+        "check",
+        "-f",
+        "paper.md",
+        "--unused",
+        "--missing",
+        "--format",
+        "plain",
+    ]);
+    assert_eq!(status(&output), 1);
+    let out = stdout(&output);
+    let mut keys: Vec<&str> = out.lines().collect();
+    keys.sort();
+    assert_eq!(keys, ["lee2018", "nokey"]);
+}
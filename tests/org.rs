@@ -0,0 +1,143 @@
+//! Citations read from Org documents, and what is not a citation
+//!
+//! Keys are cited with org-cite or org-ref links, but not within comments, verbatim blocks, or inline code, and the bibliography of the document given with `#+bibliography:` is the one against which it is checked.
+
+mod common;
+
+use citati::source::{latex::Token, org};
+use common::{status, stdout, Project};
+use std::path::Path;
+
+/// Keys cited in the source, in order, with the citation command of each
+fn citations(src: &str) -> Vec<(String, String)> {
+    org::parse_org_from_str(src, Path::new("notes.org"))
+        .tokens
+        .into_iter()
+        .filter_map(|token| match token {
+            Token::Citation(citation) => Some((citation.key, citation.cite_cmd)),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Keys cited in the source, in order
+fn keys(src: &str) -> Vec<String> {
+    citations(src).into_iter().map(|(key, _)| key).collect()
+}
+
+#[test]
+fn org_cite_citations_cite_each_key() {
+    let found = citations("See [cite:@smith2020] and [cite/t:see @doe2019 p. 4; @lee2018].\n");
+    let found: Vec<(&str, &str)> = found
+        .iter()
+        .map(|(key, cmd)| (key.as_str(), cmd.as_str()))
+        .collect();
+    assert_eq!(
+        found,
+        [
+            ("smith2020", "cite"),
+            ("doe2019", "cite/t"),
+            ("lee2018", "cite/t"),
+        ]
+    );
+}
+
+#[test]
+fn org_ref_links_cite_each_key() {
+    let found =
+        citations("As citet:smith2020 shows (citep:doe2019,lee2018; cite:&kim2021;&jones2022).\n");
+    let found: Vec<(&str, &str)> = found
+        .iter()
+        .map(|(key, cmd)| (key.as_str(), cmd.as_str()))
+        .collect();
+    assert_eq!(
+        found,
+        [
+            ("smith2020", "citet"),
+            ("doe2019", "citep"),
+            ("lee2018", "citep"),
+            ("kim2021", "cite"),
+            ("jones2022", "cite"),
+        ]
+    );
+}
+
+#[test]
+fn keys_end_before_trailing_punctuation() {
+    assert_eq!(
+        keys("As in cite:smith2020. See [cite:@doe2019.]\n"),
+        ["smith2020", "doe2019"]
+    );
+}
+
+#[test]
+fn comments_are_not_cited() {
+    assert_eq!(keys("# cite:doe2019\ncite:smith2020\n"), ["smith2020"]);
+    assert_eq!(
+        keys("#+begin_comment\n[cite:@doe2019]\n#+end_comment\n[cite:@smith2020]\n"),
+        ["smith2020"]
+    );
+}
+
+#[test]
+fn verbatim_blocks_are_not_cited() {
+    assert_eq!(
+        keys("#+BEGIN_SRC python\nprint(\"cite:doe2019\")\n#+END_SRC\ncite:smith2020\n"),
+        ["smith2020"]
+    );
+    assert_eq!(
+        keys("#+begin_example\n[cite:@doe2019]\n#+end_example\n[cite:@smith2020]\n"),
+        ["smith2020"]
+    );
+}
+
+#[test]
+fn inline_code_is_not_cited() {
+    assert_eq!(
+        keys("Not ~cite:doe2019~ or =[cite:@lee2018]=, but cite:smith2020\n"),
+        ["smith2020"]
+    );
+}
+
+#[test]
+fn bibliographies_are_read() {
+    let document = org::parse_org_from_str(
+        "#+bibliography: refs.bib other.bib\n#+BIBLIOGRAPHY: more.bib\n",
+        Path::new("notes/notes.org"),
+    );
+    assert_eq!(
+        document.bibliographies,
+        [
+            Path::new("notes/refs.bib"),
+            Path::new("notes/other.bib"),
+            Path::new("notes/more.bib"),
+        ]
+    );
+}
+
+#[test]
+fn org_documents_are_checked_against_their_bibliography() {
+    let project = Project::new("org-check")
+        .file(
+            "notes.org",
+            "#+bibliography: refs.bib\nSee [cite:@smith2020; @nokey].\n# cite:lee2018\n",
+        )
+        .file(
+            "refs.bib",
+            "@book{smith2020,\n  title = {A},\n}\n@book{lee2018,\n  title = {C},\n}\n",
+        );
+    let output = project.run(&[
+        "check",
+        "-f",
+        "notes.org",
+        "--unused",
+        "--missing",
+        "--format",
+        "plain",
+    ]);
+    assert_eq!(status(&output), 1);
+    let out = stdout(&output);
+    let mut keys: Vec<&str> = out.lines().collect();
+    keys.sort();
+    assert_eq!(keys, ["lee2018", "nokey"]);
+}
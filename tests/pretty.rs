@@ -0,0 +1,71 @@
+//! Pretty terminal output of findings
+//!
+//! Findings are grouped by check and by file, and followed by a summary of the number of findings of each check.  Colour is only used when writing to a terminal, so none is written here.
+
+mod common;
+
+use common::{status, stdout, Project};
+
+#[test]
+fn findings_are_grouped_by_check_and_file() {
+    let project = Project::new("pretty-groups")
+        .file("document.tex", "See \\cite{nokey}.\n")
+        .file("references.bib", "@book{doe2019,\n  title = {A Book},\n}\n");
+    let output = project.run(&["check", "--unused", "--missing", "--format", "pretty"]);
+    assert_eq!(status(&output), 1);
+
+    let out = stdout(&output);
+    assert!(!out.contains('\u{1b}'), "coloured output: {out:?}");
+    let lines: Vec<&str> = out.lines().collect();
+    let unused = lines.iter().position(|l| l.starts_with("unused")).unwrap();
+    assert_eq!(lines[unused + 1], "  references.bib");
+    assert!(lines[unused + 2].contains("doe2019"));
+    let missing = lines.iter().position(|l| l.starts_with("missing")).unwrap();
+    assert_eq!(lines[missing + 1], "  document.tex");
+    assert!(lines[missing + 2].contains("1:11  nokey"));
+}
+
+/// Rows of the summary at the end of the output
+fn summary(out: &str) -> Vec<&str> {
+    out.lines()
+        .skip_while(|line| *line != "Summary")
+        .skip(1)
+        .filter(|line| !line.is_empty())
+        .collect()
+}
+
+#[test]
+fn summary_counts_are_aligned() {
+    let project = Project::new("pretty-summary")
+        .file(
+            "document.tex",
+            "\\usepackage{natbib}\n% citati-ignore-next-line missing\nSee \\citet{doe2019}.\n(see \\citep{doe2019}) \\citet{nokey}\n",
+        )
+        .file(
+            "references.bib",
+            "@book{doe2019,\n  title = {A Book},\n}\n@book{lee2018,\n  title = {B},\n}\n",
+        );
+    let checks = project.run(&["check", "--format", "pretty"]);
+    let checks = stdout(&checks);
+    let suppressions = project.run(&["check", "--unused-suppressions", "--format", "pretty"]);
+    let suppressions = stdout(&suppressions);
+    let rows: Vec<&str> = summary(&checks)
+        .into_iter()
+        .chain(summary(&suppressions))
+        .collect();
+    for check in ["unused", "missing", "cite-commands", "unused-suppression"] {
+        assert!(
+            rows.iter().any(|row| row.trim_start().starts_with(check)),
+            "no summary of {check}: {rows:?}"
+        );
+    }
+
+    // Counts start in the same column, after the longest check name
+    for row in rows {
+        assert_eq!(
+            row.find(char::is_numeric),
+            Some(2 + "unused-suppression".len() + 1),
+            "{row:?}"
+        );
+    }
+}
@@ -0,0 +1,138 @@
+//! Checking many documents against one bibliography
+//!
+//! Documents given as files, glob patterns, or directories are checked together, with files that name their root document with a magic comment checked through that root, and each document checked once however it is given.  Citations are merged across documents, so that an entry is unused only if no document cites it.
+
+mod common;
+
+use common::{status, stderr, stdout, Project};
+use serde_json::Value;
+
+const BIB: &str = "@book{smith2020,\n  title = {A},\n}\n@book{doe2019,\n  title = {B},\n}\n@book{lee2018,\n  title = {C},\n}\n";
+
+/// Thesis whose chapters name its root document, and a paper of its own, sharing one bibliography
+fn project(name: &str) -> Project {
+    Project::new(name)
+        .file(
+            "thesis/main.tex",
+            "\\documentclass{report}\n\\input{chapters/intro}\n\\cite{nokey}\n",
+        )
+        .file(
+            "thesis/chapters/intro.tex",
+            "% !TEX root = ../main.tex\n\\cite{smith2020}\n",
+        )
+        .file(
+            "thesis/chapters/notes.tex",
+            "% !TEX root = ../main.tex\n\\cite{lee2018}\n",
+        )
+        .file(
+            "paper/paper.tex",
+            "\\documentclass{article}\n\\cite{doe2019}\n",
+        )
+        .file("references.bib", BIB)
+}
+
+/// Findings of `check` with the given arguments, with the status of the run
+fn findings(project: &Project, args: &[&str]) -> (i32, Vec<Value>) {
+    let mut all = vec!["check", "--format", "json"];
+    all.extend(args);
+    let output = project.run(&all);
+    let document: Value = serde_json::from_str(&stdout(&output)).unwrap();
+    let findings = document["findings"].as_array().unwrap().clone();
+    (status(&output), findings)
+}
+
+/// Keys of the findings of a check, in order
+fn keys<'a>(findings: &'a [Value], check: &str) -> Vec<&'a str> {
+    findings
+        .iter()
+        .filter(|finding| finding["check"] == check)
+        .map(|finding| finding["key"].as_str().unwrap())
+        .collect()
+}
+
+#[test]
+fn citations_are_merged_across_documents() {
+    let project = project("project-merge");
+    let (status, found) = findings(
+        &project,
+        &["-f", "thesis/main.tex", "paper/paper.tex", "--unused"],
+    );
+    assert_eq!(status, 1);
+
+    // lee2018 is cited only in a chapter that the thesis does not include
+    assert_eq!(keys(&found, "unused"), ["lee2018"]);
+}
+
+#[test]
+fn root_documents_are_found_in_directories() {
+    let project = project("project-directory");
+    let (_, found) = findings(&project, &["-f", ".", "--unused", "--missing"]);
+    assert_eq!(keys(&found, "unused"), ["lee2018"]);
+    assert_eq!(keys(&found, "missing"), ["nokey"]);
+
+    // Files with magic comments are checked through their root, not as roots of their own
+    let locations = found[1]["locations"].as_array().unwrap();
+    assert_eq!(locations.len(), 1);
+    assert_eq!(locations[0]["file"], "thesis/main.tex");
+}
+
+#[test]
+fn glob_matches_are_checked_through_their_root_documents() {
+    let project = project("project-glob");
+    let output = project.run(&[
+        "check",
+        "-f",
+        "thesis/chapters/*.tex",
+        "--missing",
+        "--format",
+        "json",
+    ]);
+    assert!(
+        !stderr(&output).contains("Could not"),
+        "{}",
+        stderr(&output)
+    );
+    let document: Value = serde_json::from_str(&stdout(&output)).unwrap();
+    let found = document["findings"].as_array().unwrap();
+
+    // Both chapters have the same root, whose citations are found once
+    assert_eq!(keys(found, "missing"), ["nokey"]);
+    let locations = found[0]["locations"].as_array().unwrap();
+    assert_eq!(locations.len(), 1);
+    assert_eq!(locations[0]["file"], "thesis/main.tex");
+    assert_eq!(locations[0]["line"], 3);
+
+    // The chapters are not checked as documents of their own, so the
+    // citation in the chapter that is not included is still unused
+    let (_, found) = findings(&project, &["-f", "thesis/chapters/*.tex", "--unused"]);
+    assert_eq!(keys(&found, "unused"), ["doe2019", "lee2018"]);
+}
+
+#[test]
+fn documents_are_checked_once_however_they_are_given() {
+    let project = project("project-once");
+    let (_, found) = findings(
+        &project,
+        &[
+            "-f",
+            "thesis/main.tex",
+            "./thesis/main.tex",
+            "thesis/chapters/intro.tex",
+            "thesis/chapters/*.tex",
+            "thesis",
+            "--missing",
+        ],
+    );
+    assert_eq!(keys(&found, "missing"), ["nokey"]);
+    let locations = found[0]["locations"].as_array().unwrap();
+    assert_eq!(locations.len(), 1, "{locations:?}");
+    assert_eq!(locations[0]["file"], "thesis/main.tex");
+}
+
+#[test]
+fn patterns_matching_nothing_are_warned_about() {
+    let project = project("project-no-match");
+    let output = project.run(&["check", "-f", "*.md", "--missing"]);
+    assert_eq!(status(&output), 2);
+    assert!(stderr(&output).contains("No files match pattern \"*.md\""));
+}
@@ -0,0 +1,213 @@
+//! Citations within biblatex refsections and refsegments
+//!
+//! Refsections and refsegments are numbered across the whole document (including the files it inputs), and keys cited within a refsection are resolved against the bibliographies local to it.  A key that is defined is still missing if no bibliography printed for its refsection selects it, by refsegment, keyword, or type.  Control files do not record refsegments, so bibliographies of a refsegment are assumed to print any entry of their refsection where citations are read from one.
+
+mod common;
+
+use citati::source::latex::{Lexer, Token};
+use common::{status, stdout, Project};
+
+/// Keys cited by the document of the project, with the refsection and refsegment in which each is cited
+fn refs(project: &Project) -> Vec<(String, usize, usize)> {
+    let mut lexer = Lexer::from_path(&project.path("document.tex")).unwrap();
+    std::iter::from_fn(|| lexer.next_token().transpose())
+        .filter_map(|token| match token.unwrap() {
+            Token::Citation(citation) => {
+                Some((citation.key, citation.refsection, citation.refsegment))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Missing citations reported by `check --missing` as GitHub annotations, which give the location and message of each, with the status of the run
+fn missing(project: &Project, args: &[&str]) -> (i32, String) {
+    let mut all = vec!["check", "--missing", "--format", "github"];
+    all.extend(args);
+    let output = project.run(&all);
+    (status(&output), stdout(&output))
+}
+
+const BIB: &str = "@book{doe2019,\n  title = {A},\n  keywords = {primary},\n}\n@article{lee2018,\n  title = {B},\n  keywords = {secondary, review},\n}\n@book{kim2021,\n  title = {C},\n}\n";
+
+#[test]
+fn refsections_and_refsegments_are_numbered_across_the_document() {
+    let project = Project::new("refs-numbering")
+        .file(
+            "document.tex",
+            "\\cite{a}\n\\newrefsegment\\cite{b}\n\\begin{refsection}\n\\cite{c}\n\\input{chapter}\n\\end{refsection}\n\\cite{f}\n",
+        )
+        .file(
+            "chapter.tex",
+            "\\begin{refsegment}\\cite{d}\\end{refsegment}\n\\newrefsection \\newrefsegment \\cite{e}\n",
+        );
+    let found = refs(&project);
+    let found: Vec<(&str, usize, usize)> = found
+        .iter()
+        .map(|(key, section, segment)| (key.as_str(), *section, *segment))
+        .collect();
+    assert_eq!(
+        found,
+        [
+            ("a", 0, 0),
+            ("b", 0, 1),
+            ("c", 1, 0),
+            ("d", 1, 2),
+            ("e", 2, 3),
+            ("f", 0, 0),
+        ]
+    );
+}
+
+#[test]
+fn printed_bibliographies_are_read_with_their_options() {
+    let project = Project::new("refs-printed").file(
+        "document.tex",
+        "\\begin{refsection}\n\\printbibliography[heading=subbibliography, segment=2, keyword={primary}, nottype=article]\n\\end{refsection}\n\\printbibliography[section=1, category=cited]\n",
+    );
+    let mut lexer = Lexer::from_path(&project.path("document.tex")).unwrap();
+    let printed: Vec<_> = std::iter::from_fn(|| lexer.next_token().transpose())
+        .filter_map(|token| match token.unwrap() {
+            Token::Bibliography(printed) => Some(printed),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(printed.len(), 2);
+    assert_eq!(printed[0].refsection, 1);
+    assert_eq!(printed[0].refsegment, Some(2));
+    assert_eq!(printed[0].keyword.as_deref(), Some("primary"));
+    assert_eq!(printed[0].not_entry_type.as_deref(), Some("article"));
+    assert!(!printed[0].other_filters);
+    assert_eq!(printed[0].location.line, 2);
+    assert_eq!(printed[1].refsection, 1);
+    assert!(printed[1].other_filters);
+}
+
+#[test]
+fn keys_are_resolved_against_bibliographies_of_their_refsection() {
+    let project = Project::new("refs-local")
+        .file(
+            "document.tex",
+            "\\begin{refsection}[chapter2.bib]\n\\cite{doe2019, smith2020}\n\\end{refsection}\n\\begin{refsection}[chapter3.bib]\n\\cite{jones2022}\n\\end{refsection}\n",
+        )
+        .file("references.bib", BIB)
+        .file("chapter2.bib", "@book{smith2020,\n  title = {D},\n}\n")
+        .file("chapter3.bib", "@book{jones2022,\n  title = {E},\n}\n");
+    let (status, out) = missing(&project, &[]);
+    assert_eq!(status, 0, "{out}");
+
+    // An entry defined only for chapter 3 is missing from chapter 2
+    let project = project.file(
+        "document.tex",
+        "\\begin{refsection}[chapter2.bib]\n\\cite{jones2022}\n\\end{refsection}\n\\begin{refsection}[chapter3.bib]\n\\cite{jones2022}\n\\end{refsection}\n",
+    );
+    let (status, out) = missing(&project, &[]);
+    assert_eq!(status, 1);
+    assert!(out.contains("jones2022"), "{out}");
+    assert!(out.contains("file=document.tex,line=2,"), "{out}");
+    assert!(!out.contains("file=document.tex,line=5,"), "{out}");
+}
+
+#[test]
+fn entries_not_printed_by_keyword_are_missing() {
+    let project = Project::new("refs-keyword")
+        .file(
+            "document.tex",
+            "\\cite{doe2019, lee2018, kim2021}\n\\printbibliography[keyword=primary]\n\\printbibliography[keyword=secondary, type=article]\n",
+        )
+        .file("references.bib", BIB);
+    let (status, out) = missing(&project, &[]);
+    assert_eq!(status, 1);
+    assert!(out.contains("kim2021"), "{out}");
+    assert!(!out.contains("doe2019"), "{out}");
+    assert!(!out.contains("lee2018"), "{out}");
+
+    // A bibliography without filters prints the rest
+    let project = project.file(
+        "document.tex",
+        "\\cite{doe2019, lee2018, kim2021}\n\\printbibliography[keyword=primary]\n\\printbibliography[notkeyword=primary]\n",
+    );
+    let (status, out) = missing(&project, &[]);
+    assert_eq!(status, 0, "{out}");
+}
+
+#[test]
+fn entries_not_printed_by_refsegment_are_missing() {
+    let project = Project::new("refs-segment")
+        .file(
+            "document.tex",
+            "\\begin{refsection}\n\\newrefsegment \\cite{doe2019}\n\\newrefsegment \\cite{lee2018}\n\\printbibliography[segment=1]\n\\end{refsection}\n",
+        )
+        .file("references.bib", BIB);
+    let (status, out) = missing(&project, &[]);
+    assert_eq!(status, 1);
+    assert!(out.contains("lee2018"), "{out}");
+    assert!(!out.contains("doe2019"), "{out}");
+
+    // Refsections for which no bibliography is printed are not checked
+    let project = project.file(
+        "document.tex",
+        "\\begin{refsection}\n\\newrefsegment \\cite{lee2018}\n\\end{refsection}\n\\printbibliography[segment=1]\n",
+    );
+    let (status, out) = missing(&project, &[]);
+    assert_eq!(status, 0, "{out}");
+}
+
+/// Control file of a document with two refsections, the second with a bibliography of its own
+const BCF: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<bcf:controlfile xmlns:bcf="https://sourceforge.net/projects/biblatex">
+  <bcf:bibdata section="0">
+    <bcf:datasource type="file" datatype="bibtex">references.bib</bcf:datasource>
+  </bcf:bibdata>
+  <bcf:bibdata section="2">
+    <bcf:datasource type="file" datatype="bibtex">chapter3.bib</bcf:datasource>
+  </bcf:bibdata>
+  <bcf:section number="1">
+    <bcf:citekey order="1">doe2019</bcf:citekey>
+    <bcf:citekey order="2">jones2022</bcf:citekey>
+    <bcf:citekey order="3">lee2018</bcf:citekey>
+  </bcf:section>
+  <bcf:section number="2">
+    <bcf:citekey order="1">jones2022</bcf:citekey>
+  </bcf:section>
+</bcf:controlfile>
+"#;
+
+#[test]
+fn control_file_refsections_are_checked() {
+    let project = Project::new("refs-bcf")
+        .file("document.bcf", BCF)
+        .file("references.bib", BIB)
+        .file("chapter3.bib", "@book{jones2022,\n  title = {E},\n}\n");
+    let (status, out) = missing(&project, &["--bcf", "document.bcf"]);
+    assert_eq!(status, 1);
+    assert!(out.contains("jones2022"), "{out}");
+    assert!(out.contains("refsection 1"), "{out}");
+    assert!(!out.contains("refsection 2"), "{out}");
+}
+
+#[test]
+fn control_file_citations_are_filtered_by_printed_bibliographies() {
+    // The bibliographies printed are read from the LaTeX source
+    let project = Project::new("refs-bcf-printed")
+        .file("document.bcf", BCF)
+        .file(
+            "document.tex",
+            "\\begin{refsection}\n\\newrefsegment \\cite{doe2019, jones2022}\n\\newrefsegment \\cite{lee2018}\n\\printbibliography[segment=1, keyword=primary]\n\\end{refsection}\n",
+        )
+        .file("references.bib", BIB)
+        .file("chapter3.bib", "@book{jones2022,\n  title = {E},\n}\n");
+    let (status, out) = missing(&project, &["--bcf", "document.bcf"]);
+    assert_eq!(status, 1);
+    assert!(out.contains("lee2018 is cited but not printed"), "{out}");
+    assert!(!out.contains("doe2019"), "{out}");
+
+    // Refsegments are not known, so a bibliography of one prints entries
+    // cited anywhere in its refsection
+    let project = project.file(
+        "document.tex",
+        "\\begin{refsection}\n\\newrefsegment \\cite{doe2019, jones2022}\n\\newrefsegment \\cite{lee2018}\n\\printbibliography[segment=1]\n\\end{refsection}\n",
+    );
+    let (_, out) = missing(&project, &["--bcf", "document.bcf"]);
+    assert!(!out.contains("not printed"), "{out}");
+}
@@ -0,0 +1,125 @@
+//! Citations read from reStructuredText documents, and what is not a citation
+//!
+//! Keys are cited with the roles of sphinxcontrib-bibtex, whose pre- and post-notes are not keys, and every entry is cited by a `bibliography` directive with the `:all:` option, but nothing within comments, literal blocks, or inline literals is cited.
+
+mod common;
+
+use citati::source::{latex::Token, rst};
+use common::{status, stdout, Project};
+use std::path::Path;
+
+/// Keys cited in the source, in order, with the citation command (the role) of each
+fn citations(src: &str) -> Vec<(String, String)> {
+    rst::parse_rst_from_str(src, Path::new("index.rst"))
+        .into_iter()
+        .filter_map(|token| match token {
+            Token::Citation(citation) => Some((citation.key, citation.cite_cmd)),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Keys cited in the source, in order
+fn keys(src: &str) -> Vec<String> {
+    citations(src).into_iter().map(|(key, _)| key).collect()
+}
+
+#[test]
+fn roles_cite_each_key() {
+    let found = citations(
+        "As :cite:t:`smith2020` shows :cite:p:`doe2019, lee2018`, see also :footcite:`kim2021`.\n",
+    );
+    let found: Vec<(&str, &str)> = found
+        .iter()
+        .map(|(key, role)| (key.as_str(), role.as_str()))
+        .collect();
+    assert_eq!(
+        found,
+        [
+            ("smith2020", "cite:t"),
+            ("doe2019", "cite:p"),
+            ("lee2018", "cite:p"),
+            ("kim2021", "footcite"),
+        ]
+    );
+}
+
+#[test]
+fn notes_are_not_keys() {
+    assert_eq!(
+        keys(":cite:p:`{see}smith2020{p. 4}{compare}doe2019{ch. 2}`\n"),
+        ["smith2020", "doe2019"]
+    );
+}
+
+#[test]
+fn bibliography_of_all_entries_cites_every_entry() {
+    assert_eq!(
+        keys(".. bibliography::\n   :all:\n   :style: plain\n"),
+        ["*"]
+    );
+    assert!(keys(".. bibliography::\n   :style: plain\n").is_empty());
+}
+
+#[test]
+fn comments_are_not_cited() {
+    assert_eq!(
+        keys(".. :cite:`doe2019`\n   :cite:`lee2018`\n\n:cite:`smith2020`\n"),
+        ["smith2020"]
+    );
+}
+
+#[test]
+fn literal_blocks_are_not_cited() {
+    assert_eq!(
+        keys("Example::\n\n    :cite:`doe2019`\n\n:cite:`smith2020`\n"),
+        ["smith2020"]
+    );
+    assert_eq!(
+        keys(".. code-block:: rst\n\n   :cite:`doe2019`\n\n:cite:`smith2020`\n"),
+        ["smith2020"]
+    );
+}
+
+#[test]
+fn inline_literals_are_not_cited() {
+    assert_eq!(
+        keys("Write ``:cite:`doe2019` `` for :cite:`smith2020`\n"),
+        ["smith2020"]
+    );
+}
+
+#[test]
+fn rst_documents_are_checked() {
+    let project = Project::new("rst-check")
+        .file(
+            "index.rst",
+            "See :cite:p:`smith2020,nokey`.\n\n.. :cite:`lee2018`\n",
+        )
+        .file(
+            "references.bib",
+            "@book{smith2020,\n  title = {A},\n}\n@book{lee2018,\n  title = {C},\n}\n",
+        );
+    let output = project.run(&[
+        "check",
+        "-f",
+        "index.rst",
+        "--unused",
+        "--missing",
+        "--format",
+        "plain",
+    ]);
+    assert_eq!(status(&output), 1);
+    let out = stdout(&output);
+    let mut keys: Vec<&str> = out.lines().collect();
+    keys.sort();
+    assert_eq!(keys, ["lee2018", "nokey"]);
+
+    // A bibliography of all entries uses every entry
+    let project = project.file(
+        "index.rst",
+        "See :cite:`smith2020`.\n\n.. bibliography::\n   :all:\n",
+    );
+    let output = project.run(&["check", "-f", "index.rst", "--unused", "--format", "plain"]);
+    assert_eq!(status(&output), 0, "{}", stdout(&output));
+}
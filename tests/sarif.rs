@@ -0,0 +1,79 @@
+//! SARIF output of findings
+//!
+//! The log has one run, whose driver describes every check as a rule, and whose results point into the LaTeX source and bib file with physical locations.
+
+mod common;
+
+use common::{status, stdout, Project};
+use serde_json::{json, Value};
+
+fn sarif(project: &Project, args: &[&str]) -> Value {
+    let mut args = args.to_vec();
+    args.extend(["--format", "sarif"]);
+    let output = project.run(&args);
+    assert_eq!(status(&output), 1);
+    serde_json::from_str(&stdout(&output)).unwrap()
+}
+
+#[test]
+fn every_check_is_a_rule() {
+    let project = Project::new("sarif-rules")
+        .file("document.tex", "See \\cite{nokey}.\n")
+        .file("references.bib", "");
+    let log = sarif(&project, &["check", "--missing"]);
+    assert_eq!(log["version"], "2.1.0");
+
+    let driver = &log["runs"][0]["tool"]["driver"];
+    assert_eq!(driver["name"], "citati");
+    let rules: Vec<&str> = driver["rules"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|rule| rule["id"].as_str().unwrap())
+        .collect();
+    for check in ["unused", "missing", "pages", "articles", "cite-commands"] {
+        assert!(rules.contains(&check), "no rule for {check}");
+    }
+}
+
+#[test]
+fn results_point_into_the_latex_source_and_bib_file() {
+    let project = Project::new("sarif-results")
+        .file("document.tex", "Text\nSee \\cite{nokey}.\n")
+        .file(
+            "references.bib",
+            "\n@book{doe2019,\n  title = {A Book},\n}\n",
+        );
+    let log = sarif(&project, &["check", "--missing", "--unused"]);
+    let run = &log["runs"][0];
+    let results = run["results"].as_array().unwrap();
+    assert_eq!(results.len(), 2);
+
+    let rules = run["tool"]["driver"]["rules"].as_array().unwrap();
+    for result in results {
+        let index = result["ruleIndex"].as_u64().unwrap() as usize;
+        assert_eq!(rules[index]["id"], result["ruleId"]);
+    }
+
+    let location = |result: &Value| result["locations"][0]["physicalLocation"].clone();
+    let unused = results.iter().find(|r| r["ruleId"] == "unused").unwrap();
+    assert_eq!(unused["level"], "warning");
+    assert_eq!(
+        location(unused),
+        json!({
+            "artifactLocation": { "uri": "references.bib" },
+            "region": { "startLine": 2, "startColumn": 1 },
+        })
+    );
+
+    let missing = results.iter().find(|r| r["ruleId"] == "missing").unwrap();
+    assert_eq!(missing["level"], "error");
+    assert_eq!(missing["properties"]["key"], "nokey");
+    assert_eq!(
+        location(missing),
+        json!({
+            "artifactLocation": { "uri": "document.tex" },
+            "region": { "startLine": 2, "startColumn": 11 },
+        })
+    );
+}
@@ -0,0 +1,82 @@
+//! Citation statistics
+//!
+//! `citati stats` counts the citations of each key and the works cited by type, year, decade, and journal, written as tables or as JSON, and rejects formats of `check` that it cannot write.
+
+mod common;
+
+use common::{status, stderr, stdout, Project};
+use serde_json::{json, Value};
+
+/// Document citing one work twice, another once, and a key not in the bibliography
+fn project(name: &str) -> Project {
+    Project::new(name)
+        .file(
+            "document.tex",
+            "\\cite{smith2020, doe2019}\n\\input{chapter}\n\\cite{nokey}\n",
+        )
+        .file("chapter.tex", "\\cite{smith2020}\n")
+        .file(
+            "references.bib",
+            "@article{smith2020,\n  title = {A},\n  journal = {Journal of Foo},\n  year = {2020},\n}\n@book{doe2019,\n  title = {B},\n  date = {2019-05},\n}\n@book{lee2018,\n  title = {C},\n}\n",
+        )
+}
+
+#[test]
+fn statistics_are_written_as_json() {
+    let project = project("stats-json");
+    let output = project.run(&["stats", "--format", "json"]);
+    assert_eq!(status(&output), 0);
+    let stats: Value = serde_json::from_str(&stdout(&output)).unwrap();
+    assert_eq!(stats["citations"], 4);
+    assert_eq!(stats["distinct"], 3);
+    assert_eq!(stats["undefined"], 1);
+    assert_eq!(
+        stats["keys"][0],
+        json!({
+            "key": "smith2020",
+            "count": 2,
+            "files": ["document.tex", "chapter.tex"],
+            "defined": true,
+        })
+    );
+    assert_eq!(stats["keys"][2]["key"], "nokey");
+    assert_eq!(stats["keys"][2]["defined"], false);
+    assert_eq!(stats["by_type"], json!({ "article": 1, "book": 1 }));
+    assert_eq!(stats["by_year"], json!({ "2019": 1, "2020": 1 }));
+    assert_eq!(stats["by_decade"], json!({ "2010s": 1, "2020s": 1 }));
+    assert_eq!(stats["by_journal"], json!({ "Journal of Foo": 1 }));
+}
+
+#[test]
+fn statistics_are_written_as_tables() {
+    let project = project("stats-plain");
+    let plain = stdout(&project.run(&["stats"]));
+    assert!(plain.starts_with("4 citations of 3 distinct works (1 not in the bibliography)\n"));
+    assert!(
+        plain.contains("nokey         1  document.tex (undefined)"),
+        "{plain}"
+    );
+
+    // Pretty is the same as plain
+    let pretty = stdout(&project.run(&["stats", "--format", "pretty"]));
+    assert_eq!(pretty, plain);
+
+    // The number of most-cited works listed can be limited
+    let top = stdout(&project.run(&["stats", "--top", "1"]));
+    let most_cited = top.split("\n\n").nth(1).unwrap();
+    assert_eq!(most_cited, "Most cited\n     2  smith2020");
+}
+
+#[test]
+fn formats_of_check_are_rejected() {
+    let project = project("stats-formats");
+    for format in ["ndjson", "sarif", "github", "gitlab"] {
+        let output = project.run(&["stats", "--format", format]);
+        assert_eq!(status(&output), 2);
+        assert!(
+            stderr(&output).contains("invalid value"),
+            "{}",
+            stderr(&output)
+        );
+    }
+}
@@ -0,0 +1,84 @@
+//! Suppression directives in the LaTeX source and the bib file
+//!
+//! Checks of the bib file alone do not require the LaTeX source, whose directives are only respected where it exists.  Entries whose findings are suppressed are not fixed by `--fix`.  An escaped `\%` does not start a comment, and so neither does a directive.
+
+mod common;
+
+use common::{status, stderr, stdout, Project};
+
+const BIB: &str = "@book{doe2019,\n  title = {A Book},\n  pages = {12-34},\n}\n";
+
+#[test]
+fn bib_checks_do_not_require_latex_source() {
+    let project = Project::new("suppress-bib-only").file("references.bib", BIB);
+    for args in [&["check", "-p"][..], &["check", "-a"], &["-p"]] {
+        let output = project.run(args);
+        assert_ne!(status(&output), 2, "{args:?}: {}", stderr(&output));
+        assert!(!stderr(&output).contains("document.tex"), "{args:?}");
+    }
+    assert!(stdout(&project.run(&["check", "-p"])).contains("doe2019"));
+}
+
+#[test]
+fn bib_directives_apply_without_latex_source() {
+    let project = Project::new("suppress-bib-only-directive")
+        .file("references.bib", &format!("% citati-ignore: pages\n{BIB}"));
+    let output = project.run(&["check", "-p"]);
+    assert_eq!(status(&output), 0, "{}", stdout(&output));
+}
+
+#[test]
+fn latex_directives_apply_to_bib_checks() {
+    let project = Project::new("suppress-latex-directive")
+        .file("document.tex", "% citati-disable pages\n\\cite{doe2019}\n")
+        .file("references.bib", BIB);
+    let output = project.run(&["check", "-p"]);
+    assert_eq!(status(&output), 0, "{}", stdout(&output));
+}
+
+#[test]
+fn escaped_percent_signs_do_not_start_comments() {
+    let project = Project::new("suppress-escaped-percent")
+        .file(
+            "document.tex",
+            "In 50\\% of cases % citati-ignore-next-line missing\n\\cite{nokey}\n",
+        )
+        .file("references.bib", BIB);
+    let output = project.run(&["check", "--missing"]);
+    assert_eq!(status(&output), 0, "{}", stdout(&output));
+
+    // Citations after an escaped percent sign are still read
+    let project = Project::new("suppress-escaped-percent-cite")
+        .file("document.tex", "In 50\\% of cases \\cite{nokey}\n")
+        .file("references.bib", BIB);
+    let output = project.run(&["check", "--missing"]);
+    assert_eq!(status(&output), 1);
+    assert!(stdout(&output).contains("nokey"));
+}
+
+#[test]
+fn suppressed_pages_are_not_fixed() {
+    let bib = format!(
+        "% citati-ignore: pages\n{BIB}@book{{lee2018,\n  title = {{B}},\n  pages = {{1-2}},\n}}\n"
+    );
+    let project = Project::new("suppress-fix").file("references.bib", &bib);
+    let output = project.run(&["check", "-p", "--fix"]);
+    assert_eq!(status(&output), 0, "{}", stderr(&output));
+    let fixed = project.read("references.bib");
+    assert!(fixed.contains("pages = {12-34}"), "{fixed}");
+    assert!(fixed.contains("pages = {1--2}"), "{fixed}");
+
+    // Nor within a region in which the check is disabled
+    let project = Project::new("suppress-fix-region")
+        .file("document.tex", "% citati-disable pages\n\\cite{doe2019}\n")
+        .file("references.bib", BIB);
+    let output = project.run(&["check", "-p", "--fix", "--dry-run"]);
+    assert_eq!(status(&output), 0, "{}", stdout(&output));
+    assert_eq!(project.read("references.bib"), BIB);
+
+    let bib = format!("% citati-disable pages\n{BIB}");
+    let project = Project::new("suppress-fix-bib-region").file("references.bib", &bib);
+    let output = project.run(&["check", "-p", "--fix"]);
+    assert_eq!(status(&output), 0, "{}", stderr(&output));
+    assert_eq!(project.read("references.bib"), bib);
+}
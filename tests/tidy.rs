@@ -0,0 +1,177 @@
+//! Formatting of bibliography source
+//!
+//! Formatting must keep every entry, field, and comment of the source, and formatting the output again must not change it.
+
+use citati::{
+    source::bib::cst::Document,
+    tidy::{default_field_order, format_document, Delimiters, Options, SortKey},
+};
+
+fn options() -> Options {
+    Options {
+        indent: 2,
+        align: true,
+        delimiters: Delimiters::Braces,
+        sort: Vec::new(),
+        field_orders: Vec::new(),
+        remove_empty_fields: true,
+    }
+}
+
+/// Format the source, checking that formatting the output again leaves it unchanged
+fn format(src: &str, options: &Options) -> String {
+    let document = Document::parse(src.to_owned()).unwrap();
+    let formatted = format_document(&document, options).unwrap();
+    let again = format_document(&formatted, options).unwrap();
+    assert_eq!(again.src(), formatted.src(), "formatting is not idempotent");
+    formatted.src().to_owned()
+}
+
+#[test]
+fn aligns_values() {
+    let src = "@Article{smith2020, Title={A}, JOURNAL={B}, year=2020}\n";
+    assert_eq!(
+        format(src, &options()),
+        "@article{smith2020,\n  title   = {A},\n  journal = {B},\n  year    = 2020,\n}\n"
+    );
+
+    let options = Options {
+        align: false,
+        indent: 4,
+        ..options()
+    };
+    assert_eq!(
+        format(src, &options),
+        "@article{smith2020,\n    title = {A},\n    journal = {B},\n    year = 2020,\n}\n"
+    );
+}
+
+#[test]
+fn converts_quotes_to_braces() {
+    let src = "@book{doe2019, title = \"A {B} C\", note = \"x\" # \" y\"}\n";
+    let formatted = format(src, &options());
+    assert!(formatted.contains("title = {A {B} C},"), "{formatted}");
+
+    // Concatenations cannot be converted to a single delimited value
+    assert!(formatted.contains("note  = \"x\" # \" y\","), "{formatted}");
+}
+
+#[test]
+fn converts_braces_to_quotes() {
+    let src = "@book{doe2019, title = {A {B} C}, note = {Say \"hi\"}}\n";
+    let options = Options {
+        delimiters: Delimiters::Quotes,
+        ..options()
+    };
+    let formatted = format(src, &options);
+    assert!(formatted.contains("title = \"A {B} C\","), "{formatted}");
+
+    // Values containing quotes keep their braces
+    assert!(formatted.contains("note  = {Say \"hi\"},"), "{formatted}");
+}
+
+#[test]
+fn sorts_entries() {
+    let src = "@string{j = {J}}\n\
+               @misc{c, year = {2001}}\n\
+               @misc{a, year = {2003}}\n\
+               @misc{b, year = {2002}}\n\
+               % Trailing text\n";
+    let entries = |formatted: &str| -> Vec<String> {
+        let document = Document::parse(formatted.to_owned()).unwrap();
+        document
+            .entries()
+            .iter()
+            .map(|entry| document.key(entry).to_owned())
+            .collect()
+    };
+
+    let by_key = format(
+        src,
+        &Options {
+            sort: vec![SortKey::Key],
+            ..options()
+        },
+    );
+    assert_eq!(entries(&by_key), ["a", "b", "c"]);
+    assert!(by_key.starts_with("@string{j = {J}}\n"), "{by_key}");
+    assert!(by_key.ends_with("% Trailing text\n"), "{by_key}");
+
+    let by_year = format(
+        src,
+        &Options {
+            sort: vec![SortKey::Year],
+            ..options()
+        },
+    );
+    assert_eq!(entries(&by_year), ["c", "b", "a"]);
+}
+
+#[test]
+fn sorts_fields() {
+    let src = "@article{a, year = {2020}, journal = {J}, author = {A}, title = {T}, zzz = {Z}}\n";
+    let options = Options {
+        field_orders: vec![default_field_order()],
+        ..options()
+    };
+    let formatted = format(src, &options);
+    let names: Vec<&str> = formatted
+        .lines()
+        .skip(1)
+        .filter_map(|line| line.split_whitespace().next())
+        .filter(|name| *name != "}")
+        .collect();
+    assert_eq!(names, ["title", "author", "year", "journal", "zzz"]);
+}
+
+#[test]
+fn keeps_comments_and_special_blocks() {
+    let src = "% Header\n\
+               @preamble{\"\\newcommand{\\x}{y}\"}\n\
+               @article{a,\n\
+               % citati-ignore-next-line\n\
+               title = {T}, % why\n\
+               year = {2020}\n\
+               }\n\
+               @comment{kept as is}\n";
+    let formatted = format(src, &options());
+    for text in [
+        "% Header",
+        "@preamble{\"\\newcommand{\\x}{y}\"}",
+        "% citati-ignore-next-line",
+        "% why",
+        "@comment{kept as is}",
+    ] {
+        assert!(
+            formatted.contains(text),
+            "{text:?} missing from {formatted}"
+        );
+    }
+}
+
+#[test]
+fn removes_empty_fields() {
+    let src = "@misc{a, title = {T}, note = {}, year = \"\"}\n";
+    assert_eq!(format(src, &options()), "@misc{a,\n  title = {T},\n}\n");
+
+    let options = Options {
+        remove_empty_fields: false,
+        ..options()
+    };
+    assert!(format(src, &options).contains("note  = {},"));
+}
+
+#[test]
+fn preserves_crlf() {
+    let src = "@misc{a,\r\n title = {T}}\r\n";
+    assert_eq!(
+        format(src, &options()),
+        "@misc{a,\r\n  title = {T},\r\n}\r\n"
+    );
+}
+
+#[test]
+fn formatted_source_is_unchanged() {
+    let src = "@article{smith2020,\n  title   = {A},\n  journal = {B},\n}\n";
+    assert_eq!(format(src, &options()), src);
+}
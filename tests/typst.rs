@@ -0,0 +1,92 @@
+//! Citations read from Typst source, and what is not a citation
+//!
+//! Nothing within comments or raw text is a citation, nor is a reference to a label defined in the document, and source that ends within a comment or raw text (possibly with a multibyte character) must still be read to its end.
+
+use citati::source::{latex::Token, typst};
+use std::path::Path;
+
+/// Keys cited in the source, in order
+fn keys(src: &str) -> Vec<String> {
+    typst::parse_typst_from_str(src, Path::new("paper.typ"))
+        .unwrap()
+        .tokens
+        .into_iter()
+        .filter_map(|token| match token {
+            Token::Citation(citation) => Some(citation.key),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn block_comments_are_not_cited() {
+    assert_eq!(keys("/* @doe2019 */ @smith2020\n"), ["smith2020"]);
+    assert_eq!(
+        keys("/* outer /* @doe2019 */ @lee2018 */ @smith2020\n"),
+        ["smith2020"]
+    );
+    assert_eq!(keys("/**/ @smith2020\n"), ["smith2020"]);
+}
+
+#[test]
+fn unterminated_block_comments_run_to_the_end() {
+    assert!(keys("hello /* unterminated é").is_empty());
+    assert!(keys("hello /* unterminated €").is_empty());
+    assert_eq!(keys("@smith2020 /* @doe2019"), ["smith2020"]);
+    assert!(keys("/*/").is_empty());
+    assert!(keys("/* /* nested */ é").is_empty());
+}
+
+#[test]
+fn line_comments_are_not_cited() {
+    assert_eq!(keys("// @doe2019\n@smith2020\n"), ["smith2020"]);
+    assert!(keys("// @doe2019 é").is_empty());
+}
+
+#[test]
+fn urls_are_not_comments() {
+    assert_eq!(keys("See https://example.com @smith2020\n"), ["smith2020"]);
+}
+
+#[test]
+fn raw_text_is_not_cited() {
+    assert_eq!(keys("`@doe2019` @smith2020\n"), ["smith2020"]);
+    assert_eq!(keys("```\n@doe2019\n``` @smith2020\n"), ["smith2020"]);
+    assert_eq!(keys("```` ``` @doe2019 ```` @smith2020\n"), ["smith2020"]);
+    assert_eq!(keys("`` @smith2020\n"), ["smith2020"]);
+}
+
+#[test]
+fn unterminated_raw_text_runs_to_the_end() {
+    assert!(keys("`@doe2019 é").is_empty());
+    assert!(keys("```\n@doe2019\né").is_empty());
+}
+
+#[test]
+fn references_to_labels_are_not_cited() {
+    let src = "See @fig:setup and @smith2020.\n#figure[] <fig:setup>\n";
+    assert_eq!(keys(src), ["smith2020"]);
+    assert_eq!(
+        keys("@müller2020 and @réf\n= Intro <réf>\n"),
+        ["müller2020"]
+    );
+}
+
+#[test]
+fn references_end_before_trailing_punctuation() {
+    assert_eq!(
+        keys("As in @smith2020: see @doe2019.\n"),
+        ["smith2020", "doe2019"]
+    );
+}
+
+#[test]
+fn email_addresses_are_not_cited() {
+    assert!(keys("Write to jane@example.com\n").is_empty());
+}
+
+#[test]
+fn cite_function_cites_labels() {
+    let src = "#cite(<smith2020>) and #cite(label(\"doe2019\"))\n";
+    assert_eq!(keys(src), ["smith2020", "doe2019"]);
+}